@@ -1,25 +1,36 @@
 use std::{
+    collections::VecDeque,
     io,
     io::Write,
     sync::{mpsc, Arc},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    backend::Backend,
+    askpass,
+    backend::{Backend, Operation},
+    config::Config,
+    messages::RECORDING,
     mode::*,
-    platform::{Key, Platform, PlatformEventReader},
+    platform::{self, Key, Platform, PlatformEventReader},
+    session,
     tool::*,
-    ui::Drawer,
+    ui::{self, screen::Screen, Drawer},
 };
 
+#[cfg(feature = "testing")]
+pub mod headless;
+
+pub type OnEditorExit = fn(&ModeContext, Result<(), String>);
+
 enum Event {
     Key(Key),
     Resize(u16, u16),
     Response(ModeResponse),
     ModeChange(ModeKind, ModeChangeInfo),
     ModeRevert,
+    Editor(String, usize, OnEditorExit), // path, line
 }
 
 #[derive(Clone)]
@@ -36,19 +47,49 @@ impl EventSender {
     pub fn send_mode_revert(&self) {
         self.0.send(Event::ModeRevert).unwrap();
     }
+
+    // the editor takes over the terminal, so it is run from the main loop instead of a mode thread
+    pub fn send_editor(&self, path: String, line: usize, on_exit: OnEditorExit) {
+        self.0.send(Event::Editor(path, line, on_exit)).unwrap();
+    }
 }
 
 #[derive(Default)]
 struct Application {
     mode: ModeBuf,
     spinner_state: u8,
+    recording: Option<Vec<Key>>, // the keys pressed since [q] started recording
+    recorded: Vec<Key>,          // what [@] replays
+    replaying: VecDeque<Key>,
+    operation: Option<Operation>, // checked once the backend answered, not on every frame
 }
 impl Application {
+    pub fn start(&mut self, ctx: &ModeContext, mode_kind: ModeKind, info: ModeChangeInfo) {
+        self.mode.start(ctx, mode_kind, info);
+        self.operation = ctx.backend.operation();
+    }
+
     pub fn current_mode(&mut self) -> &mut dyn ModeTrait {
         self.mode.mode()
     }
 
     pub fn on_key(&mut self, ctx: &ModeContext, key: Key) -> bool {
+        if let Some(keys) = &mut self.recording {
+            keys.push(key);
+        }
+        if !self.current_mode().has_text_focus() && is_key_disabled(ctx, &self.mode.mode_kind(), key) {
+            return true;
+        }
+
+        // custom actions from the config take over the keys they are bound to
+        if !self.current_mode().has_text_focus() {
+            let mode_kind = self.mode.mode_kind();
+            if let Some(command) = custom_action(&ctx.config, &mode_kind, self.current_mode(), key) {
+                self.mode.enter_mode(ctx, ModeKind::Command, ModeChangeInfo::command(mode_kind, command));
+                return true;
+            }
+        }
+
         let status = self.current_mode().on_key(ctx, key);
 
         if !status.pending_input {
@@ -61,16 +102,37 @@ impl Application {
                 return true;
             }
 
+            match key {
+                Key::Char('q') => match self.recording.take() {
+                    Some(mut keys) => {
+                        keys.pop();
+                        self.recorded = keys;
+                    }
+                    None => self.recording = Some(Vec::new()),
+                },
+                // replaying while recording would record the replayed keys twice
+                Key::Char('@') => match &mut self.recording {
+                    Some(keys) => {
+                        keys.pop();
+                    }
+                    None => self.replaying.extend(&self.recorded),
+                },
+                _ => (),
+            }
+
             let target_mode_kind = match key {
                 Key::Char('s') => Some(ModeKind::Status),
                 Key::Char('l') => Some(ModeKind::Log),
                 Key::Char('b') => Some(ModeKind::Branches),
                 Key::Char('t') => Some(ModeKind::Tags),
                 Key::Char('S') => Some(ModeKind::Stash),
+                Key::Char('M') => Some(ModeKind::Maintenance),
+                Key::Char('G') => Some(ModeKind::Grep),
+                Key::Ctrl('z') => Some(ModeKind::Undo),
                 _ => None,
             };
 
-            if let Some(target_mode_kind) = target_mode_kind {
+            if let Some(target_mode_kind) = target_mode_kind.filter(|_| !is_global_key_disabled(ctx, key)) {
                 self.mode.enter_mode(ctx, target_mode_kind, ModeChangeInfo::new(self.mode.mode_kind()));
             }
         }
@@ -79,6 +141,8 @@ impl Application {
     }
 
     pub fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        // whatever the backend did, like a rebase stopping on conflicts, was done before it answered
+        self.operation = ctx.backend.operation();
         if response.mode_kind() == self.mode.mode_kind() {
             //log(format!("kind same, {:?}\n", self.mode.mode_kind()));
             self.current_mode().on_response(ctx, response);
@@ -91,7 +155,19 @@ impl Application {
         self.current_mode().is_waiting_response()
     }
 
-    pub fn draw_header(&mut self, drawer: &mut Drawer) {
+    pub fn is_replaying(&self) -> bool {
+        !self.replaying.is_empty()
+    }
+
+    // one at a time, each once the previous one is done with, as the modes answer keys from their threads
+    pub fn next_replayed_key(&mut self) -> Option<Key> {
+        if self.is_waiting_response() {
+            return None;
+        }
+        self.replaying.pop_front()
+    }
+
+    pub fn draw_header(&mut self, ctx: &ModeContext, drawer: &mut Drawer) {
         let spinner = [b'-', b'\\', b'|', b'/'];
         self.spinner_state = (self.spinner_state + 1) % spinner.len() as u8;
         let spinner = match self.is_waiting_response() {
@@ -99,8 +175,31 @@ impl Application {
             false => b' ',
         };
 
+        let mode_kind = self.mode.mode_kind();
+        let recording = self.recording.is_some();
+        let messages = ctx.config.messages();
+        let operation = self.operation;
         let (mode_name, left_help, right_help) = self.current_mode().header();
-        drawer.header(mode_name, left_help, right_help, spinner);
+        // a stopped rebase or merge is easy to forget about, so it is shown whatever the mode
+        let mut mode_name = match operation {
+            Some(operation) => {
+                let in_progress = format!("{} in progress", operation.as_str());
+                format!("{} | {}", messages.get(mode_name), messages.get(&in_progress))
+            }
+            None => messages.get(mode_name).to_owned(),
+        };
+        if recording {
+            mode_name.push_str(" | ");
+            mode_name.push_str(messages.get(RECORDING));
+        }
+        drawer.header(
+            &mode_name,
+            left_help,
+            right_help,
+            spinner,
+            |label| key_from_help_label(label).map(|key| is_global_key_disabled(ctx, key)).unwrap_or(false),
+            |label| key_from_help_label(label).map(|key| is_key_disabled(ctx, &mode_kind, key)).unwrap_or(false),
+        );
     }
 
     pub fn draw_body(&mut self, drawer: &mut Drawer) {
@@ -134,33 +233,91 @@ fn terminal_event_loop(mut event_reader: PlatformEventReader, sender: mpsc::Send
     }
 }
 
-pub fn run(platform_event_reader: PlatformEventReader, backend: Arc<dyn Backend>) {
+fn run_editor<W: Write>(platform: &Platform, stdout: &mut W, path: &str, line: usize) -> Result<(), String> {
+    stdout.write_all(ui::RESET_STYLE_CODE).unwrap();
+    stdout.write_all(ui::SHOW_CURSOR_CODE).unwrap();
+    stdout.write_all(ui::EXIT_ALTERNATE_BUFFER_CODE).unwrap();
+    stdout.flush().unwrap();
+    platform.suspend();
+
+    let result = platform::open_editor(path, line);
+
+    platform.resume();
+    stdout.write_all(ui::ENTER_ALTERNATE_BUFFER_CODE).unwrap();
+    stdout.write_all(ui::HIDE_CURSOR_CODE).unwrap();
+    stdout.flush().unwrap();
+    result
+}
+
+pub fn run(
+    platform: &Platform,
+    platform_event_reader: PlatformEventReader,
+    backend: Arc<dyn Backend>,
+    config: Config,
+    start_mode: (ModeKind, ModeChangeInfo),
+) {
     let (event_sender, event_receiver) = mpsc::channel();
 
-    let mut ctx =
-        ModeContext { backend, event_sender: EventSender(event_sender.clone()), viewport_size: Platform::terminal_size() };
+    let mut ctx = ModeContext {
+        backend,
+        config: Arc::new(config),
+        event_sender: EventSender(event_sender.clone()),
+        viewport_size: Platform::terminal_size(),
+        prefetched: Arc::default(),
+    };
+    // started before any git process, which inherit the askpass program from the environment
+    let _askpass = askpass::Server::start(&ctx.event_sender);
 
     let _ = thread::spawn(move || {
         terminal_event_loop(platform_event_reader, event_sender);
     });
 
     let mut application = Application::default();
-    application.mode.enter_mode(&ctx, ModeKind::default(), ModeChangeInfo::new(ModeKind::default()));
+    let (start_mode_kind, start_info) = start_mode;
+    application.start(&ctx, start_mode_kind, start_info);
+
+    // a missing identity or a git too old are pointed out before operations fail on them, going back continues
+    let needs_setup = ctx.config.get_bool("setup.check", true)
+        && ctx.backend.setup_info().map(|info| setup::needs_setup(&info)).unwrap_or(false);
+    if needs_setup {
+        let from = application.mode.mode_kind();
+        application.mode.enter_mode(&ctx, ModeKind::Setup, ModeChangeInfo::new(from));
+    }
 
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
     let mut stdout_buf = Vec::new();
+    let mut screen = Screen::default();
+    let mut screen_buf = Vec::new();
 
     const TIMEOUT: Duration = Duration::from_millis(100);
+    const DEFAULT_MAX_FPS: u32 = 60;
+    const REPLAY_DELAY: Duration = Duration::from_millis(50);
+
+    let max_fps = ctx.config.get("ui.max_fps").and_then(|f| f.parse().ok()).filter(|&f| f > 0);
+    let frame_interval = Duration::from_secs(1) / max_fps.unwrap_or(DEFAULT_MAX_FPS);
+    let mut next_frame = Instant::now();
+    let mut draw_body = false; // something changed since the last frame
 
     loop {
-        let event = if application.is_waiting_response() {
+        let event = if application.is_replaying() {
+            // the threads a replayed key starts without waiting for them, like getting a note to edit,
+            // answer before the next key goes in
+            match event_receiver.recv_timeout(REPLAY_DELAY) {
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    application.next_replayed_key().map(Event::Key).ok_or(mpsc::RecvTimeoutError::Timeout)
+                }
+                event => event,
+            }
+        } else if draw_body {
+            event_receiver.recv_timeout(next_frame.saturating_duration_since(Instant::now()))
+        } else if application.is_waiting_response() {
             event_receiver.recv_timeout(TIMEOUT)
         } else {
             event_receiver.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected)
         };
 
-        let mut draw_body = true;
+        draw_body |= event.is_ok();
 
         match event {
             Ok(Event::Key(key)) => {
@@ -174,18 +331,38 @@ pub fn run(platform_event_reader: PlatformEventReader, backend: Arc<dyn Backend>
             Ok(Event::Response(response)) => application.on_response(&ctx, response),
             Ok(Event::ModeChange(mode, info)) => application.mode.enter_mode(&ctx, mode, info),
             Ok(Event::ModeRevert) => application.mode.revert_mode(&ctx),
-            Err(mpsc::RecvTimeoutError::Timeout) => draw_body = false,
+            Ok(Event::Editor(path, line, on_exit)) => {
+                let result = run_editor(platform, &mut stdout, &path, line);
+                screen.invalidate();
+                on_exit(&ctx, result);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => (),
             Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
 
-        let mut drawer = Drawer::new(stdout_buf, ctx.viewport_size);
-        application.draw_header(&mut drawer);
+        // the events arriving before the frame is due are handled first and drawn at once,
+        // so a held key moves through a long list without a redraw queued behind each repeat
+        if draw_body && Instant::now() < next_frame {
+            continue;
+        }
+
+        let mut drawer = Drawer::new(stdout_buf, ctx.viewport_size, ctx.config.clone());
+        application.draw_header(&ctx, &mut drawer);
         if draw_body {
             application.draw_body(&mut drawer);
         }
         stdout_buf = drawer.take_buf();
 
-        stdout.write_all(&stdout_buf).unwrap();
+        // only what changed since the previous frame is sent, redrawing everything flickers on slow terminals
+        screen.update(&stdout_buf, ctx.viewport_size, &mut screen_buf);
+        stdout.write_all(&screen_buf).unwrap();
         stdout.flush().unwrap();
+        draw_body = false;
+        next_frame = Instant::now() + frame_interval;
+    }
+
+    // read-only leaves the git directory alone too
+    if ctx.config.get_bool("session.restore", true) && !ctx.config.get_bool("read_only", false) {
+        let _ = session::save(ctx.backend.git_dir(), application.mode.session());
     }
 }
@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{self, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Application, Event, EventSender};
+use crate::{
+    backend::Backend,
+    config::Config,
+    mode::*,
+    platform::Key,
+    ui::{screen::Screen, Drawer},
+};
+
+// long enough for a mode thread answering from a mock backend, short enough for a stuck test to fail
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// runs the application without a terminal, so tests can press keys and read the screen back as text
+pub struct Headless {
+    application: Application,
+    ctx: ModeContext,
+    event_receiver: mpsc::Receiver<Event>,
+    screen: Screen,
+}
+
+impl Headless {
+    pub fn new(
+        backend: Arc<dyn Backend>,
+        config: Config,
+        viewport_size: (u16, u16),
+        start_mode: (ModeKind, ModeChangeInfo),
+    ) -> Self {
+        let (event_sender, event_receiver) = mpsc::channel();
+        let ctx = ModeContext {
+            backend,
+            config: Arc::new(config),
+            event_sender: EventSender(event_sender),
+            viewport_size,
+            prefetched: Arc::default(),
+        };
+
+        let mut application = Application::default();
+        let (start_mode_kind, start_info) = start_mode;
+        application.start(&ctx, start_mode_kind, start_info);
+
+        let mut headless = Self { application, ctx, event_receiver, screen: Screen::default() };
+        headless.settle();
+        headless
+    }
+
+    pub fn mode_kind(&self) -> ModeKind {
+        self.application.mode.mode_kind()
+    }
+
+    // false when the key quits the application
+    pub fn key(&mut self, key: Key) -> bool {
+        let running = self.application.on_key(&self.ctx, key);
+        self.settle();
+        running
+    }
+
+    pub fn keys(&mut self, keys: &[Key]) {
+        for &key in keys {
+            self.key(key);
+        }
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.key(Key::Char(c));
+        }
+    }
+
+    // lets the threads of the modes run for a while, like the ones refreshing on a timer
+    pub fn wait(&mut self, duration: Duration) {
+        thread::sleep(duration);
+        self.settle();
+    }
+
+    // like wait() until the condition holds, false when it still does not after the timeout
+    pub fn wait_until(&mut self, timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !condition() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            self.wait(POLL_INTERVAL);
+        }
+        true
+    }
+
+    pub fn resize(&mut self, viewport_size: (u16, u16)) {
+        self.ctx.viewport_size = viewport_size;
+    }
+
+    // every thread a mode starts, like describe in log, works on a clone of the context and drops it once it
+    // sent what it has, the ones only waiting on a timer hold the event sender alone
+    fn are_mode_threads_done(&self) -> bool {
+        let done = Arc::strong_count(&self.ctx.prefetched) == 1;
+        // what the threads sent before dropping their context is then in the channel
+        atomic::fence(Ordering::Acquire);
+        done
+    }
+
+    // handles the responses and mode changes until the current mode stops waiting, the threads of the modes are done
+    // and what they sent is handled
+    pub fn settle(&mut self) {
+        let mut deadline = Instant::now() + WAIT_TIMEOUT;
+        loop {
+            let threads_done = self.are_mode_threads_done();
+            let event = match self.event_receiver.try_recv() {
+                Ok(event) => event,
+                // like the terminal loop, a replayed key goes in once nothing else arrives
+                Err(mpsc::TryRecvError::Empty) if threads_done && !self.application.is_waiting_response() => {
+                    match self.application.next_replayed_key() {
+                        Some(key) => Event::Key(key),
+                        None => break,
+                    }
+                }
+                Err(_) if Instant::now() >= deadline => {
+                    panic!("{:?} is still waiting after {:?}", self.mode_kind(), WAIT_TIMEOUT)
+                }
+                Err(_) => match self.event_receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(event) => event,
+                    Err(_) => continue,
+                },
+            };
+            match event {
+                Event::Key(key) => {
+                    self.application.on_key(&self.ctx, key);
+                }
+                Event::Resize(width, height) => self.ctx.viewport_size = (width, height),
+                Event::Response(response) => self.application.on_response(&self.ctx, response),
+                Event::ModeChange(mode, info) => self.application.mode.enter_mode(&self.ctx, mode, info),
+                Event::ModeRevert => self.application.mode.revert_mode(&self.ctx),
+                Event::Editor(_, _, on_exit) => on_exit(&self.ctx, Err("no editor when headless".to_owned())),
+            }
+            deadline = Instant::now() + WAIT_TIMEOUT;
+        }
+    }
+
+    // the header and the current mode as the terminal would show them, without the colors
+    pub fn screen(&mut self) -> String {
+        let mut drawer = Drawer::new(Vec::new(), self.ctx.viewport_size, self.ctx.config.clone());
+        self.application.draw_header(&self.ctx, &mut drawer);
+        self.application.draw_body(&mut drawer);
+        self.screen.update(&drawer.take_buf(), self.ctx.viewport_size, &mut Vec::new());
+        self.screen.text()
+    }
+}
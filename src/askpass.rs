@@ -0,0 +1,164 @@
+// git and ssh ask for credentials through an askpass program instead of the terminal the application draws on:
+// the program is this very executable run again with "--askpass", it hands the prompt over a socket
+// to the running application, which asks it in a message input and sends the answer back
+
+#[cfg(unix)]
+pub use unix::{run_helper, Server};
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        env, fs,
+        io::{BufRead, BufReader, Read, Write},
+        os::unix::{
+            fs::{DirBuilderExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+        process,
+        sync::{mpsc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        application::EventSender,
+        backend::Process,
+        mode::{ModeChangeInfo, ModeContext, ModeKind},
+        tool::shell_quote,
+    };
+
+    const SOCKET_VAR: &str = "GITUSE_ASKPASS_SOCKET";
+    // a prompt left for another mode without an answer fails instead of holding git forever
+    const ANSWER_TIMEOUT: Duration = Duration::from_secs(120);
+
+    // the prompt being answered in the message input
+    static PENDING: Mutex<Option<mpsc::Sender<Option<String>>>> = Mutex::new(None);
+
+    fn answer(answer: Option<String>) {
+        if let Some(sender) = PENDING.lock().unwrap().take() {
+            let _ = sender.send(answer);
+        }
+    }
+
+    // usernames and host key confirmations can be seen, passwords, passphrases and codes not
+    fn is_secret(prompt: &str) -> bool {
+        let prompt = prompt.to_ascii_lowercase();
+        !prompt.starts_with("username") && !prompt.contains("(yes/no")
+    }
+
+    fn ask(event_sender: &EventSender, prompt: &str) -> Option<String> {
+        let (sender, receiver) = mpsc::channel();
+        *PENDING.lock().unwrap() = Some(sender);
+
+        // the mode asking is not known from here, going back from the message input does not depend on it
+        let on_submit = |_: &ModeContext, message: String| answer(Some(message));
+        let info = ModeChangeInfo::message_input(ModeKind::MessageInput, false, "", on_submit)
+            .with_prompt(prompt.trim_end(), is_secret(prompt))
+            .with_on_cancel(|_| answer(None));
+        event_sender.send_mode_change(ModeKind::MessageInput, info);
+
+        let answer = receiver.recv_timeout(ANSWER_TIMEOUT).ok().flatten();
+        // answering after the timeout does nothing
+        PENDING.lock().unwrap().take();
+        answer
+    }
+
+    // git uses its own askpass program before asking ssh's, "core.askPass" is the one configured for git
+    fn has_askpass_program() -> bool {
+        let configured = Process::spawn("git", &["config", "core.askPass"]).and_then(Process::wait);
+        env::var_os("GIT_ASKPASS").is_some() || env::var_os("SSH_ASKPASS").is_some() || configured.is_ok()
+    }
+
+    // answers the prompts as long as it lives, the socket and the script are in a directory only the user can read
+    pub struct Server {
+        dir: PathBuf,
+    }
+    impl Server {
+        // not when the user already has an askpass program, which gets the prompts instead
+        pub fn start(event_sender: &EventSender) -> Option<Self> {
+            if has_askpass_program() {
+                return None;
+            }
+            let dir = env::temp_dir().join(format!("gituse-askpass-{}", process::id()));
+            fs::DirBuilder::new().mode(0o700).create(&dir).ok()?;
+            let server = Self { dir };
+
+            let exe = env::current_exe().ok()?;
+            let script = server.dir.join("askpass");
+            let text = format!("#!/bin/sh\nexec {} --askpass \"$1\"\n", shell_quote(&exe.to_string_lossy()));
+            fs::write(&script, text).ok()?;
+            fs::set_permissions(&script, fs::Permissions::from_mode(0o700)).ok()?;
+
+            let socket = server.dir.join("socket");
+            let listener = UnixListener::bind(&socket).ok()?;
+
+            // inherited by every git process, ssh only uses its askpass when forced to while a terminal is there
+            env::set_var(SOCKET_VAR, &socket);
+            env::set_var("GIT_ASKPASS", &script);
+            env::set_var("SSH_ASKPASS", &script);
+            env::set_var("SSH_ASKPASS_REQUIRE", "force");
+
+            let event_sender = event_sender.clone();
+            thread::spawn(move || {
+                // one prompt at a time, git asks for the username before the password
+                for mut stream in listener.incoming().flatten() {
+                    let mut prompt = String::new();
+                    if BufReader::new(&stream).read_line(&mut prompt).is_err() {
+                        continue;
+                    }
+                    // closing without an answer tells the helper the prompt was cancelled
+                    if let Some(answer) = ask(&event_sender, &prompt) {
+                        let _ = writeln!(stream, "{}", answer);
+                    }
+                }
+            });
+
+            Some(server)
+        }
+    }
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    // run as the askpass program: prints the answer for git to read, fails when the prompt was cancelled
+    pub fn run_helper(prompt: &str) -> i32 {
+        let socket = match env::var_os(SOCKET_VAR) {
+            Some(socket) => socket,
+            None => return 1,
+        };
+        let mut stream = match UnixStream::connect(socket) {
+            Ok(stream) => stream,
+            Err(_) => return 1,
+        };
+        let mut answer = String::new();
+        let asked = writeln!(stream, "{}", prompt.replace('\n', " ")).and_then(|_| stream.read_to_string(&mut answer));
+        match asked {
+            Ok(_) if !answer.is_empty() => {
+                print!("{}", answer);
+                0
+            }
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+use crate::application::EventSender;
+
+// no askpass program without unix sockets, git keeps prompting on its own
+#[cfg(not(unix))]
+pub struct Server;
+#[cfg(not(unix))]
+impl Server {
+    pub fn start(_event_sender: &EventSender) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn run_helper(_prompt: &str) -> i32 {
+    1
+}
@@ -1,12 +1,17 @@
 use std::{
-    path::PathBuf,
+    io::{Read, Write},
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    sync::Arc,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
 };
 
-use crate::mode::{fuzzy_matches, FilterEntry};
+use crate::mode::{fuzzy_matches, FilterEntry, SelectableEntry};
 
 pub mod git;
+#[cfg(feature = "testing")]
+pub mod mock;
 
 pub type BackendResult<T> = std::result::Result<T, String>;
 
@@ -55,22 +60,53 @@ impl FileStatus {
 pub struct StatusInfo {
     pub header: String,
     pub entries: Vec<RevisionEntry>,
+    pub operation: Option<Operation>,
 }
 
+// an operation that stopped halfway, usually on conflicts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+}
+impl Operation {
+    // also the git command that continues, skips or aborts it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct RevisionInfo {
+    pub date: String, // the author date
     pub message: String,
+    pub notes: String, // empty when the commit has no note
     pub entries: Vec<RevisionEntry>,
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum LfsState {
+    Downloaded,
+    Pointer, // only the pointer file is checked out, the object is missing
+}
+
 #[derive(Clone, Debug)]
 pub struct RevisionEntry {
     pub selected: bool,
     pub name: String,
     pub status: FileStatus,
+    pub lfs: Option<LfsState>,
 }
 impl RevisionEntry {
     pub fn new(name: String, status: FileStatus) -> Self {
-        Self { selected: false, name, status }
+        Self { selected: false, name, status, lfs: None }
     }
 }
 impl FilterEntry for RevisionEntry {
@@ -78,15 +114,71 @@ impl FilterEntry for RevisionEntry {
         fuzzy_matches(&self.name, pattern)
     }
 }
+impl SelectableEntry for RevisionEntry {
+    fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CiStatus {
+    Success,
+    Failure,
+    Pending,
+}
+impl CiStatus {
+    // forge apis report either a combined "state", per status "state"s or check run "status"/"conclusion"s,
+    // any failure wins over pending which wins over success
+    pub fn parse(json: &str) -> Option<Self> {
+        let mut status = None;
+        for key in &["\"state\"", "\"status\"", "\"conclusion\""] {
+            for (pos, _) in json.match_indices(key) {
+                let value = json[pos + key.len()..].trim_start().trim_start_matches(':').trim_start();
+                let value = value.trim_start_matches('"');
+                let value = &value[..value.find(['"', ',', '}']).unwrap_or(value.len())];
+
+                let new_status = match value.trim() {
+                    "failure" | "failed" | "error" | "cancelled" | "canceled" | "timed_out" | "action_required" => {
+                        Self::Failure
+                    }
+                    "pending" | "queued" | "in_progress" | "running" | "created" | "waiting" | "null" => Self::Pending,
+                    "success" | "passed" => Self::Success,
+                    _ => continue,
+                };
+                status = match (status, new_status) {
+                    (Some(Self::Failure), _) | (_, Self::Failure) => Some(Self::Failure),
+                    (Some(Self::Pending), _) | (_, Self::Pending) => Some(Self::Pending),
+                    _ => Some(Self::Success),
+                };
+            }
+        }
+        status
+    }
+
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub graph: String,
-    pub hash: String,
+    pub hash: String,       // full, what operations on the commit use
+    pub short_hash: String, // abbreviated by git, longer in repositories where 7 characters are ambiguous
     pub date: String,
     pub author: String,
     pub refs: String,
     pub message: String,
+    pub ci_status: Option<CiStatus>,
+    pub shallow: bool,                // the history below this commit was not fetched
+    pub note: bool,                   // only known while the log shows notes
+    pub describe: Option<String>,     // `git describe --tags`, fetched once the commit is hovered
+    pub full_message: Option<String>, // fetched once the commit is hovered with the preview shown
+    pub all_refs: Option<String>,     // the excluded refs too, fetched when asked for
 }
 impl FilterEntry for LogEntry {
     fn fuzzy_matches(&self, pattern: &str) -> bool {
@@ -94,12 +186,75 @@ impl FilterEntry for LogEntry {
             || fuzzy_matches(&self.refs, pattern)
             || fuzzy_matches(&self.author, pattern)
             || fuzzy_matches(&self.date, pattern)
-            || fuzzy_matches(&self.hash, pattern)
+            || fuzzy_matches(&self.short_hash, pattern)
+    }
+}
+
+// how the dates of commits are shown, the time zone is the one of the commit unless local
+#[derive(Clone, Debug)]
+pub struct DateFormat {
+    pub pattern: String, // strftime, like "%Y-%m-%d %H:%M"
+    pub local: bool,
+}
+
+// limits the commits the log and the statistics look at, dates are anything git understands ("2 weeks ago")
+#[derive(Clone, Default, Debug)]
+pub struct LogFilter {
+    pub path: Option<String>,
+    pub author: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub range: Option<String>, // like "a~..b", HEAD when none
+    pub first_parent: bool,    // only the commits of the branch itself, merges stand for what they brought in
+    pub merges: Option<bool>,  // only the merges when true, none of them when false
+}
+impl LogFilter {
+    pub fn description(&self) -> String {
+        let mut description = Vec::new();
+        if let Some(range) = &self.range {
+            description.push(format!("range: {}", range));
+        }
+        if let Some(author) = &self.author {
+            description.push(format!("author: {}", author));
+        }
+        if let Some(path) = &self.path {
+            description.push(format!("path: {}", path));
+        }
+        if let Some(since) = &self.since {
+            description.push(format!("since: {}", since));
+        }
+        if let Some(until) = &self.until {
+            description.push(format!("until: {}", until));
+        }
+        if self.first_parent {
+            description.push("first parent".to_owned());
+        }
+        match self.merges {
+            Some(true) => description.push("merges only".to_owned()),
+            Some(false) => description.push("no merges".to_owned()),
+            None => (),
+        }
+        match description.is_empty() {
+            true => "all commits".to_owned(),
+            false => description.join(", "),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthorEntry {
+    pub name: String,
+    pub commit_count: usize,
+}
+impl FilterEntry for AuthorEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.name, pattern)
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct BranchEntry {
+    pub selected: bool,
     pub name: String,
     pub checked_out: bool,
 }
@@ -108,9 +263,19 @@ impl FilterEntry for BranchEntry {
         fuzzy_matches(&self.name, pattern)
     }
 }
+impl SelectableEntry for BranchEntry {
+    fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct TagEntry {
+    pub selected: bool,
     pub name: String,
 }
 impl FilterEntry for TagEntry {
@@ -118,6 +283,71 @@ impl FilterEntry for TagEntry {
         fuzzy_matches(&self.name, pattern)
     }
 }
+impl SelectableEntry for TagEntry {
+    fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeEntry {
+    pub path: String,
+    pub directory: bool,
+}
+impl TreeEntry {
+    pub fn name(&self) -> &str {
+        match self.path.rsplit_once('/') {
+            Some((_, name)) => name,
+            None => &self.path,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.matches('/').count()
+    }
+}
+impl FilterEntry for TreeEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.path, pattern)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlameEntry {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub path: String,                       // the file name in that commit, it changes across renames
+    pub original_line: usize,               // the line number in that commit
+    pub previous: Option<(String, String)>, // parent commit and file name there, none for lines from a root commit
+    pub text: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct GrepEntry {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+}
+impl FilterEntry for GrepEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.path, pattern) || fuzzy_matches(&self.text, pattern)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SparseEntry {
+    pub directory: String,
+}
+impl FilterEntry for SparseEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.directory, pattern)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct StashEntry {
@@ -131,46 +361,264 @@ impl FilterEntry for StashEntry {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SignatureStatus {
+    Good,
+    GoodUnknownValidity,
+    Bad,
+    Expired,
+    ExpiredKey,
+    RevokedKey,
+    MissingKey, // signed, but the key is not known so it could not be checked
+    Unsigned,
+}
+impl SignatureStatus {
+    // the letters of git's %G? placeholder
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "G" => Self::Good,
+            "U" => Self::GoodUnknownValidity,
+            "B" => Self::Bad,
+            "X" => Self::Expired,
+            "Y" => Self::ExpiredKey,
+            "R" => Self::RevokedKey,
+            "E" => Self::MissingKey,
+            _ => Self::Unsigned,
+        }
+    }
+
+    pub const fn max_len() -> usize {
+        13
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::GoodUnknownValidity => "good, unknown",
+            Self::Bad => "bad",
+            Self::Expired => "expired",
+            Self::ExpiredKey => "expired key",
+            Self::RevokedKey => "revoked key",
+            Self::MissingKey => "missing key",
+            Self::Unsigned => "unsigned",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SignatureEntry {
+    pub hash: String,
+    pub tag: Option<String>, // the signature is on this tag, not on the commit
+    pub subject: String,
+    pub status: SignatureStatus,
+    pub key: String,
+    pub signer: String,
+    pub trust: String, // empty when git does not tell
+}
+impl FilterEntry for SignatureEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.subject, pattern)
+            || fuzzy_matches(&self.signer, pattern)
+            || fuzzy_matches(&self.key, pattern)
+            || fuzzy_matches(self.status.as_str(), pattern)
+            || self.tag.as_deref().is_some_and(|t| fuzzy_matches(t, pattern))
+    }
+}
+
+// state before an operation started from the tool, so it can be restored later
+#[derive(Clone, Debug)]
+pub struct UndoEntry {
+    pub operation: String,
+    pub branch: Option<String>, // None when the head was detached
+    pub head: String,
+    pub reset: bool, // the operation moved the branch tip, not only the checked out branch
+}
+
+// what a reset keeps of the commits it moves the branch away from
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResetMode {
+    Soft,  // their changes stay staged
+    Mixed, // their changes stay in the working tree, unstaged
+    Hard,  // their changes are thrown away, along with the uncommitted ones
+}
+impl ResetMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "soft" => Some(Self::Soft),
+            "mixed" => Some(Self::Mixed),
+            "hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Soft => "soft",
+            Self::Mixed => "mixed",
+            Self::Hard => "hard",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MaintenanceTask {
+    Gc,
+    Prune,
+    Fsck,
+    CountObjects,
+    CheckRemotes(Duration), // how long each remote has to answer
+}
+impl MaintenanceTask {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gc => "gc",
+            Self::Prune => "prune",
+            Self::Fsck => "fsck",
+            Self::CountObjects => "count-objects",
+            Self::CheckRemotes(_) => "ls-remote",
+        }
+    }
+}
+
+// sizes are in KiB, as reported by "git count-objects -v"
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RepositorySize {
+    pub loose_count: usize,
+    pub loose_size: usize,
+    pub pack_count: usize,
+    pub packed_count: usize,
+    pub pack_size: usize,
+    pub garbage_size: usize,
+}
+
+// what is checked when starting, an identity is none when it is not configured
+#[derive(Clone, Default, Debug)]
+pub struct SetupInfo {
+    pub git_version: String, // like "2.43.0", with the suffix of some builds like ".windows.1"
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+}
+
 pub trait Backend: 'static + Send + Sync {
-    fn status(&self) -> BackendResult<StatusInfo>;
-    fn commit(&self, message: &str, entries: &[RevisionEntry], amend: bool) -> BackendResult<()>;
-    fn discard(&self, entries: &[RevisionEntry]) -> BackendResult<()>;
+    fn is_bare(&self) -> bool;
+    fn git_dir(&self) -> &Path;
+    // the excluded paths, like generated directories, are not even scanned
+    fn status(&self, excluded: &[&str]) -> BackendResult<StatusInfo>;
+    // with no entries the whole work tree but the excluded paths
+    fn commit(&self, message: &str, entries: &[RevisionEntry], amend: bool, excluded: &[&str]) -> BackendResult<()>;
+    fn commit_fixup(&self, revision: &str) -> BackendResult<()>;
+    fn commit_template(&self) -> BackendResult<String>;
+    fn has_staged_changes(&self) -> BackendResult<bool>;
+    fn recent_authors(&self, commit_count: usize) -> BackendResult<Vec<String>>;
+    fn discard(&self, entries: &[RevisionEntry], excluded: &[&str]) -> BackendResult<()>;
     fn diff(&self, revision: Option<&str>, entries: &[RevisionEntry]) -> BackendResult<String>;
+    fn stage_patch(&self, patch: &str) -> BackendResult<()>;
     fn resolve_taking_ours(&self, entries: &[RevisionEntry]) -> BackendResult<()>;
     fn resolve_taking_theirs(&self, entries: &[RevisionEntry]) -> BackendResult<()>;
 
-    fn log(&self, start: usize, len: usize) -> BackendResult<(usize, Vec<LogEntry>)>;
+    // the refs matching the excluded patterns, like "remotes/*", are left out of the refs of the entries
+    fn log(
+        &self,
+        start: usize,
+        len: usize,
+        filter: &LogFilter,
+        date_format: &DateFormat,
+        decorate_exclude: &[&str],
+    ) -> BackendResult<(usize, Vec<LogEntry>)>;
+    // the first commit the log lists that is not newer than the date, which git parses like "last march"
+    fn first_commit_until(&self, date: &str, filter: &LogFilter) -> BackendResult<Option<String>>;
+    fn shortlog(&self, filter: &LogFilter) -> BackendResult<Vec<AuthorEntry>>;
+    fn commit_times(&self, filter: &LogFilter) -> BackendResult<Vec<i64>>;
     fn checkout(&self, revision: &str) -> BackendResult<()>;
     fn merge(&self, revision: &str) -> BackendResult<()>;
+    fn lfs_pull(&self) -> BackendResult<()>;
     fn fetch(&self) -> BackendResult<()>;
+    fn fetch_remote(&self, name: &str) -> BackendResult<()>;
+    fn add_remote(&self, name: &str, url: &str) -> BackendResult<()>; // only once the url answers
+    fn deepen(&self, count: usize) -> BackendResult<()>;
+    fn unshallow(&self) -> BackendResult<()>;
     fn pull(&self) -> BackendResult<()>;
     fn push(&self) -> BackendResult<()>;
+    fn push_force(&self) -> BackendResult<()>;
+    fn push_branches(&self, names: &[String]) -> BackendResult<()>;
+    fn push_tags(&self, names: &[String]) -> BackendResult<()>;
+    fn push_all_tags(&self) -> BackendResult<()>;
+    fn remote_branch_refs(&self, names: &[String]) -> BackendResult<Vec<(String, String)>>; // remote, ref
+    fn delete_remote_branches(&self, refs: &[(String, String)]) -> BackendResult<()>;
+    fn delete_remote_tags(&self, names: &[String]) -> BackendResult<()>;
+    fn push_preview(&self) -> BackendResult<String>;
     fn push_gerrit(&self) -> BackendResult<()>;
-    fn reset(&self, revision: &str) -> BackendResult<()>;
+    fn reset(&self, revision: &str, mode: ResetMode) -> BackendResult<()>;
+    fn reset_preview(&self) -> BackendResult<(String, bool)>; // the commits discarded, whether there are local changes
+    fn autosquash(&self, revision: &str) -> BackendResult<()>;
+    fn squash_preview(&self, range: &str) -> BackendResult<(String, usize)>; // the combined message, how many are pushed
+    fn squash(&self, range: &str, message: &str) -> BackendResult<()>;
+    fn format_patches(&self, range: &str, directory: &Path) -> BackendResult<Vec<String>>; // the cover letter first
+    fn archive(&self, revision: &str, path: &str, prefix: &str) -> BackendResult<()>; // the format follows the extension
+    fn rebase(&self, onto: &str, upstream: Option<&str>) -> BackendResult<()>;
+    fn operation(&self) -> Option<Operation>;
+    fn operation_continue(&self) -> BackendResult<()>;
+    fn operation_skip(&self) -> BackendResult<()>;
+    fn operation_abort(&self) -> BackendResult<()>;
+    fn undo_list(&self) -> Vec<UndoEntry>;
+    fn undo(&self) -> BackendResult<()>;
+    fn ci_status(&self, url_template: &str, token: Option<&str>, revision: &str) -> BackendResult<CiStatus>;
 
     fn stash(&self, message: &str, entries: &[RevisionEntry]) -> BackendResult<()>;
+    fn stash_all(&self, message: &str) -> BackendResult<()>;
     fn stash_list(&self) -> BackendResult<Vec<StashEntry>>;
     fn stash_pop(&self, id: usize) -> BackendResult<()>;
     fn stash_show(&self, id: usize) -> BackendResult<String>;
     fn stash_diff(&self, id: usize) -> BackendResult<String>;
     fn stash_drop(&self, id: usize) -> BackendResult<()>;
 
-    fn revision_details(&self, revision: &str) -> BackendResult<RevisionInfo>;
+    fn revision_details(&self, revision: &str, date_format: &DateFormat) -> BackendResult<RevisionInfo>;
+    fn describe(&self, revision: &str) -> BackendResult<String>;
+    fn refs(&self, revision: &str) -> BackendResult<String>; // all of them, none excluded
+
+    fn note(&self, revision: &str) -> BackendResult<String>;
+    fn noted_revisions(&self) -> BackendResult<Vec<String>>;
+    fn set_note(&self, revision: &str, note: &str) -> BackendResult<()>;
+    fn remove_note(&self, revision: &str) -> BackendResult<()>;
 
     fn branches(&self) -> BackendResult<Vec<BranchEntry>>;
     fn new_branch(&self, name: &str) -> BackendResult<()>;
     fn delete_branch(&self, name: &str, force: bool) -> BackendResult<()>;
+    fn delete_branches(&self, names: &[String], force: bool) -> BackendResult<()>;
+    fn merged_branches(&self) -> BackendResult<(String, Vec<String>)>;
+
+    fn tree(&self, revision: &str) -> BackendResult<Vec<TreeEntry>>;
+    fn file_content(&self, revision: &str, path: &str) -> BackendResult<String>;
+    fn blame(&self, revision: &str, path: &str) -> BackendResult<Vec<BlameEntry>>;
+    fn signatures(&self, range: &str) -> BackendResult<Vec<SignatureEntry>>;
+    fn grep(&self, pattern: &str, revision: Option<&str>) -> BackendResult<Vec<GrepEntry>>;
+
+    fn sparse_checkout_list(&self) -> BackendResult<Vec<SparseEntry>>;
+    fn sparse_checkout_add(&self, directory: &str) -> BackendResult<()>;
+    fn sparse_checkout_remove(&self, directory: &str) -> BackendResult<()>;
+    fn sparse_checkout_reapply(&self) -> BackendResult<()>;
+    fn sparse_checkout_disable(&self) -> BackendResult<()>;
 
     fn tags(&self) -> BackendResult<Vec<TagEntry>>;
     fn new_tag(&self, name: &str) -> BackendResult<()>;
     fn delete_tag(&self, name: &str) -> BackendResult<()>;
+
+    fn maintenance(&self, task: MaintenanceTask, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()>;
+    fn repository_size(&self) -> BackendResult<RepositorySize>;
+    fn setup_info(&self) -> BackendResult<SetupInfo>;
+    fn set_config(&self, key: &str, value: &str, global: bool) -> BackendResult<()>; // in the repository otherwise
+    fn run_command(&self, command: &str, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()>;
 }
 
 pub struct Process(Child);
 impl Process {
     pub fn spawn(command_name: &str, args: &[&str]) -> BackendResult<Self> {
+        Self::spawn_with_env(command_name, args, &[])
+    }
+
+    pub fn spawn_with_env(command_name: &str, args: &[&str], envs: &[(&str, &str)]) -> BackendResult<Self> {
         let mut command = Command::new(command_name);
-        command.args(args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        command.args(args).envs(envs.iter().copied()).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
 
         match command.spawn() {
             Ok(child) => Ok(Self(child)),
@@ -178,6 +626,34 @@ impl Process {
         }
     }
 
+    // what is written to stdin does not show up in the process list, unlike the args
+    pub fn spawn_with_input(command_name: &str, args: &[&str], input: &str) -> BackendResult<Self> {
+        let mut command = Command::new(command_name);
+        command.args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(error) => return Err(format!("could not spawn process '{}': {}", command_name, error)),
+        };
+        // dropping stdin closes it, so the process sees the end of the input
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).map_err(|error| format!("could not write to '{}': {}", command_name, error))?;
+        }
+        Ok(Self(child))
+    }
+
+    // exit status, stdout and stderr, for commands that report through stderr even when failing
+    pub fn wait_output(self) -> BackendResult<(bool, String, String)> {
+        match self.0.wait_with_output() {
+            Ok(output) => Ok((
+                output.status.success(),
+                String::from_utf8_lossy(&output.stdout).into(),
+                String::from_utf8_lossy(&output.stderr).into(),
+            )),
+            Err(error) => Err(format!("could not wait for process: {}", error)),
+        }
+    }
+
     pub fn wait(self) -> BackendResult<String> {
         let output = match self.0.wait_with_output() {
             Ok(output) => output,
@@ -196,12 +672,96 @@ impl Process {
             Err(error)
         }
     }
-}
 
-pub fn backend_from_current_repository() -> Option<(PathBuf, Arc<dyn Backend>)> {
-    if let Some((root, git)) = git::Git::try_new() {
-        Some((root, Arc::new(git)))
-    } else {
-        None
+    // none when the process did not exit in time, it is killed then
+    pub fn wait_timeout(mut self, timeout: Duration) -> BackendResult<Option<String>> {
+        fn read_pipe<R: 'static + Read + Send>(pipe: Option<R>) -> mpsc::Receiver<String> {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let mut text = String::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_string(&mut text);
+                }
+                let _ = sender.send(text);
+            });
+            receiver
+        }
+
+        // read while waiting, a process filling a pipe nobody reads would never exit
+        let stdout = read_pipe(self.0.stdout.take());
+        let stderr = read_pipe(self.0.stderr.take());
+        let start = Instant::now();
+        loop {
+            match self.0.try_wait() {
+                Ok(Some(status)) => {
+                    let stdout = stdout.recv().unwrap_or_default();
+                    return match status.success() {
+                        true => Ok(Some(stdout)),
+                        false => Err(format!("{}\n{}", stdout, stderr.recv().unwrap_or_default())),
+                    };
+                }
+                Ok(None) if start.elapsed() >= timeout => {
+                    let _ = self.0.kill();
+                    let _ = self.0.wait();
+                    return Ok(None);
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(20)),
+                Err(error) => return Err(format!("could not wait for process: {}", error)),
+            }
+        }
+    }
+
+    // calls on_line with every line as soon as it is printed on stdout or stderr,
+    // progress lines that end with '\r' are flagged since the next line overwrites them
+    pub fn stream(mut self, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        fn read_pipe<R: 'static + Read + Send>(index: usize, mut pipe: R, sender: mpsc::Sender<(usize, Vec<u8>)>) {
+            thread::spawn(move || {
+                let mut buf = [0; 1024];
+                loop {
+                    match pipe.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(len) => {
+                            if sender.send((index, buf[..len].to_vec())).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        if let Some(stdout) = self.0.stdout.take() {
+            read_pipe(0, stdout, sender.clone());
+        }
+        if let Some(stderr) = self.0.stderr.take() {
+            read_pipe(1, stderr, sender.clone());
+        }
+        drop(sender);
+
+        // each pipe keeps its own partial line so stdout and stderr never mix within a line
+        let mut pending = [Vec::new(), Vec::new()];
+        for (index, bytes) in receiver {
+            let pending = &mut pending[index];
+            pending.extend(bytes);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n' || b == b'\r') {
+                on_line(&String::from_utf8_lossy(&pending[..end]), pending[end] == b'\r');
+                pending.drain(..=end);
+            }
+        }
+        for pending in pending.iter().filter(|p| !p.is_empty()) {
+            on_line(&String::from_utf8_lossy(pending), false);
+        }
+
+        match self.0.wait() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("process failed with {}", status)),
+            Err(error) => Err(format!("could not wait for process: {}", error)),
+        }
     }
 }
+
+pub fn backend_from_path(path: &Path) -> BackendResult<(PathBuf, Arc<dyn Backend>)> {
+    let (root, git) = git::Git::try_new(path)?;
+    Ok((root, Arc::new(git)))
+}
@@ -1,25 +1,123 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use super::{
-    Backend, BackendResult, BranchEntry, FileStatus, LogEntry, Process, RevisionEntry, RevisionInfo, StashEntry, StatusInfo,
-    TagEntry,
+    AuthorEntry, Backend, BackendResult, BlameEntry, BranchEntry, CiStatus, DateFormat, FileStatus, GrepEntry, LfsState,
+    LogEntry, LogFilter, MaintenanceTask, Operation, Process, RepositorySize, ResetMode, RevisionEntry, RevisionInfo,
+    SetupInfo, SignatureEntry, SignatureStatus, SparseEntry, StashEntry, StatusInfo, TagEntry, TreeEntry, UndoEntry,
 };
 
-//use crate::tool;
+use crate::tool;
+
+// what a root commit is diffed against
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
 
-pub struct Git;
+pub struct Git {
+    bare: bool,
+    git_dir: PathBuf,
+    ci_status_cache: Mutex<HashMap<String, CiStatus>>, // full hash -> final status
+    undo_stack: Mutex<Vec<UndoEntry>>,
+}
 
 impl Git {
-    pub fn try_new() -> Option<(PathBuf, Self)> {
-        let output = Process::spawn("git", &["rev-parse", "--show-toplevel"]).ok()?.wait().ok()?;
+    // looks for the repository in path or any of its parents
+    pub fn try_new(path: &Path) -> BackendResult<(PathBuf, Self)> {
+        let path = match env::current_dir() {
+            Ok(current_dir) => current_dir.join(path),
+            Err(_) => path.to_owned(),
+        };
+        if !path.is_dir() {
+            return Err(format!("'{}' is not a directory", path.display()));
+        }
+        if let Err(error) = Process::spawn("git", &["--version"]).and_then(Process::wait) {
+            return Err(format!("git could not be run, is it installed and in the PATH?\n{}", error));
+        }
+        let is_bare_dir =
+            |dir: &Path| dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir();
+        let root = match path.ancestors().find(|dir| dir.join(".git").exists() || is_bare_dir(dir)) {
+            Some(root) => root,
+            None => {
+                return Err(format!(
+                    "no git repository found in '{}' or any of its parent directories, 'git init' creates one",
+                    path.display()
+                ))
+            }
+        };
+
+        let root = root.to_string_lossy();
+        let output = match Process::spawn("git", &["-C", &root, "rev-parse", "--is-bare-repository"])?.wait() {
+            Ok(output) => output,
+            Err(error) => return Err(format!("'{}' is not a valid git repository: {}", root, error.trim())),
+        };
+        // a bare repository has no top level, its root is the git directory itself
+        let bare = output.trim() == "true";
+        let git_dir: PathBuf =
+            Process::spawn("git", &["-C", &root, "rev-parse", "--absolute-git-dir"])?.wait()?.trim().into();
+        let root = if bare {
+            git_dir.clone()
+        } else {
+            Process::spawn("git", &["-C", &root, "rev-parse", "--show-toplevel"])?.wait()?.trim().into()
+        };
 
-        let root = Path::new(output.trim()).into();
-        Some((root, Self))
+        let git = Self { bare, git_dir, ci_status_cache: Mutex::new(HashMap::new()), undo_stack: Mutex::new(Vec::new()) };
+        Ok((root, git))
     }
 
+    // the commit message git would ask for is kept as is
+    fn operation_command(&self, flag: &str) -> BackendResult<()> {
+        let operation = self.operation().ok_or_else(|| "no merge, rebase, cherry-pick or revert in progress".to_owned())?;
+        Process::spawn_with_env("git", &[operation.as_str(), flag], &[("GIT_EDITOR", ":")])?.wait()?;
+        Ok(())
+    }
+
+    // "origin" when there are several remotes
     fn remote(&self) -> BackendResult<String> {
-        let remote = Process::spawn("git", &["remote"])?.wait()?.trim().to_owned();
-        Ok(remote)
+        let remotes = Process::spawn("git", &["remote"])?.wait()?;
+        match remotes.lines().find(|&r| r == "origin").or_else(|| remotes.lines().next()) {
+            Some(remote) => Ok(remote.to_owned()),
+            None => Err("no remote configured".to_owned()),
+        }
+    }
+
+    // the branch the remote HEAD points to, otherwise main or master,
+    // the remote tracking branch is used when there is no local one
+    fn default_branch(&self) -> BackendResult<String> {
+        let local_branch_exists = |branch: &str| {
+            let local_ref = format!("refs/heads/{}", branch);
+            Process::spawn("git", &["show-ref", "--verify", "--quiet", &local_ref]).and_then(Process::wait).is_ok()
+        };
+
+        if let Ok(remote) = self.remote() {
+            let remote_head = format!("refs/remotes/{}/HEAD", remote);
+            if let Ok(branch) = Process::spawn("git", &["symbolic-ref", "--short", &remote_head]).and_then(Process::wait) {
+                let branch = branch.trim();
+                return match branch.strip_prefix(&format!("{}/", remote)) {
+                    Some(local_branch) if local_branch_exists(local_branch) => Ok(local_branch.to_owned()),
+                    _ => Ok(branch.to_owned()),
+                };
+            }
+        }
+        match ["main", "master"].iter().find(|b| local_branch_exists(b)) {
+            Some(branch) => Ok(branch.to_string()),
+            None => Err("could not tell the default branch, there is no remote HEAD, main or master".to_owned()),
+        }
+    }
+
+    // a single push to the default remote for all the refs
+    fn push_refs<I: Iterator<Item = String>>(&self, options: &[&str], refs: I) -> BackendResult<()> {
+        let remote = self.remote()?;
+        let refs: Vec<_> = refs.collect();
+        let mut args = vec!["push"];
+        args.extend(options);
+        args.push(&remote);
+        args.extend(refs.iter().map(String::as_str));
+        Process::spawn("git", &args)?.wait()?;
+        Ok(())
     }
 
     fn current_branch(&self) -> BackendResult<String> {
@@ -27,6 +125,62 @@ impl Git {
         Ok(branch)
     }
 
+    // remembers where HEAD was when the operation succeeds
+    fn with_undo<F>(&self, operation: String, reset: bool, f: F) -> BackendResult<()>
+    where
+        F: FnOnce() -> BackendResult<()>,
+    {
+        let head = Process::spawn("git", &["rev-parse", "HEAD"])?.wait()?.trim().to_owned();
+        let branch = self.current_branch().ok();
+        f()?;
+        self.undo_stack.lock().unwrap().push(UndoEntry { operation, branch, head, reset });
+        Ok(())
+    }
+
+    fn git_path(&self, path: &str) -> BackendResult<PathBuf> {
+        let path = Process::spawn("git", &["rev-parse", "--git-path", path])?.wait()?;
+        Ok(PathBuf::from(path.trim()))
+    }
+
+    // returns how many lfs objects in HEAD are not downloaded, nothing is marked when git-lfs is not installed
+    fn mark_lfs_entries(&self, entries: &mut [RevisionEntry]) -> usize {
+        // "<oid> * <path>" when the object is downloaded, "<oid> - <path>" when only the pointer is checked out
+        let files = match Process::spawn("git", &["lfs", "ls-files"]).and_then(Process::wait) {
+            Ok(files) => files,
+            Err(_) => return 0,
+        };
+        let mut states = HashMap::new();
+        for line in files.lines() {
+            let mut splits = line.splitn(3, ' ');
+            let (_, state, path) = (splits.next(), splits.next(), splits.next().unwrap_or(""));
+            let state = if state == Some("*") { LfsState::Downloaded } else { LfsState::Pointer };
+            states.insert(path.to_owned(), state);
+        }
+        let missing_count = states.values().filter(|s| matches!(s, LfsState::Pointer)).count();
+
+        // changed files that are not committed yet are only known by their attributes
+        if !entries.is_empty() {
+            let mut args = vec!["check-attr", "filter", "--"];
+            args.extend(entries.iter().map(|e| e.name.as_str()));
+            let attributes = Process::spawn("git", &args).and_then(Process::wait).unwrap_or_default();
+            for path in attributes.lines().filter_map(|l| l.strip_suffix(": filter: lfs")) {
+                states.entry(path.to_owned()).or_insert(LfsState::Downloaded);
+            }
+        }
+
+        for entry in entries {
+            entry.lfs = states.get(&entry.name).copied();
+        }
+        missing_count
+    }
+
+    fn sparse_checkout_enabled(&self) -> bool {
+        match Process::spawn("git", &["config", "--bool", "core.sparseCheckout"]).and_then(Process::wait) {
+            Ok(value) => value.trim() == "true",
+            Err(_) => false,
+        }
+    }
+
     fn remote_branch(&self) -> BackendResult<String> {
         let mut remote = self.remote()?;
         let current_branch = self.current_branch()?;
@@ -37,12 +191,23 @@ impl Git {
 }
 
 impl Backend for Git {
-    fn status(&self) -> BackendResult<StatusInfo> {
-        let output = Process::spawn("git", &["status", "--branch", "--no-rename", "--null"])?.wait()?;
+    fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    fn status(&self, excluded: &[&str]) -> BackendResult<StatusInfo> {
+        let pathspecs = work_tree_pathspecs(excluded);
+        let mut args = vec!["status", "--branch", "--no-rename", "--null", "--"];
+        args.extend(pathspecs.iter().map(String::as_str));
+        let output = Process::spawn("git", &args)?.wait()?;
         let mut splits = output.split('\0').map(str::trim);
 
-        let header = splits.next().unwrap_or("").into();
-        let entries = splits
+        let mut header: String = splits.next().unwrap_or("").into();
+        let mut entries: Vec<_> = splits
             .filter(|e| e.len() >= 2)
             .map(|e| {
                 let (status, filename) = e.split_at(2);
@@ -50,12 +215,23 @@ impl Backend for Git {
             })
             .collect();
 
-        Ok(StatusInfo { header, entries })
+        let missing_count = self.mark_lfs_entries(&mut entries);
+        if missing_count > 0 {
+            header.push_str(&format!("  lfs: {} object(s) not downloaded", missing_count));
+        }
+
+        Ok(StatusInfo { header, entries, operation: self.operation() })
     }
 
-    fn commit(&self, message: &str, entries: &[RevisionEntry], amend: bool) -> BackendResult<()> {
+    fn commit(&self, message: &str, entries: &[RevisionEntry], amend: bool, excluded: &[&str]) -> BackendResult<()> {
         if entries.is_empty() {
-            Process::spawn("git", &["add", "--all"])?.wait()?;
+            // lines staged from the diff view are committed by themselves
+            if !self.has_staged_changes()? {
+                let pathspecs = work_tree_pathspecs(excluded);
+                let mut args = vec!["add", "--all", "--"];
+                args.extend(pathspecs.iter().map(String::as_str));
+                Process::spawn("git", &args)?.wait()?;
+            }
         } else {
             let mut args = vec!["add", "--"];
             for entry in entries {
@@ -73,10 +249,55 @@ impl Backend for Git {
         Ok(())
     }
 
-    fn discard(&self, entries: &[RevisionEntry]) -> BackendResult<()> {
-        if entries.is_empty() {
+    fn commit_fixup(&self, revision: &str) -> BackendResult<()> {
+        Process::spawn("git", &["commit", &format!("--fixup={}", revision)])?.wait()?;
+        Ok(())
+    }
+
+    // the prepare-commit-msg hook is not run here, "git commit" runs it once the message is written
+    fn commit_template(&self) -> BackendResult<String> {
+        // "git config" fails when the key is not set
+        let template_path = match Process::spawn("git", &["config", "--path", "commit.template"])?.wait() {
+            Ok(path) => PathBuf::from(path.trim()),
+            Err(_) => PathBuf::from(".gitmessage"),
+        };
+        let message = fs::read_to_string(&template_path).unwrap_or_default();
+
+        let message: Vec<_> = message.lines().filter(|l| !l.starts_with('#')).collect();
+        Ok(message.join("\n").trim_end().to_owned())
+    }
+
+    // "diff --quiet" fails when there are any
+    fn has_staged_changes(&self) -> BackendResult<bool> {
+        Ok(Process::spawn("git", &["diff", "--cached", "--quiet"])?.wait().is_err())
+    }
+
+    fn recent_authors(&self, commit_count: usize) -> BackendResult<Vec<String>> {
+        let commit_count = commit_count.to_string();
+        let output = Process::spawn("git", &["log", "--max-count", &commit_count, "--format=%aN <%aE>"])?.wait()?;
+        let own_email = Process::spawn("git", &["config", "user.email"])?.wait().unwrap_or_default();
+        let own_email = format!("<{}>", own_email.trim());
+
+        let mut authors: Vec<String> = Vec::new();
+        for author in output.lines() {
+            if !author.ends_with(&own_email) && !authors.iter().any(|a| a == author) {
+                authors.push(author.to_owned());
+            }
+        }
+        Ok(authors)
+    }
+
+    fn discard(&self, entries: &[RevisionEntry], excluded: &[&str]) -> BackendResult<()> {
+        if entries.is_empty() && excluded.is_empty() {
             Process::spawn("git", &["reset", "--hard", "HEAD"])?.wait()?;
             Process::spawn("git", &["clean", "--force"])?.wait()?;
+        } else if entries.is_empty() {
+            // "reset --hard" takes no pathspecs, the added files become untracked ones for the clean
+            let pathspecs = work_tree_pathspecs(excluded);
+            let pathspecs: Vec<_> = pathspecs.iter().map(String::as_str).collect();
+            Process::spawn("git", &[&["reset", "--quiet", "HEAD", "--"], &pathspecs[..]].concat())?.wait()?;
+            Process::spawn("git", &[&["checkout", "--"], &pathspecs[..]].concat())?.wait()?;
+            Process::spawn("git", &[&["clean", "--force", "--"], &pathspecs[..]].concat())?.wait()?;
         } else {
             let drop_entry = |f: fn(&FileStatus) -> bool, args: &[&str]| -> BackendResult<()> {
                 let filter_entries: Vec<_> = entries.iter().filter(|&e| f(&e.status)).map(|e| e.name.as_str()).collect();
@@ -97,14 +318,21 @@ impl Backend for Git {
         Ok(())
     }
 
+    // renames and copies are found, submodules are summed up with the subjects of the commits they moved by
     fn diff(&self, revision: Option<&str>, entries: &[RevisionEntry]) -> BackendResult<String> {
         match revision {
             Some(revision) => {
+                // a range is diffed as a whole, a commit against its parent
                 let parent = format!("{}~", revision);
+                let mut args = match (revision.contains(".."), root_range(revision)) {
+                    (true, Some(newest)) => vec!["diff", "--find-copies", "--submodule=log", EMPTY_TREE, newest],
+                    (true, None) => vec!["diff", "--find-copies", "--submodule=log", revision],
+                    (false, _) => vec!["diff", "--find-copies", "--submodule=log", &parent, revision],
+                };
                 if entries.is_empty() {
-                    Process::spawn("git", &["diff", &parent, revision])?.wait()
+                    Process::spawn("git", &args)?.wait()
                 } else {
-                    let mut args = vec!["diff", &parent, revision, "--"];
+                    args.push("--");
                     for entry in entries {
                         args.push(&entry.name);
                     }
@@ -114,9 +342,9 @@ impl Backend for Git {
             }
             None => {
                 if entries.is_empty() {
-                    Process::spawn("git", &["diff", "-z"])?.wait()
+                    Process::spawn("git", &["diff", "--find-copies", "--submodule=log", "-z"])?.wait()
                 } else {
-                    let mut args = vec!["diff", "--"];
+                    let mut args = vec!["diff", "--find-copies", "--submodule=log", "--"];
                     for entry in entries {
                         args.push(&entry.name);
                     }
@@ -126,6 +354,14 @@ impl Backend for Git {
         }
     }
 
+    fn stage_patch(&self, patch: &str) -> BackendResult<()> {
+        let patch_path = self.git_path("GITUSE_PATCH")?;
+        fs::write(&patch_path, patch).map_err(|e| format!("could not write {:?}: {}", patch_path, e))?;
+        let result = Process::spawn("git", &["apply", "--cached", "--recount", &patch_path.to_string_lossy()])?.wait();
+        let _ = fs::remove_file(&patch_path);
+        result.map(|_| ())
+    }
+
     fn resolve_taking_ours(&self, entries: &[RevisionEntry]) -> BackendResult<()> {
         if entries.is_empty() {
             Process::spawn("git", &["checkout", "--ours", "."])?.wait()?;
@@ -168,52 +404,124 @@ impl Backend for Git {
         Ok(())
     }
 
-    fn log(&self, skip: usize, len: usize) -> BackendResult<(usize, Vec<LogEntry>)> {
+    fn log(
+        &self,
+        skip: usize,
+        len: usize,
+        filter: &LogFilter,
+        date_format: &DateFormat,
+        decorate_exclude: &[&str],
+    ) -> BackendResult<(usize, Vec<LogEntry>)> {
         let skip_text = skip.to_string();
         let len = len.to_string();
-        let template = "--format=format:%x00%h%x00%as%x00%aN%x00%D%x00%s";
-        let output = Process::spawn(
-            "git",
-            &[
-                "log",
-                //"--all",
-                "--decorate",
-                "--oneline",
-                "--graph",
-                "--skip",
-                &skip_text,
-                "--max-count",
-                &len,
-                template,
-            ],
-        )?
-        .wait()?;
+        let date_arg = date_arg(date_format);
+        let template = "--format=format:%x00%H%x00%h%x00%ad%x00%aN%x00%D%x00%s";
+        let mut args = vec![
+            "log",
+            //"--all",
+            "--decorate",
+            "--oneline",
+            "--graph",
+            "--skip",
+            &skip_text,
+            "--max-count",
+            &len,
+            &date_arg,
+            template,
+        ];
+        let exclude_args: Vec<_> = decorate_exclude.iter().map(|p| format!("--decorate-refs-exclude={}", p)).collect();
+        args.extend(exclude_args.iter().map(String::as_str));
+        let filter_args = log_filter_args(filter);
+        args.extend(filter_args.iter().map(String::as_str));
+        let output = Process::spawn("git", &args)?.wait()?;
 
         let mut entries = Vec::new();
         for line in output.lines() {
-            let mut splits = line.splitn(6, '\0');
+            let mut splits = line.splitn(7, '\0');
 
             let graph = splits.next().unwrap_or("").into();
             let hash = splits.next().unwrap_or("").into();
+            let short_hash = splits.next().unwrap_or("").into();
             let date = splits.next().unwrap_or("").into();
             let author = splits.next().unwrap_or("").into();
-            let refs = splits.next().unwrap_or("").into();
+            let refs = splits.next().unwrap_or("");
             let message = splits.next().unwrap_or("").into();
 
-            entries.push(LogEntry { graph, hash, date, author, refs, message });
+            let shallow = refs.split(", ").any(|r| r == "grafted");
+            let refs = without_grafted(refs);
+
+            entries.push(LogEntry {
+                graph,
+                hash,
+                short_hash,
+                date,
+                author,
+                refs,
+                message,
+                ci_status: None,
+                shallow,
+                note: false,
+                describe: None,
+                full_message: None,
+                all_refs: None,
+            });
         }
 
         Ok((skip, entries))
     }
 
+    fn first_commit_until(&self, date: &str, filter: &LogFilter) -> BackendResult<Option<String>> {
+        // in the order of the graph the log draws, the filter goes last as it can end with paths
+        let until = format!("--until={}", date);
+        let mut args = vec!["log", "--topo-order", "--max-count=1", "--format=format:%H", &until];
+        let filter_args = log_filter_args(filter);
+        args.extend(filter_args.iter().map(String::as_str));
+        let output = Process::spawn("git", &args)?.wait()?;
+        Ok(output.lines().next().map(str::to_owned).filter(|hash| !hash.is_empty()))
+    }
+
+    fn shortlog(&self, filter: &LogFilter) -> BackendResult<Vec<AuthorEntry>> {
+        // without a revision shortlog reads a log from stdin
+        let mut args = vec!["shortlog", "--summary", "--numbered"];
+        if filter.range.is_none() {
+            args.push("HEAD");
+        }
+        let filter_args = log_filter_args(filter);
+        args.extend(filter_args.iter().map(String::as_str));
+        let output = Process::spawn("git", &args)?.wait()?;
+
+        let entries = output
+            .lines()
+            .filter_map(|l| l.trim_start().split_once('\t'))
+            .map(|(count, name)| AuthorEntry { name: name.into(), commit_count: count.parse().unwrap_or(0) })
+            .collect();
+        Ok(entries)
+    }
+
+    // author dates as unix times, the most recent first
+    fn commit_times(&self, filter: &LogFilter) -> BackendResult<Vec<i64>> {
+        let mut args = vec!["log", "--format=%at"];
+        if filter.range.is_none() {
+            args.push("HEAD");
+        }
+        let filter_args = log_filter_args(filter);
+        args.extend(filter_args.iter().map(String::as_str));
+        let output = Process::spawn("git", &args)?.wait()?;
+        Ok(output.lines().filter_map(|l| l.trim().parse().ok()).collect())
+    }
+
     fn checkout(&self, revision: &str) -> BackendResult<()> {
-        Process::spawn("git", &["checkout", revision])?.wait()?;
-        Ok(())
+        self.with_undo(format!("checkout {}", revision), false, || {
+            Process::spawn("git", &["checkout", revision])?.wait()?;
+            Ok(())
+        })
     }
 
     fn merge(&self, revision: &str) -> BackendResult<()> {
-        Process::spawn("git", &["merge", "--no-ff", revision])?.wait()?;
-        Ok(())
+        self.with_undo(format!("merge {}", revision), true, || {
+            Process::spawn("git", &["merge", "--no-ff", revision])?.wait()?;
+            Ok(())
+        })
     }
 
     fn fetch(&self) -> BackendResult<()> {
@@ -221,6 +529,39 @@ impl Backend for Git {
         Ok(())
     }
 
+    fn lfs_pull(&self) -> BackendResult<()> {
+        Process::spawn("git", &["lfs", "pull"])?.wait()?;
+        Ok(())
+    }
+
+    fn fetch_remote(&self, name: &str) -> BackendResult<()> {
+        Process::spawn("git", &["fetch", "--prune", name])?.wait()?;
+        Ok(())
+    }
+
+    // a mistyped url is refused before it is added, the credentials it asks for go through askpass
+    // the name and the url come from the clipboard, so they are never taken as options
+    fn add_remote(&self, name: &str, url: &str) -> BackendResult<()> {
+        if name.starts_with('-') || url.starts_with('-') {
+            return Err(format!("'{}' or '{}' starts with '-'", name, url));
+        }
+        Process::spawn("git", &["ls-remote", "--heads", "--", url])?
+            .wait()
+            .map_err(|error| format!("'{}' did not answer as a repository: {}", url, error.trim()))?;
+        Process::spawn("git", &["remote", "add", "--", name, url])?.wait()?;
+        Ok(())
+    }
+
+    fn deepen(&self, count: usize) -> BackendResult<()> {
+        Process::spawn("git", &["fetch", &format!("--deepen={}", count)])?.wait()?;
+        Ok(())
+    }
+
+    fn unshallow(&self) -> BackendResult<()> {
+        Process::spawn("git", &["fetch", "--unshallow"])?.wait()?;
+        Ok(())
+    }
+
     fn pull(&self) -> BackendResult<()> {
         Process::spawn("git", &["pull", "--all"])?.wait()?;
         Ok(())
@@ -231,6 +572,90 @@ impl Backend for Git {
         Ok(())
     }
 
+    fn push_branches(&self, names: &[String]) -> BackendResult<()> {
+        self.push_refs(&[], names.iter().map(|n| format!("refs/heads/{}", n)))
+    }
+
+    fn push_tags(&self, names: &[String]) -> BackendResult<()> {
+        self.push_refs(&[], names.iter().map(|n| format!("refs/tags/{}", n)))
+    }
+
+    fn push_all_tags(&self) -> BackendResult<()> {
+        self.push_refs(&["--tags"], std::iter::empty())
+    }
+
+    // what each branch tracks is deleted, it can be on another remote or have another name there,
+    // a branch tracking nothing is deleted under its own name from the default remote
+    // a branch tracking a local one has "." as its remote, that upstream is not on any remote
+    fn remote_branch_refs(&self, names: &[String]) -> BackendResult<Vec<(String, String)>> {
+        let remotes = Process::spawn("git", &["remote"])?.wait()?;
+        let format = "--format=%(refname:short)%00%(upstream:remotename)%00%(upstream:remoteref)";
+        let upstreams = Process::spawn("git", &["for-each-ref", format, "refs/heads"])?.wait()?;
+        let upstreams: HashMap<_, _> = upstreams
+            .lines()
+            .filter_map(|line| {
+                let mut splits = line.splitn(3, '\0');
+                let (name, remote, remote_ref) = (splits.next()?, splits.next()?, splits.next()?);
+                let is_remote = remotes.lines().any(|r| r == remote);
+                (is_remote && !remote_ref.is_empty()).then_some((name, (remote, remote_ref)))
+            })
+            .collect();
+
+        let mut refs = Vec::new();
+        for name in names {
+            refs.push(match upstreams.get(name.as_str()) {
+                Some(&(remote, remote_ref)) => (remote.to_owned(), remote_ref.to_owned()),
+                None => (self.remote()?, format!("refs/heads/{}", name)),
+            });
+        }
+        Ok(refs)
+    }
+
+    fn delete_remote_branches(&self, refs: &[(String, String)]) -> BackendResult<()> {
+        let mut refs_by_remote: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (remote, remote_ref) in refs {
+            refs_by_remote.entry(remote).or_default().push(remote_ref);
+        }
+        for (remote, refs) in refs_by_remote {
+            let mut args = vec!["push", "--delete", remote];
+            args.extend(refs);
+            Process::spawn("git", &args)?.wait()?;
+        }
+        Ok(())
+    }
+
+    fn delete_remote_tags(&self, names: &[String]) -> BackendResult<()> {
+        self.push_refs(&["--delete"], names.iter().map(|n| format!("refs/tags/{}", n)))
+    }
+
+    fn push_force(&self) -> BackendResult<()> {
+        Process::spawn("git", &["push", "--force-with-lease"])?.wait()?;
+        Ok(())
+    }
+
+    fn push_preview(&self) -> BackendResult<String> {
+        let refs = Process::spawn("git", &["push", "--dry-run", "--porcelain"])?.wait()?;
+        // @{push} is missing when the branch was never pushed, then show everything not on any remote
+        let commits = match Process::spawn("git", &["log", "--oneline", "--no-decorate", "@{push}..HEAD"])?.wait() {
+            Ok(commits) => commits,
+            Err(_) => Process::spawn("git", &["log", "--oneline", "--no-decorate", "HEAD", "--not", "--remotes"])?.wait()?,
+        };
+
+        let mut preview = String::new();
+        preview.push_str("refs to push:\n");
+        for line in refs.lines().filter(|l| l.contains('\t')) {
+            preview.push_str(line.trim_start());
+            preview.push('\n');
+        }
+        preview.push_str("\ncommits to push:\n");
+        if commits.trim().is_empty() {
+            preview.push_str("none\n");
+        } else {
+            preview.push_str(&commits);
+        }
+        Ok(preview)
+    }
+
     fn push_gerrit(&self) -> BackendResult<()> {
         let remote = self.remote()?;
         let current_branch = self.current_branch()?;
@@ -256,6 +681,12 @@ impl Backend for Git {
         Ok(())
     }
 
+    // untracked files included, so that nothing is left in the way of a hard reset
+    fn stash_all(&self, message: &str) -> BackendResult<()> {
+        Process::spawn("git", &["stash", "push", "--include-untracked", "-m", message])?.wait()?;
+        Ok(())
+    }
+
     fn stash_list(&self) -> BackendResult<Vec<StashEntry>> {
         let entries = Process::spawn("git", &["stash", "list"])?
             .wait()?
@@ -290,21 +721,233 @@ impl Backend for Git {
         Ok(())
     }
 
-    fn reset(&self, revision: &str) -> BackendResult<()> {
-        let output = Process::spawn("git", &["status", "--null"])?.wait()?;
-        if !output.is_empty() {
+    fn reset(&self, revision: &str, mode: ResetMode) -> BackendResult<()> {
+        // only a hard reset loses the uncommitted changes
+        if mode == ResetMode::Hard && !Process::spawn("git", &["status", "--null"])?.wait()?.is_empty() {
             return Err("There are local changes! Please stash / commit / discard first.".to_owned());
         }
         let revision = if revision == "" { self.remote_branch()? } else { revision.to_owned() };
-        Process::spawn("git", &["reset", "--hard", &revision])?.wait()?;
+        self.with_undo(format!("{} reset to {}", mode.as_str(), revision), true, || {
+            Process::spawn("git", &["reset", &format!("--{}", mode.as_str()), &revision])?.wait()?;
+            Ok(())
+        })
+    }
+
+    // the local commits missing from the remote branch a reset to it goes to, and the changes it would refuse to lose
+    fn reset_preview(&self) -> BackendResult<(String, bool)> {
+        let range = format!("{}..HEAD", self.remote_branch()?);
+        let commits = Process::spawn("git", &["log", "--oneline", "--no-decorate", &range])?.wait()?;
+        let local_changes = !Process::spawn("git", &["status", "--null"])?.wait()?.is_empty();
+        Ok((commits, local_changes))
+    }
+
+    fn autosquash(&self, revision: &str) -> BackendResult<()> {
+        let parent = format!("{}~", revision);
+        let base = match Process::spawn("git", &["rev-parse", "--verify", "--quiet", &parent])?.wait() {
+            Ok(_) => &parent[..],
+            Err(_) => "--root",
+        };
+        self.with_undo(format!("autosquash onto {}", revision), true, || {
+            // the generated todo list is taken as is, so the rebase does not stop for it,
+            // and the local changes are put back once it is done
+            let args = ["rebase", "--interactive", "--autosquash", "--autostash", base];
+            Process::spawn_with_env("git", &args, &[("GIT_SEQUENCE_EDITOR", ":")])?.wait()?;
+            Ok(())
+        })
+    }
+
+    // the messages oldest first, and how many of the commits are on a remote already
+    fn squash_preview(&self, range: &str) -> BackendResult<(String, usize)> {
+        let range = root_range(range).unwrap_or(range);
+        let messages = Process::spawn("git", &["log", "--reverse", "--format=%B%x00", range])?.wait()?;
+        let messages: Vec<_> = messages.split('\0').map(str::trim).filter(|m| !m.is_empty()).collect();
+
+        let count = |args: &[&str]| -> BackendResult<usize> {
+            let count = Process::spawn("git", args)?.wait()?;
+            count.trim().parse().map_err(|_| format!("invalid commit count '{}'", count.trim()))
+        };
+        let commit_count = count(&["rev-list", "--count", range])?;
+        let unpushed_count = count(&["rev-list", "--count", range, "--not", "--remotes"])?;
+        Ok((messages.join("\n\n"), commit_count - unpushed_count))
+    }
+
+    // the tree of the newest commit is committed once on the parent of the oldest, with the author of the oldest,
+    // then the commits after the range are moved on top of it
+    fn squash(&self, range: &str, message: &str) -> BackendResult<()> {
+        let (base, newest) = range.split_once("..").ok_or_else(|| format!("invalid range '{}'", range))?;
+        if Process::spawn("git", &["merge-base", "--is-ancestor", newest, "HEAD"])?.wait().is_err() {
+            return Err(format!("{} is not in the history of the current branch", newest));
+        }
+
+        let oldest = base.trim_end_matches('~');
+        let author = Process::spawn("git", &["log", "-1", "--format=%an%x00%ae%x00%aD", oldest])?.wait()?;
+        let mut author = author.trim_end_matches('\n').split('\0');
+        let (name, email, date) = (author.next().unwrap_or(""), author.next().unwrap_or(""), author.next().unwrap_or(""));
+        let envs = [("GIT_AUTHOR_NAME", name), ("GIT_AUTHOR_EMAIL", email), ("GIT_AUTHOR_DATE", date)];
+        let tree = format!("{}^{{tree}}", newest);
+        // squashing from a root commit makes a new root
+        let mut args = vec!["commit-tree", &tree, "-m", message];
+        if root_range(range).is_none() {
+            args.extend(["-p", base]);
+        }
+        let squashed = Process::spawn_with_env("git", &args, &envs)?.wait()?;
+
+        self.with_undo(format!("squash {}", range), true, || {
+            Process::spawn("git", &["rebase", "--onto", squashed.trim(), newest])?.wait()?;
+            Ok(())
+        })
+    }
+
+    // with an upstream only the commits after it are transplanted, like "rebase --onto"
+    // numbered even when there is one patch, the paths are printed as they are written
+    fn format_patches(&self, range: &str, directory: &Path) -> BackendResult<Vec<String>> {
+        let directory = directory.to_string_lossy();
+        let mut args = vec!["format-patch", "--cover-letter", "--numbered", "--output-directory", &directory];
+        // a single revision would be the commits since it, not the ones up to it
+        match root_range(range) {
+            Some(newest) => args.extend(["--root", newest]),
+            None => args.push(range),
+        }
+        let output = Process::spawn("git", &args)?.wait()?;
+        Ok(output.lines().map(str::to_owned).collect())
+    }
+
+    fn archive(&self, revision: &str, path: &str, prefix: &str) -> BackendResult<()> {
+        // without the trailing slash, the prefix would be glued to the file names
+        let separator = if prefix.is_empty() || prefix.ends_with('/') { "" } else { "/" };
+        let output = format!("--output={}", path);
+        let prefix = format!("--prefix={}{}", prefix, separator);
+        Process::spawn("git", &["archive", &output, &prefix, revision])?.wait()?;
         Ok(())
     }
 
-    fn revision_details(&self, revision: &str) -> BackendResult<RevisionInfo> {
-        let message = Process::spawn("git", &["show", "-s", "--format=%B", "--no-renames", revision])?;
+    fn rebase(&self, onto: &str, upstream: Option<&str>) -> BackendResult<()> {
+        // the upstream is typed in, resolved first so that it cannot be taken for an option
+        let upstream = match upstream {
+            Some(upstream) if upstream.starts_with('-') => return Err(format!("'{}' is not a commit", upstream)),
+            Some(upstream) => {
+                let commit = format!("{}^{{commit}}", upstream);
+                let hash = Process::spawn("git", &["rev-parse", "--verify", "--quiet", &commit])?.wait();
+                Some(hash.map_err(|_| format!("'{}' is not a commit", upstream))?.trim().to_owned())
+            }
+            None => None,
+        };
+        self.with_undo(format!("rebase onto {}", onto), true, || {
+            match upstream.as_deref() {
+                Some(upstream) => Process::spawn("git", &["rebase", "--onto", onto, upstream])?.wait()?,
+                None => Process::spawn("git", &["rebase", onto])?.wait()?,
+            };
+            Ok(())
+        })
+    }
+
+    // checks for the files git leaves in its directory while the operation is stopped
+    fn operation(&self) -> Option<Operation> {
+        let exists = |name: &str| self.git_dir.join(name).exists();
+        if exists("rebase-merge") || exists("rebase-apply") {
+            Some(Operation::Rebase)
+        } else if exists("MERGE_HEAD") {
+            Some(Operation::Merge)
+        } else if exists("CHERRY_PICK_HEAD") {
+            Some(Operation::CherryPick)
+        } else if exists("REVERT_HEAD") {
+            Some(Operation::Revert)
+        } else {
+            None
+        }
+    }
+
+    // files that were in conflict are marked as resolved first, unless they still have conflict markers
+    fn operation_continue(&self) -> BackendResult<()> {
+        let unmerged = Process::spawn("git", &["diff", "--name-only", "--diff-filter=U", "-z"])?.wait()?;
+        let unmerged: Vec<_> = unmerged.split('\0').filter(|name| !name.is_empty()).collect();
+        for name in &unmerged {
+            let content = fs::read_to_string(name).unwrap_or_default();
+            if content.lines().any(|line| line.starts_with("<<<<<<< ") || line.starts_with(">>>>>>> ")) {
+                return Err(format!("'{}' still has conflict markers", name));
+            }
+        }
+        if !unmerged.is_empty() {
+            let mut args = vec!["add", "--"];
+            args.extend(unmerged);
+            Process::spawn("git", &args)?.wait()?;
+        }
+
+        self.operation_command("--continue")
+    }
+
+    fn operation_skip(&self) -> BackendResult<()> {
+        if let Some(Operation::Merge) = self.operation() {
+            return Err("a merge can not be skipped, abort it instead".to_owned());
+        }
+        self.operation_command("--skip")
+    }
+
+    fn operation_abort(&self) -> BackendResult<()> {
+        self.operation_command("--abort")
+    }
+
+    fn undo_list(&self) -> Vec<UndoEntry> {
+        self.undo_stack.lock().unwrap().clone()
+    }
+
+    fn undo(&self) -> BackendResult<()> {
+        let entry = match self.undo_stack.lock().unwrap().pop() {
+            Some(entry) => entry,
+            None => return Err("nothing to undo".to_owned()),
+        };
+
+        let result = match &entry.branch {
+            Some(branch) => Process::spawn("git", &["checkout", branch])?.wait().map(|_| ()),
+            None => Process::spawn("git", &["checkout", "--detach", &entry.head])?.wait().map(|_| ()),
+        };
+        // "--keep" refuses to throw away local changes, unlike "--hard"
+        let result = result.and_then(|_| match entry.reset {
+            true => Process::spawn("git", &["reset", "--keep", &entry.head])?.wait().map(|_| ()),
+            false => Ok(()),
+        });
+
+        if result.is_err() {
+            self.undo_stack.lock().unwrap().push(entry);
+        }
+        result
+    }
+
+    fn ci_status(&self, url_template: &str, token: Option<&str>, revision: &str) -> BackendResult<CiStatus> {
+        let hash = Process::spawn("git", &["rev-parse", revision])?.wait()?.trim().to_owned();
+        if let Some(status) = self.ci_status_cache.lock().unwrap().get(&hash) {
+            return Ok(*status);
+        }
+
+        let url = url_template.replace("{hash}", &hash);
+        let args = ["--silent", "--fail", "--max-time", "10", "--header", "Accept: application/json", &url];
+        // the token goes through a config read from stdin, as anyone can read the args of a process
+        let output = match token {
+            Some(token) => {
+                let token = token.replace('\\', "\\\\").replace('"', "\\\"");
+                let config = format!("header = \"Authorization: Bearer {}\"\n", token);
+                let mut args = args.to_vec();
+                args.extend(["--config", "-"]);
+                Process::spawn_with_input("curl", &args, &config)?.wait()?
+            }
+            None => Process::spawn("curl", &args)?.wait()?,
+        };
+        let status = CiStatus::parse(&output).ok_or_else(|| format!("no ci status found for {}", hash))?;
+        if status.is_final() {
+            self.ci_status_cache.lock().unwrap().insert(hash, status);
+        }
+        Ok(status)
+    }
+
+    fn revision_details(&self, revision: &str, date_format: &DateFormat) -> BackendResult<RevisionInfo> {
+        let date_arg = date_arg(date_format);
+        let message = Process::spawn("git", &["show", "-s", &date_arg, "--format=%ad%n%B", "--no-renames", revision])?;
         let changes = Process::spawn("git", &["diff-tree", "--no-commit-id", "--name-status", "-r", "-z", revision])?;
 
-        let message = message.wait()?.trim().into();
+        let message = message.wait()?;
+        let (date, message) = message.split_once('\n').unwrap_or((&message, ""));
+        let (date, message) = (date.into(), message.trim().into());
+        let notes = self.note(revision)?;
 
         let changes = changes.wait()?;
         let mut splits = changes.split('\0');
@@ -323,7 +966,40 @@ impl Backend for Git {
             entries.push(RevisionEntry::new(name, status));
         }
 
-        Ok(RevisionInfo { message, entries })
+        Ok(RevisionInfo { date, message, notes, entries })
+    }
+
+    // empty when no tag is reachable from the commit
+    fn describe(&self, revision: &str) -> BackendResult<String> {
+        let (found, output, _) = Process::spawn("git", &["describe", "--tags", revision])?.wait_output()?;
+        Ok(if found { output.trim().into() } else { String::new() })
+    }
+
+    fn refs(&self, revision: &str) -> BackendResult<String> {
+        let output = Process::spawn("git", &["log", "--no-walk", "--format=%D", revision])?.wait()?;
+        Ok(without_grafted(output.trim()))
+    }
+
+    // empty when the commit has no note
+    fn note(&self, revision: &str) -> BackendResult<String> {
+        let output = Process::spawn("git", &["show", "-s", "--format=%N", revision])?.wait()?;
+        Ok(output.trim_end().into())
+    }
+
+    // full hashes of the commits with a note
+    fn noted_revisions(&self) -> BackendResult<Vec<String>> {
+        let output = Process::spawn("git", &["notes", "list"])?.wait()?;
+        Ok(output.lines().filter_map(|l| l.split_once(' ')).map(|(_, revision)| revision.into()).collect())
+    }
+
+    fn set_note(&self, revision: &str, note: &str) -> BackendResult<()> {
+        Process::spawn("git", &["notes", "add", "--force", "--message", note, revision])?.wait()?;
+        Ok(())
+    }
+
+    fn remove_note(&self, revision: &str) -> BackendResult<()> {
+        Process::spawn("git", &["notes", "remove", "--ignore-missing", revision])?.wait()?;
+        Ok(())
     }
 
     fn branches(&self) -> BackendResult<Vec<BranchEntry>> {
@@ -342,7 +1018,7 @@ impl Backend for Git {
             let mut splits = l.splitn(2, ' ');
             let name = splits.next().unwrap_or("").into();
             let checked_out = splits.next().unwrap_or("") == "*";
-            BranchEntry { name, checked_out }
+            BranchEntry { selected: false, name, checked_out }
         })
         .collect();
         Ok(entries)
@@ -365,11 +1041,192 @@ impl Backend for Git {
         Ok(())
     }
 
+    // git goes on past the branches it cannot delete and reports each of them
+    fn delete_branches(&self, names: &[String], force: bool) -> BackendResult<()> {
+        let delete_option = if force { "-D" } else { "--delete" };
+        let mut args = vec!["branch", delete_option, "--"];
+        args.extend(names.iter().map(String::as_str));
+        Process::spawn("git", &args)?.wait()?;
+        Ok(())
+    }
+
+    // the default branch itself and the checked out one are never listed
+    fn merged_branches(&self) -> BackendResult<(String, Vec<String>)> {
+        let default_branch = self.default_branch()?;
+        let current_branch = self.current_branch().unwrap_or_default();
+        let branches = Process::spawn("git", &["branch", "--merged", &default_branch, "--format=%(refname:short)"])?
+            .wait()?
+            .lines()
+            .filter(|&b| b != default_branch && b != current_branch)
+            .map(String::from)
+            .collect();
+        Ok((default_branch, branches))
+    }
+
+    fn tree(&self, revision: &str) -> BackendResult<Vec<TreeEntry>> {
+        // "<mode> <type> <object>\t<path>", directories come before their content
+        let output = Process::spawn("git", &["ls-tree", "-r", "-t", "-z", "--full-tree", revision])?.wait()?;
+        let entries = output
+            .split('\0')
+            .filter_map(|e| e.split_once('\t'))
+            .map(|(info, path)| TreeEntry { path: path.into(), directory: info.split(' ').nth(1) == Some("tree") })
+            .collect();
+        Ok(entries)
+    }
+
+    fn file_content(&self, revision: &str, path: &str) -> BackendResult<String> {
+        let content = Process::spawn("git", &["show", &format!("{}:{}", revision, path)])?.wait()?;
+        if content.contains('\0') {
+            return Err(format!("{} is a binary file", path));
+        }
+        Ok(content)
+    }
+
+    // searches the working tree without a revision
+    fn grep(&self, pattern: &str, revision: Option<&str>) -> BackendResult<Vec<GrepEntry>> {
+        // "<path>\0<line>\0<text>", prefixed with "<revision>:" when searching a revision
+        let mut args = vec!["grep", "--line-number", "--null", "--full-name", "-I", "-e", pattern];
+        args.extend(revision);
+        let output = match Process::spawn("git", &args)?.wait() {
+            Ok(output) => output,
+            Err(error) if error.trim().is_empty() => return Ok(Vec::new()), // nothing matched
+            Err(error) => return Err(error),
+        };
+
+        let prefix = revision.map(|r| format!("{}:", r)).unwrap_or_default();
+        let mut entries = Vec::new();
+        for line in output.lines() {
+            let mut splits = line.splitn(3, '\0');
+            let path = splits.next().unwrap_or("");
+            let path = path.strip_prefix(&prefix).unwrap_or(path).into();
+            let line = splits.next().and_then(|l| l.parse().ok()).unwrap_or(0);
+            let text = splits.next().unwrap_or("").into();
+            entries.push(GrepEntry { path, line, text });
+        }
+        Ok(entries)
+    }
+
+    // the commits in the range, each preceded by the annotated tags pointing at it
+    fn signatures(&self, range: &str) -> BackendResult<Vec<SignatureEntry>> {
+        // lightweight tags have no object to peel and no signature
+        let tags =
+            Process::spawn("git", &["for-each-ref", "refs/tags", "--format=%(refname:short)%00%(*objectname)"])?.wait()?;
+        let mut tags_by_commit = HashMap::new();
+        for (name, commit) in tags.lines().filter_map(|l| l.split_once('\0')).filter(|(_, c)| !c.is_empty()) {
+            tags_by_commit.entry(commit).or_insert_with(Vec::new).push(name);
+        }
+
+        let template = "--format=%H%x00%h%x00%G?%x00%GK%x00%GS%x00%GT%x00%s";
+        let output = Process::spawn("git", &["log", template, range, "--"])?.wait()?;
+
+        let mut entries = Vec::new();
+        for line in output.lines() {
+            let splits: Vec<_> = line.splitn(7, '\0').collect();
+            if let [hash, short_hash, status, key, signer, trust, subject] = splits[..] {
+                for name in tags_by_commit.get(hash).into_iter().flatten() {
+                    let (verified, _, status_lines) = Process::spawn("git", &["verify-tag", "--raw", name])?.wait_output()?;
+                    entries.push(tag_signature(name, short_hash, verified, &status_lines));
+                }
+                entries.push(SignatureEntry {
+                    hash: short_hash.into(),
+                    tag: None,
+                    subject: subject.into(),
+                    status: SignatureStatus::from_code(status),
+                    key: key.into(),
+                    signer: signer.into(),
+                    trust: trust.into(),
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn blame(&self, revision: &str, path: &str) -> BackendResult<Vec<BlameEntry>> {
+        // every line gets a "<hash> <original line> <final line>" header, then "key value" lines and "\t<text>"
+        let output = Process::spawn("git", &["blame", "--line-porcelain", revision, "--", path])?.wait()?;
+
+        let mut entries = Vec::new();
+        let mut entry: Option<BlameEntry> = None;
+        for line in output.lines() {
+            if let Some(text) = line.strip_prefix('\t') {
+                if let Some(mut entry) = entry.take() {
+                    entry.text = text.into();
+                    entries.push(entry);
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match (&mut entry, key) {
+                (None, hash) => {
+                    let original_line = value.split(' ').next().and_then(|l| l.parse().ok()).unwrap_or(1);
+                    entry = Some(BlameEntry {
+                        hash: hash.into(),
+                        author: String::new(),
+                        date: String::new(),
+                        path: path.into(),
+                        original_line,
+                        previous: None,
+                        text: String::new(),
+                    });
+                }
+                (Some(entry), "author") => entry.author = value.into(),
+                (Some(entry), "author-time") => entry.date = tool::date_from_unix_time(value.parse().unwrap_or(0)),
+                (Some(entry), "filename") => entry.path = value.into(),
+                (Some(entry), "previous") => {
+                    entry.previous = value.split_once(' ').map(|(hash, path)| (hash.to_owned(), path.to_owned()))
+                }
+                _ => (),
+            }
+        }
+        Ok(entries)
+    }
+
+    fn sparse_checkout_list(&self) -> BackendResult<Vec<SparseEntry>> {
+        if !self.sparse_checkout_enabled() {
+            return Ok(Vec::new());
+        }
+        let entries = Process::spawn("git", &["sparse-checkout", "list"])?
+            .wait()?
+            .lines()
+            .map(|l| SparseEntry { directory: l.into() })
+            .collect();
+        Ok(entries)
+    }
+
+    fn sparse_checkout_add(&self, directory: &str) -> BackendResult<()> {
+        // the first directory turns sparse checkout on, in cone mode only the top level files are kept besides it
+        if self.sparse_checkout_enabled() {
+            Process::spawn("git", &["sparse-checkout", "add", directory])?.wait()?;
+        } else {
+            Process::spawn("git", &["sparse-checkout", "set", "--cone", directory])?.wait()?;
+        }
+        Ok(())
+    }
+
+    fn sparse_checkout_remove(&self, directory: &str) -> BackendResult<()> {
+        let directories = Process::spawn("git", &["sparse-checkout", "list"])?.wait()?;
+        let mut args = vec!["sparse-checkout", "set"];
+        args.extend(directories.lines().filter(|&d| d != directory));
+        Process::spawn("git", &args)?.wait()?;
+        Ok(())
+    }
+
+    fn sparse_checkout_reapply(&self) -> BackendResult<()> {
+        Process::spawn("git", &["sparse-checkout", "reapply"])?.wait()?;
+        Ok(())
+    }
+
+    fn sparse_checkout_disable(&self) -> BackendResult<()> {
+        Process::spawn("git", &["sparse-checkout", "disable"])?.wait()?;
+        Ok(())
+    }
+
     fn tags(&self) -> BackendResult<Vec<TagEntry>> {
         let entries = Process::spawn("git", &["tag", "--list", "--format=%(refname:short)"])?
             .wait()?
             .lines()
-            .map(|l| TagEntry { name: l.into() })
+            .map(|l| TagEntry { selected: false, name: l.into() })
             .collect();
         Ok(entries)
     }
@@ -387,6 +1244,215 @@ impl Backend for Git {
         //Process::spawn("git", &["push", "--delete", remote.trim(), name])?.wait()?;
         Ok(())
     }
+
+    fn maintenance(&self, task: MaintenanceTask, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        // progress is only printed to a terminal unless asked for, gc has no option for it
+        let args: &[&str] = match task {
+            MaintenanceTask::Gc => &["gc"],
+            MaintenanceTask::Prune => &["prune", "--verbose", "--progress"],
+            MaintenanceTask::Fsck => &["fsck", "--progress"],
+            MaintenanceTask::CountObjects => &["count-objects", "--verbose"],
+            MaintenanceTask::CheckRemotes(timeout) => return check_remotes(timeout, on_line),
+        };
+        Process::spawn("git", args)?.stream(on_line)
+    }
+
+    fn repository_size(&self) -> BackendResult<RepositorySize> {
+        let output = Process::spawn("git", &["count-objects", "--verbose"])?.wait()?;
+        let mut size = RepositorySize::default();
+        for (key, value) in output.lines().filter_map(|l| l.split_once(": ")) {
+            let value = value.trim().parse().unwrap_or(0);
+            match key {
+                "count" => size.loose_count = value,
+                "size" => size.loose_size = value,
+                "in-pack" => size.packed_count = value,
+                "packs" => size.pack_count = value,
+                "size-pack" => size.pack_size = value,
+                "size-garbage" => size.garbage_size = value,
+                _ => (),
+            }
+        }
+        Ok(size)
+    }
+
+    fn setup_info(&self) -> BackendResult<SetupInfo> {
+        let version = Process::spawn("git", &["--version"])?.wait()?;
+        // git config fails for a key that is not set
+        let config = |key: &str| -> Option<String> {
+            let value = Process::spawn("git", &["config", key]).and_then(Process::wait).ok()?;
+            Some(value.trim().to_owned()).filter(|v| !v.is_empty())
+        };
+        Ok(SetupInfo {
+            git_version: version.trim().trim_start_matches("git version ").to_owned(),
+            user_name: config("user.name"),
+            user_email: config("user.email"),
+        })
+    }
+
+    fn set_config(&self, key: &str, value: &str, global: bool) -> BackendResult<()> {
+        let scope = if global { "--global" } else { "--local" };
+        Process::spawn("git", &["config", scope, key, value])?.wait()?;
+        Ok(())
+    }
+
+    // the command runs through the shell from the repository root, so it can use pipes and its own quoting
+    fn run_command(&self, command: &str, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        Process::spawn(shell, &[flag, command])?.stream(on_line)
+    }
+}
+
+// gpg reports the verification through "[GNUPG:] <KEYWORD> <arguments>" status lines
+fn tag_signature(name: &str, short_hash: &str, verified: bool, status_lines: &str) -> SignatureEntry {
+    let mut entry = SignatureEntry {
+        hash: short_hash.into(),
+        tag: Some(name.into()),
+        subject: String::new(),
+        status: SignatureStatus::Unsigned,
+        key: String::new(),
+        signer: String::new(),
+        trust: String::new(),
+    };
+    for line in status_lines.lines() {
+        let mut words = match line.strip_prefix("[GNUPG:] ") {
+            Some(line) => line.splitn(3, ' '),
+            None => continue,
+        };
+        let keyword = words.next().unwrap_or("");
+        let key = words.next().unwrap_or("").to_owned();
+        let signer = words.next().unwrap_or("").to_owned();
+        entry.status = match keyword {
+            "GOODSIG" => SignatureStatus::Good,
+            "BADSIG" => SignatureStatus::Bad,
+            "EXPSIG" => SignatureStatus::Expired,
+            "EXPKEYSIG" => SignatureStatus::ExpiredKey,
+            "REVKEYSIG" => SignatureStatus::RevokedKey,
+            "ERRSIG" => SignatureStatus::MissingKey,
+            _ => {
+                if let Some(trust) = keyword.strip_prefix("TRUST_") {
+                    entry.trust = trust.to_lowercase();
+                }
+                continue;
+            }
+        };
+        entry.key = key;
+        entry.signer = signer;
+    }
+    // a good signature from a key that is not trusted, as git's %G? tells it
+    if entry.status == SignatureStatus::Good && matches!(entry.trust.as_str(), "" | "undefined" | "never") {
+        entry.status = SignatureStatus::GoodUnknownValidity;
+    }
+    // ssh signatures have no gpg status lines
+    if verified && entry.status == SignatureStatus::Unsigned {
+        entry.status = SignatureStatus::Good;
+    }
+    entry
+}
+
+// each remote is asked for its branches, a line tells how long it took or why it failed;
+// git does not prompt for https credentials meanwhile, missing ones are reported as an authentication problem
+fn check_remotes(timeout: Duration, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+    let remotes = Process::spawn("git", &["remote"])?.wait()?;
+    if remotes.trim().is_empty() {
+        return Err("no remote configured".to_owned());
+    }
+
+    // ssh fails instead of asking for a passphrase or a host key confirmation, on top of the ssh command set up
+    let ssh_command = match env::var("GIT_SSH_COMMAND") {
+        Ok(command) => command,
+        Err(_) => Process::spawn("git", &["config", "core.sshCommand"])?.wait().unwrap_or_else(|_| "ssh".to_owned()),
+    };
+    let ssh_command = format!("{} -o BatchMode=yes", ssh_command.trim());
+
+    for remote in remotes.lines() {
+        let url = Process::spawn("git", &["remote", "get-url", "--push", remote])?.wait().unwrap_or_default();
+        let start = Instant::now();
+        let result = Process::spawn_with_env(
+            "git",
+            &["ls-remote", "--heads", remote],
+            &[("GIT_TERMINAL_PROMPT", "0"), ("GIT_ASKPASS", ""), ("GIT_SSH_COMMAND", &ssh_command)],
+        )?
+        .wait_timeout(timeout);
+        let seconds = start.elapsed().as_secs_f64();
+
+        let health = match result {
+            Ok(Some(heads)) => format!("reachable in {:.2} s, {} branches", seconds, heads.lines().count()),
+            Ok(None) => format!("no answer within {} s, a network problem", timeout.as_secs()),
+            Err(error) => {
+                let error = error.trim();
+                let lowercase = error.to_ascii_lowercase();
+                let auth_errors =
+                    ["authentication", "could not read username", "could not read password", "permission denied"];
+                let problem = match auth_errors.iter().any(|e| lowercase.contains(e)) {
+                    true => "an authentication problem",
+                    false => "a network problem",
+                };
+                format!("failed after {:.2} s, {}: {}", seconds, problem, error.lines().last().unwrap_or(""))
+            }
+        };
+        on_line(&format!("{} {}: {}", remote, url.trim(), health), false);
+    }
+    Ok(())
+}
+
+// the whole work tree from its top, whatever the current directory, but the excluded paths
+fn work_tree_pathspecs(excluded: &[&str]) -> Vec<String> {
+    let excluded = excluded.iter().map(|path| format!(":(top,exclude){}", path));
+    std::iter::once(":/".to_owned()).chain(excluded).collect()
+}
+
+// the "%ad" of the format templates
+fn date_arg(date_format: &DateFormat) -> String {
+    let local = if date_format.local { "-local" } else { "" };
+    format!("--date=format{}:{}", local, date_format.pattern)
+}
+
+// git decorates the shallow boundary commits with "grafted"
+fn without_grafted(refs: &str) -> String {
+    refs.split(", ").filter(|&r| r != "grafted").collect::<Vec<_>>().join(", ")
+}
+
+// the path goes last, after "--"
+fn log_filter_args(filter: &LogFilter) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(author) = &filter.author {
+        args.push("--fixed-strings".to_owned());
+        args.push(format!("--author={}", author));
+    }
+    if let Some(since) = &filter.since {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(until) = &filter.until {
+        args.push(format!("--until={}", until));
+    }
+    if filter.first_parent {
+        args.push("--first-parent".to_owned());
+    }
+    match filter.merges {
+        Some(true) => args.push("--merges".to_owned()),
+        Some(false) => args.push("--no-merges".to_owned()),
+        None => (),
+    }
+    if let Some(range) = &filter.range {
+        args.push(root_range(range).unwrap_or(range).to_owned());
+    }
+    if let Some(path) = &filter.path {
+        args.push("--".to_owned());
+        args.push(path.clone());
+    }
+    args
+}
+
+// the ranges marked in the log start from the parent of their oldest commit, "oldest~..newest", which a root commit
+// does not have: the range is then the whole history of its newest commit, returned here
+fn root_range(range: &str) -> Option<&str> {
+    let (base, newest) = range.split_once("..")?;
+    base.strip_suffix('~')?;
+    let parent = format!("{}^{{commit}}", base);
+    match Process::spawn("git", &["rev-parse", "--verify", "--quiet", &parent]).ok()?.wait() {
+        Ok(_) => None,
+        Err(_) => Some(newest),
+    }
 }
 
 fn parse_file_status(s: &str) -> FileStatus {
@@ -0,0 +1,455 @@
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    env,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use super::{
+    AuthorEntry, Backend, BackendResult, BlameEntry, BranchEntry, CiStatus, DateFormat, GrepEntry, LogEntry, LogFilter,
+    MaintenanceTask, Operation, RepositorySize, ResetMode, RevisionEntry, RevisionInfo, SetupInfo, SignatureEntry,
+    SparseEntry, StashEntry, StatusInfo, TagEntry, TreeEntry, UndoEntry,
+};
+
+type Response = Box<dyn Any + Send>;
+
+// answers with scripted responses instead of running git, so the modes can be driven by tests
+// every call is recorded; the ones only changing the repository succeed when nothing was scripted for them
+pub struct MockBackend {
+    bare: bool,
+    git_dir: PathBuf,
+    operation: Mutex<Option<Operation>>,
+    responses: Mutex<HashMap<&'static str, VecDeque<Response>>>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl MockBackend {
+    pub fn new(bare: bool) -> Self {
+        Self {
+            bare,
+            git_dir: env::temp_dir(),
+            operation: Mutex::new(None),
+            responses: Mutex::new(HashMap::new()),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    // queued per method, the type must be the one the method returns (the lines for the streaming ones)
+    pub fn script<T: 'static + Send>(&self, method: &'static str, response: BackendResult<T>) {
+        self.responses.lock().unwrap().entry(method).or_default().push_back(Box::new(response));
+    }
+
+    pub fn set_operation(&self, operation: Option<Operation>) {
+        *self.operation.lock().unwrap() = operation;
+    }
+
+    // each call as the method name followed by its arguments, like "checkout main"
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn answer<T: 'static>(&self, method: &'static str, args: String) -> Option<BackendResult<T>> {
+        let call = if args.is_empty() { method.to_owned() } else { format!("{} {}", method, args) };
+        self.calls.lock().unwrap().push(call);
+
+        let response = self.responses.lock().unwrap().get_mut(method).and_then(VecDeque::pop_front)?;
+        match response.downcast::<BackendResult<T>>() {
+            Ok(response) => Some(*response),
+            Err(_) => panic!("the response scripted for '{}' has the wrong type", method),
+        }
+    }
+
+    fn call<T: 'static>(&self, method: &'static str, args: String) -> BackendResult<T> {
+        self.answer(method, args).unwrap_or_else(|| Err(format!("no response scripted for '{}'", method)))
+    }
+
+    fn call_or<T: 'static>(&self, method: &'static str, args: String, default: T) -> BackendResult<T> {
+        self.answer(method, args).unwrap_or(Ok(default))
+    }
+
+    fn stream(&self, method: &'static str, args: String, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        for line in self.call_or::<Vec<String>>(method, args, Vec::new())? {
+            on_line(&line, false);
+        }
+        Ok(())
+    }
+}
+
+fn names(entries: &[RevisionEntry]) -> String {
+    entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>().join(",")
+}
+
+fn excluding(excluded: &[&str]) -> String {
+    match excluded.is_empty() {
+        true => String::new(),
+        false => format!(" excluding {}", excluded.join(",")),
+    }
+}
+
+impl Backend for MockBackend {
+    fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    fn status(&self, excluded: &[&str]) -> BackendResult<StatusInfo> {
+        self.call("status", excluded.join(","))
+    }
+
+    fn commit(&self, message: &str, entries: &[RevisionEntry], amend: bool, excluded: &[&str]) -> BackendResult<()> {
+        let amend = if amend { " --amend" } else { "" };
+        self.call_or("commit", format!("{} {:?}{}{}", names(entries), message, amend, excluding(excluded)), ())
+    }
+
+    fn commit_fixup(&self, revision: &str) -> BackendResult<()> {
+        self.call_or("commit_fixup", revision.to_owned(), ())
+    }
+
+    fn commit_template(&self) -> BackendResult<String> {
+        self.call_or("commit_template", String::new(), String::new())
+    }
+
+    fn has_staged_changes(&self) -> BackendResult<bool> {
+        self.call_or("has_staged_changes", String::new(), false)
+    }
+
+    fn recent_authors(&self, commit_count: usize) -> BackendResult<Vec<String>> {
+        self.call("recent_authors", commit_count.to_string())
+    }
+
+    fn discard(&self, entries: &[RevisionEntry], excluded: &[&str]) -> BackendResult<()> {
+        self.call_or("discard", format!("{}{}", names(entries), excluding(excluded)), ())
+    }
+
+    fn diff(&self, revision: Option<&str>, entries: &[RevisionEntry]) -> BackendResult<String> {
+        self.call("diff", format!("{} {}", revision.unwrap_or("-"), names(entries)).trim_end().to_owned())
+    }
+
+    fn stage_patch(&self, patch: &str) -> BackendResult<()> {
+        self.call_or("stage_patch", format!("{:?}", patch), ())
+    }
+
+    fn resolve_taking_ours(&self, entries: &[RevisionEntry]) -> BackendResult<()> {
+        self.call_or("resolve_taking_ours", names(entries), ())
+    }
+
+    fn resolve_taking_theirs(&self, entries: &[RevisionEntry]) -> BackendResult<()> {
+        self.call_or("resolve_taking_theirs", names(entries), ())
+    }
+
+    fn log(
+        &self,
+        start: usize,
+        len: usize,
+        filter: &LogFilter,
+        _date_format: &DateFormat,
+        decorate_exclude: &[&str],
+    ) -> BackendResult<(usize, Vec<LogEntry>)> {
+        self.call("log", format!("{} {} {}{}", start, len, filter.description(), excluding(decorate_exclude)))
+    }
+
+    fn first_commit_until(&self, date: &str, filter: &LogFilter) -> BackendResult<Option<String>> {
+        self.call("first_commit_until", format!("{} {}", date, filter.description()))
+    }
+
+    fn shortlog(&self, filter: &LogFilter) -> BackendResult<Vec<AuthorEntry>> {
+        self.call("shortlog", filter.description())
+    }
+
+    fn commit_times(&self, filter: &LogFilter) -> BackendResult<Vec<i64>> {
+        self.call("commit_times", filter.description())
+    }
+
+    fn checkout(&self, revision: &str) -> BackendResult<()> {
+        self.call_or("checkout", revision.to_owned(), ())
+    }
+
+    fn merge(&self, revision: &str) -> BackendResult<()> {
+        self.call_or("merge", revision.to_owned(), ())
+    }
+
+    fn lfs_pull(&self) -> BackendResult<()> {
+        self.call_or("lfs_pull", String::new(), ())
+    }
+
+    fn fetch(&self) -> BackendResult<()> {
+        self.call_or("fetch", String::new(), ())
+    }
+
+    fn fetch_remote(&self, name: &str) -> BackendResult<()> {
+        self.call_or("fetch_remote", name.to_owned(), ())
+    }
+
+    fn add_remote(&self, name: &str, url: &str) -> BackendResult<()> {
+        self.call_or("add_remote", format!("{} {}", name, url), ())
+    }
+
+    fn deepen(&self, count: usize) -> BackendResult<()> {
+        self.call_or("deepen", count.to_string(), ())
+    }
+
+    fn unshallow(&self) -> BackendResult<()> {
+        self.call_or("unshallow", String::new(), ())
+    }
+
+    fn pull(&self) -> BackendResult<()> {
+        self.call_or("pull", String::new(), ())
+    }
+
+    fn push(&self) -> BackendResult<()> {
+        self.call_or("push", String::new(), ())
+    }
+
+    fn push_force(&self) -> BackendResult<()> {
+        self.call_or("push_force", String::new(), ())
+    }
+
+    fn push_branches(&self, names: &[String]) -> BackendResult<()> {
+        self.call_or("push_branches", names.join(","), ())
+    }
+
+    fn push_tags(&self, names: &[String]) -> BackendResult<()> {
+        self.call_or("push_tags", names.join(","), ())
+    }
+
+    fn push_all_tags(&self) -> BackendResult<()> {
+        self.call_or("push_all_tags", String::new(), ())
+    }
+
+    fn remote_branch_refs(&self, names: &[String]) -> BackendResult<Vec<(String, String)>> {
+        let refs = names.iter().map(|n| ("origin".to_owned(), format!("refs/heads/{}", n))).collect();
+        self.call_or("remote_branch_refs", names.join(","), refs)
+    }
+
+    fn delete_remote_branches(&self, refs: &[(String, String)]) -> BackendResult<()> {
+        let refs: Vec<_> = refs.iter().map(|(remote, remote_ref)| format!("{} {}", remote, remote_ref)).collect();
+        self.call_or("delete_remote_branches", refs.join(","), ())
+    }
+
+    fn delete_remote_tags(&self, names: &[String]) -> BackendResult<()> {
+        self.call_or("delete_remote_tags", names.join(","), ())
+    }
+
+    fn push_preview(&self) -> BackendResult<String> {
+        self.call_or("push_preview", String::new(), String::new())
+    }
+
+    fn push_gerrit(&self) -> BackendResult<()> {
+        self.call_or("push_gerrit", String::new(), ())
+    }
+
+    fn reset(&self, revision: &str, mode: ResetMode) -> BackendResult<()> {
+        self.call_or("reset", format!("--{} {}", mode.as_str(), revision).trim_end().to_owned(), ())
+    }
+
+    fn reset_preview(&self) -> BackendResult<(String, bool)> {
+        self.call("reset_preview", String::new())
+    }
+
+    fn autosquash(&self, revision: &str) -> BackendResult<()> {
+        self.call_or("autosquash", revision.to_owned(), ())
+    }
+
+    fn squash_preview(&self, range: &str) -> BackendResult<(String, usize)> {
+        self.call("squash_preview", range.to_owned())
+    }
+
+    fn format_patches(&self, range: &str, directory: &Path) -> BackendResult<Vec<String>> {
+        self.call("format_patches", format!("{} {}", range, directory.display()))
+    }
+
+    fn archive(&self, revision: &str, path: &str, prefix: &str) -> BackendResult<()> {
+        self.call_or("archive", format!("{} {} {}", revision, path, prefix), ())
+    }
+
+    fn squash(&self, range: &str, message: &str) -> BackendResult<()> {
+        self.call_or("squash", format!("{} {}", range, message), ())
+    }
+
+    fn rebase(&self, onto: &str, upstream: Option<&str>) -> BackendResult<()> {
+        self.call_or("rebase", format!("{} {}", onto, upstream.unwrap_or("")).trim_end().to_owned(), ())
+    }
+
+    fn operation(&self) -> Option<Operation> {
+        *self.operation.lock().unwrap()
+    }
+
+    fn operation_continue(&self) -> BackendResult<()> {
+        self.call_or("operation_continue", String::new(), ())
+    }
+
+    fn operation_skip(&self) -> BackendResult<()> {
+        self.call_or("operation_skip", String::new(), ())
+    }
+
+    fn operation_abort(&self) -> BackendResult<()> {
+        self.call_or("operation_abort", String::new(), ())
+    }
+
+    fn undo_list(&self) -> Vec<UndoEntry> {
+        self.call_or("undo_list", String::new(), Vec::new()).unwrap_or_default()
+    }
+
+    fn undo(&self) -> BackendResult<()> {
+        self.call_or("undo", String::new(), ())
+    }
+
+    fn ci_status(&self, _url_template: &str, _token: Option<&str>, revision: &str) -> BackendResult<CiStatus> {
+        self.call("ci_status", revision.to_owned())
+    }
+
+    fn stash(&self, message: &str, entries: &[RevisionEntry]) -> BackendResult<()> {
+        self.call_or("stash", format!("{} {:?}", names(entries), message), ())
+    }
+
+    fn stash_all(&self, message: &str) -> BackendResult<()> {
+        self.call_or("stash_all", format!("{:?}", message), ())
+    }
+
+    fn stash_list(&self) -> BackendResult<Vec<StashEntry>> {
+        self.call("stash_list", String::new())
+    }
+
+    fn stash_pop(&self, id: usize) -> BackendResult<()> {
+        self.call_or("stash_pop", id.to_string(), ())
+    }
+
+    fn stash_show(&self, id: usize) -> BackendResult<String> {
+        self.call("stash_show", id.to_string())
+    }
+
+    fn stash_diff(&self, id: usize) -> BackendResult<String> {
+        self.call("stash_diff", id.to_string())
+    }
+
+    fn stash_drop(&self, id: usize) -> BackendResult<()> {
+        self.call_or("stash_drop", id.to_string(), ())
+    }
+
+    fn revision_details(&self, revision: &str, _date_format: &DateFormat) -> BackendResult<RevisionInfo> {
+        self.call("revision_details", revision.to_owned())
+    }
+
+    fn describe(&self, revision: &str) -> BackendResult<String> {
+        self.call_or("describe", revision.to_owned(), String::new())
+    }
+
+    fn refs(&self, revision: &str) -> BackendResult<String> {
+        self.call("refs", revision.to_owned())
+    }
+
+    fn note(&self, revision: &str) -> BackendResult<String> {
+        self.call_or("note", revision.to_owned(), String::new())
+    }
+
+    fn noted_revisions(&self) -> BackendResult<Vec<String>> {
+        self.call_or("noted_revisions", String::new(), Vec::new())
+    }
+
+    fn set_note(&self, revision: &str, note: &str) -> BackendResult<()> {
+        self.call_or("set_note", format!("{} {:?}", revision, note), ())
+    }
+
+    fn remove_note(&self, revision: &str) -> BackendResult<()> {
+        self.call_or("remove_note", revision.to_owned(), ())
+    }
+
+    fn branches(&self) -> BackendResult<Vec<BranchEntry>> {
+        self.call("branches", String::new())
+    }
+
+    fn new_branch(&self, name: &str) -> BackendResult<()> {
+        self.call_or("new_branch", name.to_owned(), ())
+    }
+
+    fn delete_branch(&self, name: &str, force: bool) -> BackendResult<()> {
+        let force = if force { " --force" } else { "" };
+        self.call_or("delete_branch", format!("{}{}", name, force), ())
+    }
+
+    fn delete_branches(&self, names: &[String], force: bool) -> BackendResult<()> {
+        let force = if force { " --force" } else { "" };
+        self.call_or("delete_branches", format!("{}{}", names.join(","), force), ())
+    }
+
+    fn merged_branches(&self) -> BackendResult<(String, Vec<String>)> {
+        self.call("merged_branches", String::new())
+    }
+
+    fn tree(&self, revision: &str) -> BackendResult<Vec<TreeEntry>> {
+        self.call("tree", revision.to_owned())
+    }
+
+    fn file_content(&self, revision: &str, path: &str) -> BackendResult<String> {
+        self.call("file_content", format!("{} {}", revision, path))
+    }
+
+    fn blame(&self, revision: &str, path: &str) -> BackendResult<Vec<BlameEntry>> {
+        self.call("blame", format!("{} {}", revision, path))
+    }
+
+    fn signatures(&self, range: &str) -> BackendResult<Vec<SignatureEntry>> {
+        self.call("signatures", range.to_owned())
+    }
+
+    fn grep(&self, pattern: &str, revision: Option<&str>) -> BackendResult<Vec<GrepEntry>> {
+        self.call("grep", format!("{} {}", pattern, revision.unwrap_or("")).trim_end().to_owned())
+    }
+
+    fn sparse_checkout_list(&self) -> BackendResult<Vec<SparseEntry>> {
+        self.call("sparse_checkout_list", String::new())
+    }
+
+    fn sparse_checkout_add(&self, directory: &str) -> BackendResult<()> {
+        self.call_or("sparse_checkout_add", directory.to_owned(), ())
+    }
+
+    fn sparse_checkout_remove(&self, directory: &str) -> BackendResult<()> {
+        self.call_or("sparse_checkout_remove", directory.to_owned(), ())
+    }
+
+    fn sparse_checkout_reapply(&self) -> BackendResult<()> {
+        self.call_or("sparse_checkout_reapply", String::new(), ())
+    }
+
+    fn sparse_checkout_disable(&self) -> BackendResult<()> {
+        self.call_or("sparse_checkout_disable", String::new(), ())
+    }
+
+    fn tags(&self) -> BackendResult<Vec<TagEntry>> {
+        self.call("tags", String::new())
+    }
+
+    fn new_tag(&self, name: &str) -> BackendResult<()> {
+        self.call_or("new_tag", name.to_owned(), ())
+    }
+
+    fn delete_tag(&self, name: &str) -> BackendResult<()> {
+        self.call_or("delete_tag", name.to_owned(), ())
+    }
+
+    fn maintenance(&self, task: MaintenanceTask, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        self.stream("maintenance", task.as_str().to_owned(), on_line)
+    }
+
+    fn repository_size(&self) -> BackendResult<RepositorySize> {
+        self.call_or("repository_size", String::new(), RepositorySize::default())
+    }
+
+    fn setup_info(&self) -> BackendResult<SetupInfo> {
+        self.call("setup_info", String::new())
+    }
+
+    fn set_config(&self, key: &str, value: &str, global: bool) -> BackendResult<()> {
+        let scope = if global { "global" } else { "local" };
+        self.call_or("set_config", format!("{} {} {}", scope, key, value), ())
+    }
+
+    fn run_command(&self, command: &str, on_line: &mut dyn FnMut(&str, bool)) -> BackendResult<()> {
+        self.stream("run_command", command.to_owned(), on_line)
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{messages::Messages, tool::Pattern};
+
+const CONFIG_DIR_NAME: &str = "gituse";
+const CONFIG_FILE_NAME: &str = "config";
+const REPOSITORY_CONFIG_FILE_NAME: &str = ".gituse";
+
+// simple "key = value" settings, lines starting with '#' are comments
+// the global file is loaded first, then the one in the repository root overrides it
+#[derive(Default, Clone, Debug)]
+pub struct Config {
+    values: HashMap<String, String>,
+    global: HashMap<String, String>, // what a cloned repository cannot change, like commands to run
+    messages: Messages,
+}
+
+impl Config {
+    pub fn load(root: &Path) -> Self {
+        let mut config = Config::default();
+        if let Some(dir) = config_dir() {
+            config.load_file(&dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME));
+        }
+        config.global = config.values.clone();
+        config.load_file(&root.join(REPOSITORY_CONFIG_FILE_NAME));
+        // relative to the directory of the global config file
+        if let Some(locale) = config.get("ui.locale") {
+            let path = match config_dir() {
+                Some(dir) => dir.join(CONFIG_DIR_NAME).join(locale),
+                None => PathBuf::from(locale),
+            };
+            config.messages = Messages::load(&path);
+        }
+        config
+    }
+
+    fn load_file(&mut self, path: &Path) {
+        if let Ok(text) = fs::read_to_string(path) {
+            self.parse(&text);
+        }
+    }
+
+    fn parse(&mut self, text: &str) {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                self.values.insert(key.trim().to_owned(), value.trim().to_owned());
+            }
+        }
+    }
+
+    // like the global file, set from the command line
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_owned(), value.to_owned());
+        self.global.insert(key.to_owned(), value.to_owned());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str).filter(|v| !v.is_empty())
+    }
+
+    // ignoring the repository file, for the commands run and the secrets sent somewhere
+    pub fn get_global(&self, key: &str) -> Option<&str> {
+        self.global.get(key).map(String::as_str).filter(|v| !v.is_empty())
+    }
+
+    pub fn messages(&self) -> &Messages {
+        &self.messages
+    }
+
+    pub fn set_message(&mut self, text: &str, translation: &str) {
+        self.messages.set(text, translation);
+    }
+
+    pub fn get_bool(&self, key: &str, default: bool) -> bool {
+        match self.get(key) {
+            Some("true") | Some("yes") | Some("1") => true,
+            Some("false") | Some("no") | Some("0") => false,
+            _ => default,
+        }
+    }
+
+    // comma separated values
+    pub fn get_list(&self, key: &str) -> Vec<&str> {
+        split_list(self.get(key))
+    }
+
+    pub fn get_global_list(&self, key: &str) -> Vec<&str> {
+        split_list(self.get_global(key))
+    }
+
+    pub fn issue_patterns(&self) -> Vec<Pattern> {
+        match self.get_list("issue.patterns") {
+            patterns if patterns.is_empty() => vec![Pattern::new(r"#\d+")],
+            patterns => patterns.into_iter().map(Pattern::new).collect(),
+        }
+    }
+}
+
+fn split_list(value: Option<&str>) -> Vec<&str> {
+    match value {
+        Some(value) => value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect(),
+        None => Vec::new(),
+    }
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(dir.into());
+    }
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+    }
+}
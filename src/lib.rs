@@ -0,0 +1,13 @@
+#[macro_use]
+mod macros;
+pub mod application;
+pub mod askpass;
+pub mod backend;
+pub mod config;
+pub mod messages;
+pub mod mode;
+pub mod platform;
+pub mod session;
+pub mod syntax;
+pub mod tool;
+pub mod ui;
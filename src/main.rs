@@ -1,51 +1,110 @@
-use std::{env, io, io::Write};
+use std::{env, io, io::Write, path::PathBuf, process};
 
-#[macro_use]
-mod macros;
-mod application;
-mod backend;
-mod mode;
-mod platform;
-mod tool;
-mod ui;
+use gituse::{
+    application, askpass, backend, config,
+    mode::{ModeChangeInfo, ModeKind},
+    platform, session, tool, ui,
+};
 
 fn main() {
+    // run again by git or ssh as their askpass program, see askpass.rs
+    let mut args = env::args().skip(1);
+    if let (Some("--askpass"), prompt) = (args.next().as_deref(), args.next()) {
+        process::exit(askpass::run_helper(&prompt.unwrap_or_default()));
+    }
+
     tool::log_init();
 
-    let mut args = env::args();
-    args.next();
-    if let Some(arg) = args.next() {
-        if args.next().is_some() {
-            eprintln!("too many args");
-        } else {
-            match &arg[..] {
-                "-h" | "--help" => {
-                    let name = env!("CARGO_PKG_NAME");
-                    let version = env!("CARGO_PKG_VERSION");
-                    println!("{} v{}", name, version);
-                    println!();
-                    println!("{}", env!("CARGO_PKG_DESCRIPTION"));
-                    println!();
-                    println!("\t-h --help\tprint this help message and exit");
-                    println!("\t-v --version\tprint version number and exit");
-                }
-                "-v" | "--version" => {
-                    print!("{}", env!("CARGO_PKG_VERSION"));
+    let mut read_only = false;
+    let mut plain = false;
+    let mut path = None;
+    let mut commands = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => {
+                let name = env!("CARGO_PKG_NAME");
+                let version = env!("CARGO_PKG_VERSION");
+                println!("{} v{}", name, version);
+                println!();
+                println!("{}", env!("CARGO_PKG_DESCRIPTION"));
+                println!();
+                println!("usage: {} [options] [status | log | diff [<revision>] | show [<revision>]]", name);
+                println!();
+                println!("\t-h --help\tprint this help message and exit");
+                println!("\t-v --version\tprint version number and exit");
+                println!("\t--read-only\tdisable every action that changes the repository");
+                println!("\t--plain\tno colors and ascii only, the hovered entry is marked with '>'");
+                println!("\t--path <dir>\topen the repository containing <dir> instead of the current one");
+                return;
+            }
+            "-v" | "--version" => {
+                print!("{}", env!("CARGO_PKG_VERSION"));
+                return;
+            }
+            "--read-only" => read_only = true,
+            "--plain" => plain = true,
+            "--path" => match args.next() {
+                Some(arg) => path = Some(arg),
+                None => {
+                    eprintln!("missing directory after '--path'");
+                    return;
                 }
-                arg => eprintln!("invalid argument '{}'", arg),
+            },
+            arg if !arg.starts_with('-') => commands.push(arg.to_owned()),
+            arg => {
+                eprintln!("invalid argument '{}'", arg);
+                return;
             }
         }
-        return;
     }
 
-    let (root, backend) = match backend::backend_from_current_repository() {
-        Some((root, backend)) => (root, backend),
-        None => {
-            eprintln!("no repository found");
+    let (root, backend) = match backend::backend_from_path(&path.map(PathBuf::from).unwrap_or_default()) {
+        Ok((root, backend)) => (root, backend),
+        Err(error) => {
+            eprintln!("{}", error);
             return;
         }
     };
 
+    let mut config = config::Config::load(&root);
+    if read_only {
+        config.set("read_only", "true");
+    }
+    if plain {
+        config.set("ui.plain", "true");
+    }
+    // https://no-color.org
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    ui::set_plain(config.get_bool("ui.plain", no_color));
+
+    // without a mode to open, the tool reopens where it was left; a restored status needs a working tree too
+    let restored = session::load(backend.git_dir())
+        .filter(|_| commands.is_empty() && config.get_bool("session.restore", true))
+        .filter(|(mode, _)| !(backend.is_bare() && *mode == ModeKind::Status))
+        .map(|(mode, session)| (mode.clone(), ModeChangeInfo::session(mode, session)));
+
+    let start_mode = match restored {
+        Some(start_mode) => start_mode,
+        None => match commands.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            [] if backend.is_bare() => (ModeKind::Log, ModeChangeInfo::new(ModeKind::Log)),
+            ["status"] | ["diff"] if backend.is_bare() => {
+                eprintln!("'{}' needs a working tree but the repository is bare", commands.join(" "));
+                return;
+            }
+            [] | ["status"] => (ModeKind::Status, ModeChangeInfo::new(ModeKind::Status)),
+            ["log"] => (ModeKind::Log, ModeChangeInfo::new(ModeKind::Log)),
+            ["diff"] => (ModeKind::Diff, ModeChangeInfo::diff(ModeKind::Status, None)),
+            ["diff", revision] => (ModeKind::Diff, ModeChangeInfo::diff(ModeKind::Log, Some(revision.to_owned()))),
+            ["show"] => (ModeKind::RevisionDetails, ModeChangeInfo::revision(ModeKind::Log, "HEAD".to_owned())),
+            ["show", revision] => (ModeKind::RevisionDetails, ModeChangeInfo::revision(ModeKind::Log, revision.to_owned())),
+            _ => {
+                eprintln!("invalid arguments '{}'", commands.join(" "));
+                return;
+            }
+        },
+    };
+
     if std::env::set_current_dir(&root).is_err() {
         eprintln!("could not set current dir to {:?}", root);
         return;
@@ -68,7 +127,7 @@ fn main() {
         stdout.flush().unwrap();
     }
 
-    application::run(platform_event_reader, backend);
+    application::run(&platform, platform_event_reader, backend, config, start_mode);
 
     {
         let stdout = io::stdout();
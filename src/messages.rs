@@ -0,0 +1,78 @@
+use std::{collections::HashMap, fs, path::Path};
+
+// the keys reaching every mode, drawn at the top right of the header
+pub const GLOBAL_HELP: &str =
+    "[s]status [l]log [b]branches [t]tags [S]stash [G]grep [M]maintenance [ctrl+z]undo [q]record [@]replay";
+pub const RECORDING: &str = "recording";
+
+// the english strings of the header are the keys of the catalog, a locale file replaces them with
+// "english = translation" lines: a mode name, the action of a "[key]action" help entry or a whole help line
+// (e.g. "record = aufnehmen"), an empty translation leaves the help entry out, the keys themselves stay
+#[derive(Default, Clone, Debug)]
+pub struct Messages {
+    translations: HashMap<String, String>,
+}
+
+impl Messages {
+    pub fn load(path: &Path) -> Self {
+        let mut messages = Self::default();
+        if let Ok(text) = fs::read_to_string(path) {
+            for line in text.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((text, translation)) = line.split_once('=') {
+                    messages.set(text.trim(), translation.trim());
+                }
+            }
+        }
+        messages
+    }
+
+    pub fn set(&mut self, text: &str, translation: &str) {
+        self.translations.insert(text.to_owned(), translation.to_owned());
+    }
+
+    pub fn get<'a>(&'a self, text: &'a str) -> &'a str {
+        self.translations.get(text).map(String::as_str).unwrap_or(text)
+    }
+
+    // a whole line translated wins over its entries translated one by one
+    pub fn help(&self, help: &str) -> String {
+        if let Some(translation) = self.translations.get(help) {
+            return translation.clone();
+        }
+
+        // actions can span several words, e.g. "[R]reset to remote"
+        let mut entries: Vec<String> = Vec::new();
+        for word in help.split_inclusive(' ') {
+            match entries.last_mut() {
+                Some(entry) if !word.starts_with('[') => entry.push_str(word),
+                _ => entries.push(word.to_owned()),
+            }
+        }
+
+        let mut localized = String::with_capacity(help.len());
+        for entry in &entries {
+            let (key, action) = match entry.strip_prefix('[').and_then(|e| e.split_once(']')) {
+                Some((key, action)) => (key, action.trim_end()),
+                None => {
+                    localized.push_str(entry);
+                    continue;
+                }
+            };
+            match self.get(action) {
+                "" => (),
+                action => {
+                    localized.push('[');
+                    localized.push_str(key);
+                    localized.push(']');
+                    localized.push_str(action);
+                    localized.push(' ');
+                }
+            }
+        }
+        localized.truncate(localized.trim_end().len());
+        localized
+    }
+}
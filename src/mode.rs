@@ -1,17 +1,47 @@
 use bounded_vec_deque::BoundedVecDeque;
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    env,
+    io::{self, Write},
+    sync::Arc,
+    thread,
+};
 
-use crate::{application::EventSender, backend::Backend, platform::Key, tool::*, ui::Drawer};
+use crate::{
+    application::EventSender,
+    backend::{Backend, BackendResult, DateFormat, FileStatus, LogFilter},
+    config::Config,
+    messages::GLOBAL_HELP,
+    platform::{self, Key},
+    tool::*,
+    ui::{self, Drawer},
+};
 
+pub mod activity;
+pub mod blame;
 pub mod branches;
+pub mod co_authors;
+pub mod command;
+pub mod confirm;
+pub mod contributors;
+pub mod conventional_commit;
 pub mod diff;
+pub mod file_view;
+pub mod grep;
 pub mod log;
+pub mod maintenance;
 pub mod message_input;
+pub mod patch_series;
 pub mod revision_details;
+pub mod setup;
+pub mod signatures;
+pub mod sparse_checkout;
 pub mod stash;
 pub mod stash_details;
 pub mod status;
 pub mod tags;
+pub mod tree;
+pub mod undo;
 
 pub enum ModeResponse {
     Status(status::Response),
@@ -22,7 +52,21 @@ pub enum ModeResponse {
     Stash(stash::Response),
     Diff(diff::Response),
     StashDetails(stash_details::Response),
-    _MessageInput(message_input::Response),
+    MessageInput(message_input::Response),
+    CoAuthors(co_authors::Response),
+    Undo(undo::Response),
+    SparseCheckout(sparse_checkout::Response),
+    Tree(tree::Response),
+    FileView(file_view::Response),
+    Blame(blame::Response),
+    Maintenance(maintenance::Response),
+    Contributors(contributors::Response),
+    Activity(activity::Response),
+    Grep(grep::Response),
+    Signatures(signatures::Response),
+    Command(command::Response),
+    PatchSeries(patch_series::Response),
+    Setup(setup::Response),
 }
 impl ModeResponse {
     pub fn mode_kind(&self) -> ModeKind {
@@ -35,7 +79,21 @@ impl ModeResponse {
             ModeResponse::Stash(_) => ModeKind::Stash,
             ModeResponse::Diff(_) => ModeKind::Diff,
             ModeResponse::StashDetails(_) => ModeKind::StashDetails,
-            ModeResponse::_MessageInput(_) => ModeKind::MessageInput,
+            ModeResponse::MessageInput(_) => ModeKind::MessageInput,
+            ModeResponse::CoAuthors(_) => ModeKind::CoAuthors,
+            ModeResponse::Undo(_) => ModeKind::Undo,
+            ModeResponse::SparseCheckout(_) => ModeKind::SparseCheckout,
+            ModeResponse::Tree(_) => ModeKind::Tree,
+            ModeResponse::FileView(_) => ModeKind::FileView,
+            ModeResponse::Blame(_) => ModeKind::Blame,
+            ModeResponse::Maintenance(_) => ModeKind::Maintenance,
+            ModeResponse::Contributors(_) => ModeKind::Contributors,
+            ModeResponse::Activity(_) => ModeKind::Activity,
+            ModeResponse::Grep(_) => ModeKind::Grep,
+            ModeResponse::Signatures(_) => ModeKind::Signatures,
+            ModeResponse::Command(_) => ModeKind::Command,
+            ModeResponse::PatchSeries(_) => ModeKind::PatchSeries,
+            ModeResponse::Setup(_) => ModeKind::Setup,
         }
     }
 }
@@ -43,7 +101,7 @@ impl ModeResponse {
 #[derive(Clone, Debug)]
 pub enum Mode {
     Status(status::Mode),
-    Log(log::Mode),
+    Log(Box<log::Mode>), // boxed, the largest by far
     RevisionDetails(revision_details::Mode),
     Branches(branches::Mode),
     Tags(tags::Mode),
@@ -51,6 +109,22 @@ pub enum Mode {
     Diff(diff::Mode),
     StashDetails(stash_details::Mode),
     MessageInput(message_input::Mode),
+    ConventionalCommit(conventional_commit::Mode),
+    CoAuthors(co_authors::Mode),
+    Undo(undo::Mode),
+    Confirm(confirm::Mode),
+    SparseCheckout(sparse_checkout::Mode),
+    Tree(tree::Mode),
+    FileView(file_view::Mode),
+    Blame(blame::Mode),
+    Maintenance(maintenance::Mode),
+    Contributors(contributors::Mode),
+    Activity(activity::Mode),
+    Grep(grep::Mode),
+    Signatures(signatures::Mode),
+    Command(command::Mode),
+    PatchSeries(patch_series::Mode),
+    Setup(setup::Mode),
 }
 impl Default for Mode {
     fn default() -> Self {
@@ -62,7 +136,7 @@ impl Mode {
     fn default_from_mode_kind(mode_kind: ModeKind) -> Self {
         match mode_kind {
             ModeKind::Status => Self::Status(status::Mode::default()),
-            ModeKind::Log => Self::Log(log::Mode::default()),
+            ModeKind::Log => Self::Log(Box::default()),
             ModeKind::RevisionDetails => Self::RevisionDetails(revision_details::Mode::default()),
             ModeKind::Branches => Self::Branches(branches::Mode::default()),
             ModeKind::Tags => Self::Tags(tags::Mode::default()),
@@ -70,13 +144,29 @@ impl Mode {
             ModeKind::Diff => Self::Diff(diff::Mode::default()),
             ModeKind::StashDetails => Self::StashDetails(stash_details::Mode::default()),
             ModeKind::MessageInput => Self::MessageInput(message_input::Mode::default()),
+            ModeKind::ConventionalCommit => Self::ConventionalCommit(conventional_commit::Mode::default()),
+            ModeKind::CoAuthors => Self::CoAuthors(co_authors::Mode::default()),
+            ModeKind::Undo => Self::Undo(undo::Mode::default()),
+            ModeKind::Confirm => Self::Confirm(confirm::Mode::default()),
+            ModeKind::SparseCheckout => Self::SparseCheckout(sparse_checkout::Mode::default()),
+            ModeKind::Tree => Self::Tree(tree::Mode::default()),
+            ModeKind::FileView => Self::FileView(file_view::Mode::default()),
+            ModeKind::Blame => Self::Blame(blame::Mode::default()),
+            ModeKind::Maintenance => Self::Maintenance(maintenance::Mode::default()),
+            ModeKind::Contributors => Self::Contributors(contributors::Mode::default()),
+            ModeKind::Activity => Self::Activity(activity::Mode::default()),
+            ModeKind::Grep => Self::Grep(grep::Mode::default()),
+            ModeKind::Signatures => Self::Signatures(signatures::Mode::default()),
+            ModeKind::Command => Self::Command(command::Mode::default()),
+            ModeKind::PatchSeries => Self::PatchSeries(patch_series::Mode::default()),
+            ModeKind::Setup => Self::Setup(setup::Mode::default()),
         }
     }
 
     fn mode(&mut self) -> &mut dyn ModeTrait {
         match self {
             Self::Status(mode) => mode,
-            Self::Log(mode) => mode,
+            Self::Log(mode) => mode.as_mut(),
             Self::RevisionDetails(mode) => mode,
             Self::Branches(mode) => mode,
             Self::Tags(mode) => mode,
@@ -84,6 +174,22 @@ impl Mode {
             Self::Diff(mode) => mode,
             Self::StashDetails(mode) => mode,
             Self::MessageInput(mode) => mode,
+            Self::ConventionalCommit(mode) => mode,
+            Self::CoAuthors(mode) => mode,
+            Self::Undo(mode) => mode,
+            Self::Confirm(mode) => mode,
+            Self::SparseCheckout(mode) => mode,
+            Self::Tree(mode) => mode,
+            Self::FileView(mode) => mode,
+            Self::Blame(mode) => mode,
+            Self::Maintenance(mode) => mode,
+            Self::Contributors(mode) => mode,
+            Self::Activity(mode) => mode,
+            Self::Grep(mode) => mode,
+            Self::Signatures(mode) => mode,
+            Self::Command(mode) => mode,
+            Self::PatchSeries(mode) => mode,
+            Self::Setup(mode) => mode,
         }
     }
 
@@ -98,6 +204,22 @@ impl Mode {
             Self::Diff(_) => ModeKind::Diff,
             Self::StashDetails(_) => ModeKind::StashDetails,
             Self::MessageInput(_) => ModeKind::MessageInput,
+            Self::ConventionalCommit(_) => ModeKind::ConventionalCommit,
+            Self::CoAuthors(_) => ModeKind::CoAuthors,
+            Self::Undo(_) => ModeKind::Undo,
+            Self::Confirm(_) => ModeKind::Confirm,
+            Self::SparseCheckout(_) => ModeKind::SparseCheckout,
+            Self::Tree(_) => ModeKind::Tree,
+            Self::FileView(_) => ModeKind::FileView,
+            Self::Blame(_) => ModeKind::Blame,
+            Self::Maintenance(_) => ModeKind::Maintenance,
+            Self::Contributors(_) => ModeKind::Contributors,
+            Self::Activity(_) => ModeKind::Activity,
+            Self::Grep(_) => ModeKind::Grep,
+            Self::Signatures(_) => ModeKind::Signatures,
+            Self::Command(_) => ModeKind::Command,
+            Self::PatchSeries(_) => ModeKind::PatchSeries,
+            Self::Setup(_) => ModeKind::Setup,
         }
     }
 }
@@ -124,7 +246,8 @@ impl ModeBuf {
     }
 
     pub fn enter_mode(&mut self, ctx: &ModeContext, mode_kind: ModeKind, info: ModeChangeInfo) {
-        if self.mode.mode_kind() != mode_kind {
+        // a mode opened from itself with new information, like a range of the log, can be gone back from
+        if self.mode.mode_kind() != mode_kind || info.info.is_some() {
             log(format!("before enter mode to {:?}:\n {:?}\n", mode_kind, self.mode));
             self.history.push_back(self.mode.clone());
         }
@@ -132,6 +255,20 @@ impl ModeBuf {
         self.mode().on_enter(ctx, info);
     }
 
+    // the first mode has nothing to go back to
+    pub fn start(&mut self, ctx: &ModeContext, mode_kind: ModeKind, info: ModeChangeInfo) {
+        self.mode = Mode::default_from_mode_kind(mode_kind);
+        self.mode().on_enter(ctx, info);
+    }
+
+    // the session of the current mode, or of the latest one it was entered from that has one
+    pub fn session(&mut self) -> Option<(ModeKind, ListSession)> {
+        let current = self.mode.mode().session().map(|session| (self.mode.mode_kind(), session));
+        current.or_else(|| {
+            self.history.iter_mut().rev().find_map(|mode| mode.mode().session().map(|session| (mode.mode_kind(), session)))
+        })
+    }
+
     pub fn revert_mode(&mut self, _ctx: &ModeContext) {
         //log(format!("revert: \n "));
         if let Some(mode) = self.history.pop_back() {
@@ -148,7 +285,16 @@ pub struct ModeChangeInfo {
 pub enum ModeInfo {
     RevisionDetails(String),
     StashDetails(usize),
+    Diff(Option<String>),
+    Tree(String),
+    FileView(String, String, usize), // revision, path, line to scroll to (0 for the top)
+    Blame(String, String),           // revision, path
+    Log(LogFilter),
     MessageInput(message_input::ModeInfo),
+    Confirm(confirm::ModeInfo),
+    Session(ListSession),
+    Command(BackendResult<String>), // the command, or why it could not be made from its template
+    PatchSeries(String),            // the range
 }
 
 impl ModeChangeInfo {
@@ -156,10 +302,53 @@ impl ModeChangeInfo {
         Self { from, info: None }
     }
 
+    pub fn command(from: ModeKind, command: BackendResult<String>) -> Self {
+        Self { from, info: Some(ModeInfo::Command(command)) }
+    }
+
+    // reopens the mode where it was left when the tool was last closed
+    pub fn session(from: ModeKind, session: ListSession) -> Self {
+        Self { from, info: Some(ModeInfo::Session(session)) }
+    }
+
     pub fn revision(from: ModeKind, revision: String) -> Self {
         Self { from, info: Some(ModeInfo::RevisionDetails(revision)) }
     }
 
+    pub fn tree(from: ModeKind, revision: String) -> Self {
+        Self { from, info: Some(ModeInfo::Tree(revision)) }
+    }
+
+    pub fn file_view(from: ModeKind, revision: String, path: String) -> Self {
+        Self::file_view_at(from, revision, path, 0)
+    }
+
+    pub fn file_view_at(from: ModeKind, revision: String, path: String, line: usize) -> Self {
+        Self { from, info: Some(ModeInfo::FileView(revision, path, line)) }
+    }
+
+    pub fn blame(from: ModeKind, revision: String, path: String) -> Self {
+        Self { from, info: Some(ModeInfo::Blame(revision, path)) }
+    }
+
+    // the log of the commits touching path
+    pub fn file_history(from: ModeKind, path: String) -> Self {
+        Self::log(from, LogFilter { path: Some(path), ..Default::default() })
+    }
+
+    pub fn log(from: ModeKind, log_filter: LogFilter) -> Self {
+        Self { from, info: Some(ModeInfo::Log(log_filter)) }
+    }
+
+    // the diff mode only fetches by itself when given this info, otherwise the caller sends the diff
+    pub fn diff(from: ModeKind, revision: Option<String>) -> Self {
+        Self { from, info: Some(ModeInfo::Diff(revision)) }
+    }
+
+    pub fn patch_series(from: ModeKind, range: String) -> Self {
+        Self { from, info: Some(ModeInfo::PatchSeries(range)) }
+    }
+
     pub fn stash(from: ModeKind, stash_id: usize) -> Self {
         Self { from, info: Some(ModeInfo::StashDetails(stash_id)) }
     }
@@ -175,6 +364,57 @@ impl ModeChangeInfo {
             info: Some(ModeInfo::MessageInput(message_input::ModeInfo::new(not_empty, placeholder.into(), on_submit))),
         }
     }
+
+    pub fn confirm<S: Into<String>>(
+        from: ModeKind,
+        message: S,
+        payload: String,
+        on_confirm: fn(&ModeContext, String),
+    ) -> Self {
+        Self { from, info: Some(ModeInfo::Confirm(confirm::ModeInfo::new(message.into(), payload, on_confirm))) }
+    }
+
+    // the message input offers to insert co-authored-by trailers
+    pub fn with_co_authors(mut self) -> Self {
+        if let Some(ModeInfo::MessageInput(info)) = &mut self.info {
+            info.co_authors = true;
+        }
+        self
+    }
+
+    // the confirmation is answered with one of the choices instead of yes, the default one with [enter]
+    pub fn with_choices(mut self, choices: Vec<confirm::Choice>, default_choice: Option<char>) -> Self {
+        if let Some(ModeInfo::Confirm(info)) = &mut self.info {
+            info.choices = choices;
+            info.default_choice = default_choice;
+        }
+        self
+    }
+
+    // the message input shows the prompt above what is typed in, masked when secret
+    pub fn with_prompt<S: Into<String>>(mut self, prompt: S, secret: bool) -> Self {
+        if let Some(ModeInfo::MessageInput(info)) = &mut self.info {
+            info.prompt = prompt.into();
+            info.secret = secret;
+        }
+        self
+    }
+
+    // called when the message input is cancelled instead of submitted
+    pub fn with_on_cancel(mut self, on_cancel: fn(&ModeContext)) -> Self {
+        if let Some(ModeInfo::MessageInput(info)) = &mut self.info {
+            info.on_cancel = message_input::OnCancel(on_cancel);
+        }
+        self
+    }
+
+    // the message input starts with this text instead of being empty
+    pub fn with_initial_message<S: Into<String>>(mut self, message: S) -> Self {
+        if let Some(ModeInfo::MessageInput(info)) = &mut self.info {
+            info.initial_message = message.into();
+        }
+        self
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -188,27 +428,218 @@ pub enum ModeKind {
     Diff,
     StashDetails,
     MessageInput,
+    ConventionalCommit,
+    CoAuthors,
+    Undo,
+    Confirm,
+    SparseCheckout,
+    Tree,
+    FileView,
+    Blame,
+    Maintenance,
+    Contributors,
+    Activity,
+    Grep,
+    Signatures,
+    Command,
+    PatchSeries,
+    Setup,
 }
 impl Default for ModeKind {
     fn default() -> Self {
         Self::Status
     }
 }
+impl ModeKind {
+    // the name used in the config and the session file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Log => "log",
+            Self::RevisionDetails => "revision_details",
+            Self::Branches => "branches",
+            Self::Tags => "tags",
+            Self::Stash => "stash",
+            Self::Diff => "diff",
+            Self::StashDetails => "stash_details",
+            Self::MessageInput => "message_input",
+            Self::ConventionalCommit => "conventional_commit",
+            Self::CoAuthors => "co_authors",
+            Self::Undo => "undo",
+            Self::Confirm => "confirm",
+            Self::SparseCheckout => "sparse_checkout",
+            Self::Tree => "tree",
+            Self::FileView => "file_view",
+            Self::Blame => "blame",
+            Self::Maintenance => "maintenance",
+            Self::Contributors => "contributors",
+            Self::Activity => "activity",
+            Self::Grep => "grep",
+            Self::Signatures => "signatures",
+            Self::Command => "command",
+            Self::PatchSeries => "patch_series",
+            Self::Setup => "setup",
+        }
+    }
+}
 
 pub trait ModeTrait {
     fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo);
     fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus;
     fn is_waiting_response(&self) -> bool;
+    // keys typed into a filter or prompt are text, not commands
+    fn has_text_focus(&self) -> bool {
+        false
+    }
     fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse);
     fn header(&self) -> (&str, &str, &str);
     fn draw(&self, drawer: &mut Drawer);
+    // where the mode was left, for the modes that can be reopened on the next launch
+    fn session(&self) -> Option<ListSession> {
+        None
+    }
+    // the values of the "{name}" placeholders of the custom actions, for the hovered entry
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+// keys that change the repository, disabled in read-only mode
+const MUTATING_KEYS: &[(ModeKind, &[Key])] = &[
+    (
+        ModeKind::Status,
+        &[
+            Key::Char('c'),
+            Key::Char('C'),
+            Key::Char('A'),
+            Key::Char('D'),
+            Key::Char('O'),
+            Key::Char('T'),
+            Key::Char('L'),
+            Key::Char('N'),
+            Key::Char('K'),
+            Key::Char('Q'),
+            Key::Ctrl('s'),
+        ],
+    ),
+    (
+        ModeKind::Log,
+        &[
+            Key::Char('c'),
+            Key::Char('r'),
+            Key::Char('R'),
+            Key::Char('m'),
+            Key::Char('f'),
+            Key::Char('p'),
+            Key::Char('P'),
+            Key::Char('F'),
+            Key::Char('g'),
+            Key::Char('H'),
+            Key::Char('U'),
+            Key::Char('x'),
+            Key::Char('X'),
+            Key::Char('B'),
+            Key::Char('O'),
+            Key::Char('N'),
+            Key::Char('D'),
+            Key::Char('Q'),
+            Key::Char('C'),
+        ],
+    ),
+    (
+        ModeKind::Branches,
+        &[
+            Key::Enter,
+            Key::Char('n'),
+            Key::Char('d'),
+            Key::Char('D'),
+            Key::Char('x'),
+            Key::Char('p'),
+            Key::Char('X'),
+            Key::Char('m'),
+            Key::Char('B'),
+            Key::Char('R'),
+        ],
+    ),
+    (ModeKind::Diff, &[Key::Char('a'), Key::Char('E')]),
+    (ModeKind::PatchSeries, &[Key::Char('e'), Key::Char('s')]),
+    (ModeKind::Tags, &[Key::Enter, Key::Char('n'), Key::Char('D'), Key::Char('p'), Key::Char('P'), Key::Char('X')]),
+    (ModeKind::Stash, &[Key::Char('p'), Key::Char('D')]),
+    (ModeKind::Undo, &[Key::Enter]),
+    (ModeKind::SparseCheckout, &[Key::Char('n'), Key::Char('D'), Key::Char('r'), Key::Char('X')]),
+    (ModeKind::Maintenance, &[Key::Char('g'), Key::Char('p')]),
+    (ModeKind::Setup, &[Key::Enter, Key::Char('r')]),
+];
+
+// keys that need a working tree, disabled in bare repositories
+const WORK_TREE_KEYS: &[(ModeKind, &[Key])] = &[
+    (ModeKind::Diff, &[Key::Char('e')]),
+    (ModeKind::Grep, &[Key::Char('e')]),
+    (
+        ModeKind::Log,
+        &[
+            Key::Char('c'),
+            Key::Char('r'),
+            Key::Char('R'),
+            Key::Char('m'),
+            Key::Char('p'),
+            Key::Char('x'),
+            Key::Char('X'),
+            Key::Char('B'),
+            Key::Char('O'),
+            Key::Char('Q'),
+        ],
+    ),
+    (ModeKind::Branches, &[Key::Enter, Key::Char('n'), Key::Char('m'), Key::Char('B')]),
+    (ModeKind::Tags, &[Key::Enter]),
+    (ModeKind::Undo, &[Key::Enter]),
+];
+
+fn find_key(table: &[(ModeKind, &[Key])], mode_kind: &ModeKind, key: Key) -> bool {
+    table.iter().any(|(kind, keys)| kind == mode_kind && keys.contains(&key))
+}
+
+pub fn is_key_disabled(ctx: &ModeContext, mode_kind: &ModeKind, key: Key) -> bool {
+    (ctx.config.get_bool("read_only", false) && find_key(MUTATING_KEYS, mode_kind, key))
+        || (ctx.backend.is_bare() && find_key(WORK_TREE_KEYS, mode_kind, key))
+}
+
+// global keys that open modes which only make sense with a working tree
+pub fn is_global_key_disabled(ctx: &ModeContext, key: Key) -> bool {
+    ctx.backend.is_bare() && matches!(key, Key::Char('s') | Key::Char('S'))
+}
+
+// the label of key_from_help_label(), for the keys a config entry can name
+pub fn help_label_from_key(key: Key) -> Option<String> {
+    match key {
+        Key::Enter => Some("enter".to_owned()),
+        Key::Char(' ') => Some("space".to_owned()),
+        Key::Char(c) => Some(c.to_string()),
+        Key::Ctrl(c) => Some(format!("ctrl+{}", c)),
+        _ => None,
+    }
+}
+
+// help entries look like "[c]checkout" or "[ctrl+s]stash"
+pub fn key_from_help_label(label: &str) -> Option<Key> {
+    match label {
+        "enter" => Some(Key::Enter),
+        "space" => Some(Key::Char(' ')),
+        _ => match label.strip_prefix("ctrl+").unwrap_or(label).chars().collect::<Vec<_>>()[..] {
+            [c] if label.starts_with("ctrl+") => Some(Key::Ctrl(c)),
+            [c] => Some(Key::Char(c)),
+            _ => None,
+        },
+    }
 }
 
 #[derive(Clone)]
 pub struct ModeContext {
     pub backend: Arc<dyn Backend>,
+    pub config: Arc<Config>,
     pub event_sender: EventSender,
     pub viewport_size: (u16, u16),
+    pub prefetched: Arc<revision_details::Prefetched>,
 }
 
 pub struct ModeStatus {
@@ -242,6 +673,19 @@ impl Output {
         self.line_count
     }
 
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    // scrolls the least needed for the line to be visible
+    pub fn scroll_to_line(&mut self, line: usize, available_height: usize) {
+        if line < self.scroll {
+            self.scroll = line;
+        } else if line >= self.scroll + available_height {
+            self.scroll = line + 1 - available_height;
+        }
+    }
+
     pub fn lines_from_scroll<'a>(&'a self) -> impl 'a + Iterator<Item = &'a str> {
         self.text.lines().skip(self.scroll)
     }
@@ -276,6 +720,14 @@ impl ReadLine {
         &self.input
     }
 
+    pub fn set<S: Into<String>>(&mut self, input: S) {
+        self.input = input.into();
+    }
+
+    pub fn new_line(&mut self) {
+        self.input.push('\n');
+    }
+
     pub fn on_key(&mut self, key: Key) {
         match key {
             Key::Home | Key::Ctrl('u') => self.input.clear(),
@@ -319,6 +771,42 @@ pub enum SelectMenuAction {
     Toggle(usize),
     ToggleAll,
 }
+impl SelectMenuAction {
+    // marks the entries seen through the filter, toggling all unmarks them only when all of them are marked
+    pub fn apply<E: SelectableEntry>(self, filter: &Filter, entries: &mut [E]) {
+        match self {
+            SelectMenuAction::None => (),
+            SelectMenuAction::Toggle(i) => {
+                if let Some(i) = filter.get_visible_index(i) {
+                    let selected = entries[i].is_selected();
+                    entries[i].set_selected(!selected);
+                }
+            }
+            SelectMenuAction::ToggleAll => {
+                let all_selected = filter.visible_indices().iter().all(|&i| entries[i].is_selected());
+                for &i in filter.visible_indices() {
+                    entries[i].set_selected(!all_selected);
+                }
+            }
+        }
+    }
+}
+
+// entries marked with [space] or [*] for operations on several of them at once
+pub trait SelectableEntry {
+    fn is_selected(&self) -> bool;
+    fn set_selected(&mut self, selected: bool);
+}
+
+// the selected entries, or the hovered one when none is selected
+pub fn selected_or_hovered<E: SelectableEntry>(entries: &[E], hovered: Option<usize>) -> Vec<&E> {
+    let selected: Vec<_> = entries.iter().filter(|e| e.is_selected()).collect();
+    match hovered {
+        _ if !selected.is_empty() => selected,
+        Some(i) => vec![&entries[i]],
+        None => Vec::new(),
+    }
+}
 
 #[derive(Default, Clone, Debug)]
 pub struct SelectMenu {
@@ -359,7 +847,7 @@ impl SelectMenu {
 
         match key {
             Key::Char(' ') if self.cursor < entries_len => SelectMenuAction::Toggle(self.cursor),
-            Key::Char('a') => SelectMenuAction::ToggleAll,
+            Key::Char('a') | Key::Char('*') => SelectMenuAction::ToggleAll,
             _ => SelectMenuAction::None,
         }
     }
@@ -442,6 +930,308 @@ impl Filter {
     pub fn as_str(&self) -> &str {
         self.readline.input()
     }
+
+    pub fn set(&mut self, text: &str) {
+        self.has_focus = false;
+        self.readline.set(text);
+    }
+}
+
+// the lines containing the text typed in after '/', browsed with n and N
+#[derive(Default, Clone, Debug)]
+pub struct Search {
+    has_focus: bool,
+    readline: ReadLine,
+    matches: Vec<usize>, // line indices
+    current: usize,
+}
+impl Search {
+    pub fn enter(&mut self) {
+        self.has_focus = true;
+        self.readline.clear();
+        self.matches.clear();
+    }
+
+    pub fn on_key(&mut self, key: Key) {
+        if key.is_submit() {
+            self.has_focus = false;
+        } else if key.is_cancel() {
+            self.has_focus = false;
+            self.readline.clear();
+            self.matches.clear();
+        } else {
+            self.readline.on_key(key);
+        }
+    }
+
+    // the current match becomes the first one from the line on
+    pub fn find<'a, I: Iterator<Item = &'a str>>(&mut self, lines: I, from_line: usize) {
+        let pattern = self.readline.input();
+        self.matches = lines.enumerate().filter(|(_, l)| !search_matches(l, pattern).is_empty()).map(|(i, _)| i).collect();
+        self.current = self.matches.iter().position(|&i| i >= from_line).unwrap_or(0);
+    }
+
+    pub fn next_match(&mut self) -> Option<usize> {
+        if !self.matches.is_empty() {
+            self.current = (self.current + 1) % self.matches.len();
+        }
+        self.current_line()
+    }
+
+    pub fn previous_match(&mut self) -> Option<usize> {
+        if !self.matches.is_empty() {
+            self.current = (self.current + self.matches.len() - 1) % self.matches.len();
+        }
+        self.current_line()
+    }
+
+    pub fn current_line(&self) -> Option<usize> {
+        self.matches.get(self.current).copied()
+    }
+
+    // the current match counted from 1, and the match count
+    pub fn position(&self) -> (usize, usize) {
+        (if self.matches.is_empty() { 0 } else { self.current + 1 }, self.matches.len())
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.has_focus || !self.readline.input().is_empty()
+    }
+
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.readline.input()
+    }
+}
+
+// the hovered entry, the scroll and the filter of a list
+#[derive(Default, Clone, Debug)]
+pub struct ListSession {
+    pub hovered: String, // the hash or the name identifying the entry
+    pub scroll: usize,
+    pub filter: String,
+}
+impl ListSession {
+    pub fn save<E>(select: &SelectMenu, filter: &Filter, entries: &[E], key: fn(&E) -> &str) -> Self {
+        let hovered = filter.get_visible_index(select.cursor).map(|i| key(&entries[i]).to_owned()).unwrap_or_default();
+        Self { hovered, scroll: select.scroll, filter: filter.as_str().to_owned() }
+    }
+
+    // once the entries are loaded, an entry that is gone leaves the cursor where it is
+    pub fn restore<E: FilterEntry>(&self, select: &mut SelectMenu, filter: &mut Filter, entries: &[E], key: fn(&E) -> &str) {
+        filter.set(&self.filter);
+        filter.filter(entries.iter());
+        let hovered = filter.visible_indices().iter().position(|&i| key(&entries[i]) == self.hovered);
+        if let Some(cursor) = hovered {
+            select.cursor = cursor;
+            select.scroll = self.scroll.min(cursor);
+        }
+    }
+}
+
+// an operation that stopped with unmerged files continues in status, where the conflicts are resolved
+pub fn has_conflicts(backend: &dyn Backend) -> bool {
+    match backend.status(&[]) {
+        Ok(status) => status.entries.iter().any(|e| e.status == FileStatus::Unmerged),
+        Err(_) => false,
+    }
+}
+
+// checkouts, merges and pulls, which git refuses when they would overwrite local changes
+#[derive(Clone, Debug)]
+pub enum WorkTreeOperation {
+    Checkout(String),
+    Merge(String),
+    Pull,
+}
+impl WorkTreeOperation {
+    // with autostash, the local changes are stashed before and applied back after,
+    // they stay in the stash when applying them conflicts or when the operation itself stops on conflicts
+    pub fn run(&self, backend: &dyn Backend, autostash: bool) -> BackendResult<()> {
+        let run = || match self {
+            Self::Checkout(revision) => backend.checkout(revision),
+            Self::Merge(revision) => backend.merge(revision),
+            Self::Pull => backend.pull(),
+        };
+        if !autostash {
+            return run();
+        }
+
+        backend.stash_all(&format!("autostash around {}", self.description()))?;
+        let result = run();
+        if result.is_err() && backend.operation().is_some() {
+            return result.map_err(|e| format!("{}\nthe local changes are kept in the stash", e));
+        }
+        let applied = backend.stash_pop(0).map_err(|e| format!("{}\nthe local changes are kept in the stash", e));
+        result.and(applied)
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::Checkout(revision) => format!("checkout of {}", revision),
+            Self::Merge(revision) => format!("merge of {}", revision),
+            Self::Pull => "pull".to_owned(),
+        }
+    }
+
+    // asks to run it again around a stash, none when it failed for another reason than the local changes
+    pub fn confirm_autostash(
+        &self,
+        from: ModeKind,
+        error: &str,
+        on_confirm: fn(&ModeContext, String),
+    ) -> Option<ModeChangeInfo> {
+        let blocked = ["would be overwritten by", "You have unstaged changes", "Your index contains uncommitted changes"];
+        if !blocked.iter().any(|b| error.contains(b)) {
+            return None;
+        }
+        let message = format!(
+            "{}\n\nstash the local changes, run the {} and apply them back afterwards?",
+            error.trim(),
+            self.description()
+        );
+        Some(ModeChangeInfo::confirm(from, message, String::new(), on_confirm))
+    }
+}
+
+// a revision exported with git archive, the path is asked for first and then the directory its files go in
+#[derive(Clone, Debug)]
+pub struct ArchiveExport {
+    pub revision: String,
+    pub path: String, // named after the project and the revision until typed in, like a release tarball
+    label: String,    // how the revision is shown
+}
+impl ArchiveExport {
+    pub fn new(revision: String, label: &str) -> Self {
+        let project = env::current_dir().ok().and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()));
+        let path = match project {
+            Some(project) => format!("{}-{}.tar.gz", project, label),
+            None => format!("{}.tar.gz", label),
+        };
+        Self { revision, path, label: label.to_owned() }
+    }
+
+    // git picks tar, tar.gz, tgz or zip from the extension, plain tar for any other
+    pub fn ask_path(&self, from: ModeKind, on_submit: fn(&ModeContext, String)) -> ModeChangeInfo {
+        let placeholder = "type in the path of the archive, ending in .tar.gz or .zip...";
+        ModeChangeInfo::message_input(from, true, placeholder, on_submit)
+            .with_prompt(format!("export {} to", self.label), false)
+            .with_initial_message(&self.path)
+    }
+
+    // the directory is named after the archive by default, an empty one puts the files at its root
+    pub fn ask_prefix(&mut self, path: String, from: ModeKind, on_submit: fn(&ModeContext, String)) -> ModeChangeInfo {
+        self.path = path;
+        let file_name = self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path);
+        let name = [".tar.gz", ".tgz", ".zip", ".tar"].iter().find_map(|e| file_name.strip_suffix(e)).unwrap_or(file_name);
+        let placeholder = "type in the directory the files go in inside the archive, or nothing...";
+        ModeChangeInfo::message_input(from, false, placeholder, on_submit)
+            .with_prompt(format!("export {} to {} under", self.label, self.path), false)
+            .with_initial_message(format!("{}/", name))
+    }
+}
+
+// opens every issue referenced in the text using the "issue.url" template
+// only the global config sets it, so that a cloned repository cannot choose what gets opened
+pub fn open_issue_references(config: &Config, text: &str) -> BackendResult<()> {
+    let url_template = config.get_global("issue.url").ok_or_else(|| "'issue.url' is not configured".to_owned())?;
+    let is_web_url = ["http://", "https://"]
+        .iter()
+        .any(|scheme| url_template.get(..scheme.len()).is_some_and(|start| start.eq_ignore_ascii_case(scheme)));
+    if !is_web_url {
+        return Err(format!("'issue.url' should be an http or https url: '{}'", url_template));
+    }
+    let ids: Vec<_> =
+        issue_references(text, &config.issue_patterns()).into_iter().map(|(begin, end)| &text[begin..end]).collect();
+    // each once, in the order they are referenced
+    let mut opened = HashSet::new();
+    for id in ids.into_iter().filter(|id| opened.insert(*id)) {
+        let number = id.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let url = url_template.replace("{id}", id).replace("{number}", number);
+        platform::open_url(&url)?;
+    }
+    Ok(())
+}
+
+// the keys in the help of the header or in the tables above, the ones a custom action cannot take over
+fn is_builtin_key(mode_kind: &ModeKind, mode: &dyn ModeTrait, key: Key) -> bool {
+    let (_, left_help, right_help) = mode.header();
+    let in_help = [GLOBAL_HELP, left_help, right_help].iter().any(|help| {
+        help.split('[')
+            .skip(1)
+            .filter_map(|entry| entry.split_once(']'))
+            .any(|(label, _)| key_from_help_label(label) == Some(key))
+    });
+    in_help || find_key(MUTATING_KEYS, mode_kind, key) || find_key(WORK_TREE_KEYS, mode_kind, key)
+}
+
+// the custom action bound to the key with "action.<mode>.<key> = <command>", with its placeholders
+// replaced by the quoted values from the mode
+// only the global config binds them, so that a cloned repository cannot run commands, and not in read-only mode
+// as what they run is unknown
+pub fn custom_action(config: &Config, mode_kind: &ModeKind, mode: &dyn ModeTrait, key: Key) -> Option<BackendResult<String>> {
+    if config.get_bool("read_only", false) || is_builtin_key(mode_kind, mode, key) {
+        return None;
+    }
+    let template = config.get_global(&format!("action.{}.{}", mode_kind.name(), help_label_from_key(key)?))?;
+    let placeholders = mode.placeholders();
+
+    let mut command = String::new();
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once('{') {
+        command.push_str(before);
+        let (name, after) = match after.split_once('}') {
+            Some(split) => split,
+            None => {
+                command.push('{');
+                rest = after;
+                continue;
+            }
+        };
+        match placeholders.iter().find(|(n, _)| *n == name) {
+            Some((_, value)) => command.push_str(&shell_quote(value)),
+            None => return Some(Err(format!("'{{{}}}' is not available in {}", name, mode_kind.name()))),
+        }
+        rest = after;
+    }
+    command.push_str(rest);
+    Some(Ok(command))
+}
+
+// "date.format" is a strftime pattern, "date.local" converts the dates from the time zone of the commit to the local one
+pub fn date_format(config: &Config) -> DateFormat {
+    DateFormat {
+        pattern: config.get("date.format").unwrap_or("%Y-%m-%d").to_owned(),
+        local: config.get_bool("date.local", false),
+    }
+}
+
+// "clipboard.osc52" copies through the terminal instead of a clipboard utility, which is the default over ssh
+// and the fallback when no utility could copy
+// a utility can take its time, so this runs in a thread and the mode gets the result from on_copied
+pub fn copy_to_clipboard(ctx: &ModeContext, text: String, on_copied: fn(BackendResult<()>) -> ModeResponse) {
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        let over_ssh = env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some();
+        let write_code = || {
+            // locked so that the code does not land in the middle of a frame
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(&ui::clipboard_code(&text))
+                .and_then(|_| stdout.flush())
+                .map_err(|error| format!("could not write to the terminal: {}", error))
+        };
+        let result = match ctx.config.get("clipboard.osc52") {
+            Some(_) if ctx.config.get_bool("clipboard.osc52", false) => write_code(),
+            Some(_) => platform::copy_to_clipboard(&text),
+            None if over_ssh => write_code(),
+            None => platform::copy_to_clipboard(&text).or_else(|_| write_code()),
+        };
+        ctx.event_sender.send_response(on_copied(result));
+    });
 }
 
 pub fn fuzzy_matches(text: &str, pattern: &str) -> bool {
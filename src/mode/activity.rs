@@ -0,0 +1,202 @@
+use std::{collections::BTreeMap, thread};
+
+use crate::{
+    backend::{BackendResult, LogFilter},
+    mode::*,
+    platform::Key,
+    tool::date_from_unix_time,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub enum Response {
+    Refresh(BackendResult<Vec<i64>>),
+    Author(String),
+    Path(String),
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+enum Period {
+    #[default]
+    Week,
+    Month,
+}
+impl Period {
+    // weeks start on monday, the unix epoch was a thursday
+    fn bucket(&self, time: i64) -> i64 {
+        match self {
+            Self::Week => (time.div_euclid(SECONDS_PER_DAY) + 3).div_euclid(7),
+            Self::Month => {
+                let date = date_from_unix_time(time);
+                let year: i64 = date[..4].parse().unwrap_or(0);
+                let month: i64 = date[5..7].parse().unwrap_or(1);
+                year * 12 + month - 1
+            }
+        }
+    }
+
+    fn label(&self, bucket: i64) -> String {
+        match self {
+            Self::Week => date_from_unix_time((bucket * 7 - 3) * SECONDS_PER_DAY),
+            Self::Month => format!("{:04}-{:02}", bucket.div_euclid(12), bucket.rem_euclid(12) + 1),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ActivityBar {
+    label: String,
+    count: usize,
+    max: usize,
+}
+
+impl SelectEntryDraw for ActivityBar {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.bar(&self.label, self.count, self.max);
+        1
+    }
+}
+
+// the most recent period first, periods without commits are kept so the gaps show
+fn activity_bars(times: &[i64], period: Period) -> Vec<ActivityBar> {
+    let mut counts = BTreeMap::new();
+    for &time in times {
+        *counts.entry(period.bucket(time)).or_insert(0) += 1;
+    }
+
+    let (first, last) = match (counts.keys().next(), counts.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
+    let max = counts.values().copied().max().unwrap_or(0);
+    (first..=last)
+        .rev()
+        .map(|bucket| ActivityBar { label: period.label(bucket), count: counts.get(&bucket).copied().unwrap_or(0), max })
+        .collect()
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    times: Vec<i64>,
+    period: Period,
+    bars: Vec<ActivityBar>,
+    log_filter: LogFilter,
+    output: Output,
+    select: SelectMenu,
+}
+impl Mode {
+    fn refresh(&mut self, ctx: &ModeContext) {
+        self.waiting = true;
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.commit_times(&log_filter);
+            ctx.event_sender.send_response(ModeResponse::Activity(Response::Refresh(result)));
+        });
+    }
+
+    fn input(&self, ctx: &ModeContext, placeholder: &str, on_submit: fn(&ModeContext, String)) {
+        let not_empty = false;
+        ctx.event_sender.send_mode_change(
+            ModeKind::MessageInput,
+            ModeChangeInfo::message_input(ModeKind::Activity, not_empty, placeholder, on_submit),
+        );
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let Some(ModeInfo::Log(log_filter)) = info.info {
+            self.log_filter = log_filter;
+        }
+        self.refresh(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        // the filter description sits above the chart
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.bars.len(), available_height, key);
+
+        match key {
+            Key::Char('w') => {
+                self.period = match self.period {
+                    Period::Week => Period::Month,
+                    Period::Month => Period::Week,
+                };
+                self.bars = activity_bars(&self.times, self.period);
+                self.select.saturate_cursor(self.bars.len());
+            }
+            Key::Char('a') if !self.waiting => {
+                self.input(ctx, "type in the author to count commits of, empty for everyone...", |ctx, author| {
+                    ctx.event_sender.send_response(ModeResponse::Activity(Response::Author(author)));
+                });
+            }
+            Key::Char('p') if !self.waiting => {
+                self.input(ctx, "type in the path to count commits in, empty for the whole repository...", |ctx, path| {
+                    ctx.event_sender.send_response(ModeResponse::Activity(Response::Path(path)));
+                });
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Activity).unwrap();
+        let non_empty = |text: &str| Some(text.trim().to_owned()).filter(|t| !t.is_empty());
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(times) if times.is_empty() => {
+                        self.times = Vec::new();
+                        self.output.set("no commits!".to_owned());
+                    }
+                    Ok(times) => {
+                        self.times = times;
+                        self.output.set(String::new());
+                    }
+                    Err(error) => {
+                        self.times = Vec::new();
+                        self.output.set(error);
+                    }
+                }
+                self.bars = activity_bars(&self.times, self.period);
+                self.select.saturate_cursor(self.bars.len());
+            }
+            Response::Author(author) => {
+                self.log_filter.author = non_empty(&author);
+                self.refresh(ctx);
+            }
+            Response::Path(path) => {
+                self.log_filter.path = non_empty(&path);
+                self.refresh(ctx);
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        let name = match self.period {
+            Period::Week => "commits per week",
+            Period::Month => "commits per month",
+        };
+        (name, "[w]weeks/months [a]author [p]path", "[Left]back [arrows]move")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        drawer.fmt(format_args!("{}{}", Color::DarkYellow, self.log_filter.description()));
+        drawer.next_line();
+        if self.output.text().is_empty() {
+            drawer.select_menu(&self.select, 1, false, self.bars.iter());
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::thread;
+
+use crate::{
+    backend::{BackendResult, BlameEntry},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<BlameEntry>>),
+}
+
+#[derive(Default, Clone, Debug)]
+enum State {
+    #[default]
+    Idle,
+    Waiting,
+}
+
+impl SelectEntryDraw for BlameEntry {
+    fn draw(&self, drawer: &mut Drawer, hovered: bool, _: bool) -> usize {
+        const MAX_AUTHOR_CHAR_COUNT: usize = 12;
+        let author = match self.author.char_indices().nth(MAX_AUTHOR_CHAR_COUNT) {
+            Some((i, _)) => &self.author[..i],
+            None => &self.author,
+        };
+        let color = |color| if hovered { Color::White } else { color };
+
+        drawer.fmt(format_args!(
+            "{}{:.8} {}{} {}{:<width$} {}",
+            color(Color::DarkYellow),
+            &self.hash,
+            color(Color::DarkBlue),
+            &self.date,
+            color(Color::DarkGreen),
+            author,
+            Color::White,
+            width = MAX_AUTHOR_CHAR_COUNT,
+        ));
+
+        let available_width = (drawer.viewport_size.0 as usize).saturating_sub(8 + 1 + 10 + 1 + MAX_AUTHOR_CHAR_COUNT + 1);
+        let text = match self.text.char_indices().nth(available_width) {
+            Some((i, _)) => &self.text[..i],
+            None => &self.text,
+        };
+        drawer.str(text);
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    state: State,
+    blamed: Vec<(String, String, usize)>, // revision, path and line of every blame, the last one is shown
+    entries: Vec<BlameEntry>,
+    output: Output,
+    select: SelectMenu,
+}
+impl Mode {
+    fn request(&mut self, ctx: &ModeContext) {
+        self.state = State::Waiting;
+
+        let (revision, path, _) = self.blamed.last().unwrap().clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.blame(&revision, &path);
+            ctx.event_sender.send_response(ModeResponse::Blame(Response::Refresh(result)));
+        });
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let State::Waiting = self.state {
+            return;
+        }
+
+        if let Some(ModeInfo::Blame(revision, path)) = info.info {
+            self.blamed = vec![(revision, path, 0)];
+        }
+        self.output.set(String::new());
+        self.request(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        if !self.output.text().is_empty() {
+            // errors are dismissed with any key
+            self.output.set(String::new());
+            return ModeStatus { pending_input: true };
+        }
+        self.select.on_key(self.entries.len(), available_height, key);
+
+        if matches!(self.state, State::Waiting) {
+            return ModeStatus { pending_input: false };
+        }
+        match key {
+            Key::Enter => {
+                if let Some(entry) = self.entries.get(self.select.cursor) {
+                    let revision = entry.hash.clone();
+                    ctx.event_sender
+                        .send_mode_change(ModeKind::RevisionDetails, ModeChangeInfo::revision(ModeKind::Blame, revision));
+                }
+            }
+            Key::Char('p') => {
+                if let Some(entry) = self.entries.get(self.select.cursor) {
+                    match entry.previous.clone() {
+                        // peel off the commit of this line and keep looking at the same line before it
+                        Some((revision, path)) => {
+                            let line = entry.original_line.saturating_sub(1);
+                            self.blamed.last_mut().unwrap().2 = self.select.cursor;
+                            self.blamed.push((revision, path, line));
+                            self.request(ctx);
+                        }
+                        None => self.output.set(format!("the line was added in {}, it has no parent", entry.hash)),
+                    }
+                }
+            }
+            Key::Char('u') if self.blamed.len() > 1 => {
+                self.blamed.pop();
+                self.request(ctx);
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Blame).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok(entries) => {
+                        self.entries = entries;
+                        self.select.cursor = self.blamed.last().map(|(_, _, line)| *line).unwrap_or(0);
+                        self.select.saturate_cursor(self.entries.len());
+                        self.select.scroll = self.select.cursor.saturating_sub(3);
+                    }
+                    Err(error) => {
+                        // stay on the last blame that worked
+                        if self.blamed.len() > 1 {
+                            self.blamed.pop();
+                        }
+                        self.output.set(error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        match self.state {
+            State::Idle => false,
+            State::Waiting => true,
+        }
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("blame", "[enter]details [p]blame parent [u]undo blame parent", "[Left]back [arrows]move")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        if let Some((revision, path, _)) = self.blamed.last() {
+            drawer.fmt(format_args!("{}:{}", revision, path));
+        }
+        drawer.next_line();
+
+        if self.output.text().is_empty() {
+            drawer.select_menu(&self.select, 1, false, self.entries.iter());
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
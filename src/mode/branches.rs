@@ -3,15 +3,27 @@ use std::thread;
 use crate::{
     backend::{Backend, BackendResult, BranchEntry},
     mode::*,
-    platform::Key,
+    platform::{self, Key},
     ui::{Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
 };
 
 pub enum Response {
     Refresh(BackendResult<Vec<BranchEntry>>),
-    Checkout(usize),
+    Checkout(String),
     New(String),
     Merge,
+    Rebase,
+    RemoteRefs(BackendResult<Vec<(String, String)>>), // what each branch deletes on which remote
+    DeleteRemote(String),                             // "<remote> <ref>" one per line
+    Merged(BackendResult<(String, Vec<String>)>),
+    DeleteSelected(String, bool), // branch names one per line, force
+    OperationFailed(WorkTreeOperation, String),
+    Autostash,
+    Pasted(String), // the clipboard, offered as the url of the remote to add
+    RemoteUrl(String),
+    AddRemote(String), // the name, the url was asked for first
+    RemoteAdded(String),
+    FetchRemote(String),
 }
 
 #[derive(Clone, Debug)]
@@ -19,8 +31,15 @@ enum WaitOperation {
     Refresh,
     New,
     Delete,
+    DeleteRemote,
+    Merged,
+    Push,
     Merge,
+    Rebase,
     Checkout,
+    Paste,
+    AddRemote,
+    FetchRemote,
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +56,7 @@ impl Default for State {
 impl SelectEntryDraw for BranchEntry {
     fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
         let status = if self.checked_out { " (checked out)" } else { "" };
+        drawer.selected_marker(self.selected);
         drawer.fmt(format_args!("{}{}", self.name, status));
         1
     }
@@ -49,23 +69,85 @@ pub struct Mode {
     output: Output,
     select: SelectMenu,
     filter: Filter,
+    restore: Option<ListSession>,         // applied once the entries are loaded
+    autostash: Option<WorkTreeOperation>, // run again around a stash once confirmed
+    remote_url: Option<String>,           // of the remote being added, asked for before its name
 }
 
 impl Mode {
-    fn set_checkout(&mut self, entry_index: usize) {
+    fn set_checkout(&mut self, name: &str) {
         for entry in &mut self.entries {
-            entry.checked_out = false;
+            entry.checked_out = entry.name == name;
         }
+    }
+
+    // the log shows what the operation did, an operation refused because of local changes is offered again around a stash
+    fn run_work_tree_operation(&mut self, ctx: &ModeContext, operation: WorkTreeOperation, autostash: bool) {
+        self.state = match operation {
+            WorkTreeOperation::Checkout(_) => State::Waiting(WaitOperation::Checkout),
+            _ => State::Waiting(WaitOperation::Merge),
+        };
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            use std::ops::Deref;
+
+            match operation.run(ctx.backend.deref(), autostash) {
+                Ok(()) => {
+                    let response = match &operation {
+                        WorkTreeOperation::Checkout(name) => Response::Checkout(name.clone()),
+                        _ => Response::Merge,
+                    };
+                    ctx.event_sender.send_response(ModeResponse::Branches(response));
+                    ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
+                }
+                Err(error) if !autostash => {
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::OperationFailed(operation, error)));
+                }
+                Err(error) => ctx.event_sender.send_response(ModeResponse::Branches(Response::Refresh(Err(error)))),
+            }
+        });
+    }
 
-        self.entries[entry_index].checked_out = true;
+    // the list is shown before anything is deleted
+    fn confirm_delete(&self, ctx: &ModeContext, message: String, names: Vec<String>, force: bool) {
+        let message = format!("{}\n\n{}", message, names.join("\n"));
+        let on_confirm = match force {
+            false => |ctx: &ModeContext, names: String| {
+                ctx.event_sender.send_response(ModeResponse::Branches(Response::DeleteSelected(names, false)));
+            },
+            true => |ctx: &ModeContext, names: String| {
+                ctx.event_sender.send_response(ModeResponse::Branches(Response::DeleteSelected(names, true)));
+            },
+        };
+        ctx.event_sender.send_mode_change(
+            ModeKind::Confirm,
+            ModeChangeInfo::confirm(ModeKind::Branches, message, names.join("\n"), on_confirm),
+        );
+    }
+}
+
+// pasted urls are offered, anything else copied is left out of the prompt
+fn is_remote_url(text: &str) -> bool {
+    !text.contains(char::is_whitespace) && (text.contains("://") || (text.contains('@') && text.contains(':')))
+}
+
+// forks are told apart by their owner, like "alice" in "https://github.com/alice/project.git"
+fn remote_name(url: &str) -> String {
+    let segments: Vec<_> = url.trim_end_matches('/').split(['/', ':']).filter(|s| !s.is_empty()).collect();
+    match segments.len() {
+        0..=2 => String::new(),
+        len => segments[len - 2].to_owned(),
     }
 }
 
 impl ModeTrait for Mode {
-    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
         if let State::Waiting(_) = self.state {
             return;
         }
+        if let Some(ModeInfo::Session(session)) = info.info {
+            self.restore = Some(session);
+        }
         self.state = State::Waiting(WaitOperation::Refresh);
 
         self.output.set(String::new());
@@ -86,7 +168,8 @@ impl ModeTrait for Mode {
 
         let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
         if self.output.text().is_empty() {
-            self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+            let action = self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+            action.apply(&self.filter, &mut self.entries);
         } else {
             self.output.on_key(available_height, key);
         }
@@ -97,24 +180,11 @@ impl ModeTrait for Mode {
             Key::Enter => {
                 if let Some(current_entry_index) = current_entry_index {
                     let entry = &self.entries[current_entry_index];
-                    let name = entry.name.clone();
-                    let ctx = ctx.clone();
-
                     if entry.checked_out {
                         ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
                     } else {
-                        self.state = State::Waiting(WaitOperation::Checkout);
-
-                        thread::spawn(move || match ctx.backend.checkout(&name) {
-                            Ok(()) => {
-                                ctx.event_sender
-                                    .send_response(ModeResponse::Branches(Response::Checkout(current_entry_index)));
-                                ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
-                            }
-                            Err(error) => {
-                                ctx.event_sender.send_response(ModeResponse::Branches(Response::Refresh(Err(error))));
-                            }
-                        });
+                        let operation = WorkTreeOperation::Checkout(entry.name.clone());
+                        self.run_work_tree_operation(ctx, operation, false);
                     }
                 }
             }
@@ -129,6 +199,20 @@ impl ModeTrait for Mode {
                     ModeChangeInfo::message_input(ModeKind::Branches, not_empty, placeholder, on_submit),
                 );
             }
+            c @ Key::Char('D') | c @ Key::Char('d') if self.entries.iter().any(|e| e.selected) => {
+                let names = self.entries.iter().filter(|e| e.selected).map(|e| e.name.clone()).collect();
+                let force = c == Key::Char('D');
+                let message = if force { "force delete the selected branches?" } else { "delete the selected branches?" };
+                self.confirm_delete(ctx, message.to_owned(), names, force);
+            }
+            Key::Char('x') => {
+                self.state = State::Waiting(WaitOperation::Merged);
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let result = ctx.backend.merged_branches();
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::Merged(result)));
+                });
+            }
             c @ Key::Char('D') | c @ Key::Char('d') => {
                 if let Some(current_entry_index) = current_entry_index {
                     let entry = &self.entries[current_entry_index];
@@ -144,21 +228,60 @@ impl ModeTrait for Mode {
                     request(ctx, move |b| b.delete_branch(&name, force));
                 }
             }
+            Key::Char('p') if current_entry_index.is_some() => {
+                self.state = State::Waiting(WaitOperation::Push);
+                let names: Vec<_> =
+                    selected_or_hovered(&self.entries, current_entry_index).iter().map(|e| e.name.clone()).collect();
+                request(ctx, move |b| b.push_branches(&names));
+            }
+            // the upstreams are looked up first, so that what is deleted is shown as it is on the remotes
+            Key::Char('X') if current_entry_index.is_some() => {
+                self.state = State::Waiting(WaitOperation::DeleteRemote);
+                let names: Vec<_> =
+                    selected_or_hovered(&self.entries, current_entry_index).iter().map(|e| e.name.clone()).collect();
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let refs = ctx.backend.remote_branch_refs(&names);
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::RemoteRefs(refs)));
+                });
+            }
+            // a clipboard utility can take a while to start, powershell on windows
+            Key::Char('R') => {
+                self.state = State::Waiting(WaitOperation::Paste);
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let text = platform::paste_from_clipboard().unwrap_or_default();
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::Pasted(text)));
+                });
+            }
             Key::Char('m') => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let operation = WorkTreeOperation::Merge(self.entries[current_entry_index].name.clone());
+                    self.run_work_tree_operation(ctx, operation, false);
+                }
+            }
+            Key::Char('B') => {
                 if let Some(current_entry_index) = current_entry_index {
                     let entry = &self.entries[current_entry_index];
-                    self.state = State::Waiting(WaitOperation::Merge);
+                    self.state = State::Waiting(WaitOperation::Rebase);
 
                     let name = entry.name.clone();
                     let ctx = ctx.clone();
-                    thread::spawn(move || match ctx.backend.merge(&name) {
-                        Ok(()) => {
-                            ctx.event_sender.send_response(ModeResponse::Branches(Response::Merge));
-                            ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
-                        }
-                        Err(error) => {
-                            ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
-                            ctx.event_sender.send_response(ModeResponse::Branches(Response::Refresh(Err(error))));
+                    thread::spawn(move || {
+                        use std::ops::Deref;
+
+                        match ctx.backend.rebase(&name, None) {
+                            Ok(()) => {
+                                ctx.event_sender.send_response(ModeResponse::Branches(Response::Rebase));
+                                ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Branches));
+                            }
+                            Err(_) if has_conflicts(ctx.backend.deref()) => {
+                                ctx.event_sender.send_response(ModeResponse::Branches(Response::Rebase));
+                                ctx.event_sender.send_mode_change(ModeKind::Status, ModeChangeInfo::new(ModeKind::Branches));
+                            }
+                            Err(error) => {
+                                ctx.event_sender.send_response(ModeResponse::Branches(Response::Refresh(Err(error))));
+                            }
                         }
                     });
                 }
@@ -194,19 +317,153 @@ impl ModeTrait for Mode {
                         self.select.cursor = i;
                     }
                 }
+                if let Some(session) = self.restore.take() {
+                    session.restore(&mut self.select, &mut self.filter, &self.entries, |e| &e.name);
+                }
             }
-            Response::Checkout(entry_index) => {
+            Response::Checkout(name) => {
                 self.state = State::Idle;
-                self.set_checkout(entry_index);
+                self.set_checkout(&name);
             }
-            Response::Merge => self.state = State::Idle,
+            Response::Merge | Response::Rebase => self.state = State::Idle,
             Response::New(message) => {
                 self.state = State::Waiting(WaitOperation::New);
                 request(ctx, move |b| b.new_branch(&message));
             }
+            Response::RemoteRefs(Ok(refs)) => {
+                self.state = State::Idle;
+                let refs: Vec<_> = refs.iter().map(|(remote, remote_ref)| format!("{} {}", remote, remote_ref)).collect();
+                let refs = refs.join("\n");
+                if ctx.config.get_bool("confirm.delete_remote", true) {
+                    let message = format!("delete from the remote?\n\n{}", refs);
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::Confirm,
+                        ModeChangeInfo::confirm(ModeKind::Branches, message, refs, |ctx, refs| {
+                            ctx.event_sender.send_response(ModeResponse::Branches(Response::DeleteRemote(refs)));
+                        }),
+                    );
+                } else {
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::DeleteRemote(refs)));
+                }
+            }
+            Response::RemoteRefs(Err(error)) => {
+                self.state = State::Idle;
+                self.output.set(error);
+            }
+            Response::DeleteRemote(refs) => {
+                self.state = State::Waiting(WaitOperation::DeleteRemote);
+                let refs: Vec<_> = refs
+                    .lines()
+                    .filter_map(|line| line.split_once(' '))
+                    .map(|(remote, remote_ref)| (remote.to_owned(), remote_ref.to_owned()))
+                    .collect();
+                request(ctx, move |b| b.delete_remote_branches(&refs));
+            }
+            Response::Merged(result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok((default_branch, names)) if names.is_empty() => {
+                        self.output.set(format!("no other branch is merged into {}", default_branch))
+                    }
+                    Ok((default_branch, names)) => {
+                        for entry in &mut self.entries {
+                            entry.selected = names.contains(&entry.name);
+                        }
+                        // "--delete" would check them against the checked out branch instead of the default one
+                        let message = format!("delete the branches merged into {}?", default_branch);
+                        self.confirm_delete(ctx, message, names, true);
+                    }
+                    Err(error) => self.output.set(error),
+                }
+            }
+            Response::OperationFailed(operation, error) => {
+                self.state = State::Idle;
+                let on_confirm = |ctx: &ModeContext, _| {
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::Autostash));
+                };
+                match operation.confirm_autostash(ModeKind::Branches, &error, on_confirm) {
+                    Some(info) => {
+                        self.autostash = Some(operation);
+                        ctx.event_sender.send_mode_change(ModeKind::Confirm, info);
+                    }
+                    None => self.output.set(error),
+                }
+            }
+            Response::Autostash => {
+                if let Some(operation) = self.autostash.take() {
+                    self.run_work_tree_operation(ctx, operation, true);
+                }
+            }
+            Response::Pasted(text) => {
+                self.state = State::Idle;
+                let url = text.trim().to_owned();
+                let url = if is_remote_url(&url) { url } else { String::new() };
+                let not_empty = true;
+                let placeholder = "type in the url of the remote to add...";
+                let on_submit = |ctx: &ModeContext, url: String| {
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::RemoteUrl(url)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::Branches, not_empty, placeholder, on_submit)
+                        .with_initial_message(url),
+                );
+            }
+            Response::RemoteUrl(url) => {
+                let url = url.trim().to_owned();
+                let not_empty = true;
+                let placeholder = "type in the name of the remote...";
+                let on_submit = |ctx: &ModeContext, name: String| {
+                    ctx.event_sender.send_response(ModeResponse::Branches(Response::AddRemote(name)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::Branches, not_empty, placeholder, on_submit)
+                        .with_initial_message(remote_name(&url)),
+                );
+                self.remote_url = Some(url);
+            }
+            Response::AddRemote(name) => {
+                if let Some(url) = self.remote_url.take() {
+                    self.state = State::Waiting(WaitOperation::AddRemote);
+                    let name = name.trim().to_owned();
+                    let ctx = ctx.clone();
+                    thread::spawn(move || {
+                        let response = match ctx.backend.add_remote(&name, &url) {
+                            Ok(()) => Response::RemoteAdded(name),
+                            Err(error) => Response::Refresh(Err(error)),
+                        };
+                        ctx.event_sender.send_response(ModeResponse::Branches(response));
+                    });
+                }
+            }
+            // fetching a large fork can wait
+            Response::RemoteAdded(name) => {
+                self.state = State::Idle;
+                let message = format!("remote '{}' added, fetch it now?", name);
+                ctx.event_sender.send_mode_change(
+                    ModeKind::Confirm,
+                    ModeChangeInfo::confirm(ModeKind::Branches, message, name, |ctx, name| {
+                        ctx.event_sender.send_response(ModeResponse::Branches(Response::FetchRemote(name)));
+                    }),
+                );
+            }
+            Response::FetchRemote(name) => {
+                self.state = State::Waiting(WaitOperation::FetchRemote);
+                request(ctx, move |b| b.fetch_remote(&name));
+            }
+            Response::DeleteSelected(names, force) => {
+                self.state = State::Waiting(WaitOperation::Delete);
+                let names: Vec<_> = names.lines().map(String::from).collect();
+                request(ctx, move |b| b.delete_branches(&names, force));
+            }
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
@@ -219,14 +476,33 @@ impl ModeTrait for Mode {
             State::Idle | State::Waiting(WaitOperation::Refresh) => "branches",
             State::Waiting(WaitOperation::New) => "new branch",
             State::Waiting(WaitOperation::Delete) => "delete branch",
+            State::Waiting(WaitOperation::DeleteRemote) => "delete remote branch",
+            State::Waiting(WaitOperation::Merged) => "merged branches",
+            State::Waiting(WaitOperation::Push) => "push branches",
             State::Waiting(WaitOperation::Merge) => "merge branch",
+            State::Waiting(WaitOperation::Rebase) => "rebase onto branch",
             State::Waiting(WaitOperation::Checkout) => "checkout",
+            State::Waiting(WaitOperation::Paste) | State::Waiting(WaitOperation::AddRemote) => "add remote",
+            State::Waiting(WaitOperation::FetchRemote) => "fetch remote",
         };
-        let (left_help, right_help) =
-            ("[enter]checkout [n]new [d]delete [D]force delete [m]merge", "[arrows]move [ctrl+f]filter");
+        let (left_help, right_help) = (
+            "[enter]checkout [n]new [d]delete [D]force delete [x]delete merged [p]push [X]delete from remote [m]merge [B]rebase onto [R]add remote",
+            "[space]select [*]select all [arrows]move [ctrl+f]filter",
+        );
         (name, left_help, right_help)
     }
 
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        match self.filter.get_visible_index(self.select.cursor) {
+            Some(current_entry_index) => vec![("branch", self.entries[current_entry_index].name.clone())],
+            None => Vec::new(),
+        }
+    }
+
+    fn session(&self) -> Option<ListSession> {
+        Some(ListSession::save(&self.select, &self.filter, &self.entries, |e| &e.name))
+    }
+
     fn draw(&self, drawer: &mut Drawer) {
         let filter_line_count = drawer.filter(&self.filter);
         if self.output.text.is_empty() {
@@ -0,0 +1,140 @@
+use std::thread;
+
+use crate::{
+    backend::BackendResult,
+    mode::*,
+    platform::Key,
+    ui::{Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+// how far back in the log co-authors are harvested from
+const AUTHORS_COMMIT_COUNT: usize = 1000;
+
+pub enum Response {
+    Refresh(BackendResult<Vec<String>>),
+}
+
+#[derive(Clone, Debug)]
+struct AuthorEntry {
+    selected: bool,
+    name: String,
+}
+impl FilterEntry for AuthorEntry {
+    fn fuzzy_matches(&self, pattern: &str) -> bool {
+        fuzzy_matches(&self.name, pattern)
+    }
+}
+impl SelectableEntry for AuthorEntry {
+    fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    fn set_selected(&mut self, selected: bool) {
+        self.selected = selected;
+    }
+}
+impl SelectEntryDraw for AuthorEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.selected_marker(self.selected);
+        drawer.str(&self.name);
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    entries: Vec<AuthorEntry>,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        self.waiting = true;
+
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.recent_authors(AUTHORS_COMMIT_COUNT);
+            ctx.event_sender.send_response(ModeResponse::CoAuthors(Response::Refresh(result)));
+        });
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.filter.filter(self.entries.iter());
+            self.select.saturate_cursor(self.filter.visible_indices().len());
+
+            return ModeStatus { pending_input: true };
+        }
+
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+        let action = self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+        action.apply(&self.filter, &mut self.entries);
+
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Enter => {
+                // the hovered author is used when none is selected
+                let mut authors: Vec<_> = self.entries.iter().filter(|e| e.selected).map(|e| e.name.clone()).collect();
+                if authors.is_empty() {
+                    if let Some(i) = self.filter.get_visible_index(self.select.cursor) {
+                        authors.push(self.entries[i].name.clone());
+                    }
+                }
+
+                if !authors.is_empty() {
+                    let trailers = authors.into_iter().map(|a| format!("Co-authored-by: {}", a)).collect();
+                    ctx.event_sender.send_mode_revert();
+                    ctx.event_sender
+                        .send_response(ModeResponse::MessageInput(message_input::Response::AddTrailers(trailers)));
+                }
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::CoAuthors).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(authors) => {
+                        self.entries = authors.into_iter().map(|name| AuthorEntry { selected: false, name }).collect()
+                    }
+                    Err(error) => self.output.set(error),
+                }
+
+                self.filter.filter(self.entries.iter());
+                self.select.saturate_cursor(self.filter.visible_indices().len());
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("co-authors", "[enter]add trailers", "[Left]back [arrows]move [space]toggle [ctrl+f]filter")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text().is_empty() {
+            drawer.select_menu(
+                &self.select,
+                filter_line_count,
+                false,
+                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+            );
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::thread;
+
+use crate::{
+    backend::BackendResult,
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Line(String, bool),
+    Done(BackendResult<()>),
+}
+
+// the command line and an empty line above the output
+const COMMAND_LINES_COUNT: usize = 2;
+
+// the output of a custom action from the config
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    running: bool,
+    command: String,
+    lines: Vec<String>,
+    progress: bool, // the last line is a progress line, the next one replaces it
+    output: Output,
+}
+impl Mode {
+    fn set_output(&mut self, ctx: &ModeContext) {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + COMMAND_LINES_COUNT);
+        self.output.set(self.lines.join("\n"));
+        self.output.on_key(available_height, Key::End);
+    }
+
+    fn run(&mut self, ctx: &ModeContext) {
+        self.running = true;
+        self.lines.clear();
+        self.progress = false;
+        self.set_output(ctx);
+
+        let command = self.command.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.run_command(&command, &mut |line, progress| {
+                ctx.event_sender.send_response(ModeResponse::Command(Response::Line(line.to_owned(), progress)));
+            });
+            ctx.event_sender.send_response(ModeResponse::Command(Response::Done(result)));
+        });
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        match info.info {
+            Some(ModeInfo::Command(Ok(command))) => {
+                self.command = command;
+                self.run(ctx);
+            }
+            Some(ModeInfo::Command(Err(error))) => {
+                self.lines = vec![error];
+                self.set_output(ctx);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + COMMAND_LINES_COUNT);
+        self.output.on_key(available_height, key);
+
+        if let Key::Char('r') = key {
+            if !self.running && !self.command.is_empty() {
+                self.run(ctx);
+            }
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Command).unwrap();
+        match response {
+            Response::Line(line, progress) => {
+                if self.progress {
+                    self.lines.pop();
+                }
+                self.lines.push(line);
+                self.progress = progress;
+            }
+            Response::Done(result) => {
+                self.running = false;
+                self.progress = false;
+                match result {
+                    Ok(()) => self.lines.push("done!".to_owned()),
+                    Err(error) => self.lines.push(error),
+                }
+            }
+        }
+        self.set_output(ctx);
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.running
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("command", "[r]run again", "[Left]back [arrows]scroll")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        drawer.fmt(format_args!("{}$ {}{}", Color::DarkYellow, self.command, Color::White));
+        drawer.next_line();
+        drawer.next_line();
+        drawer.output(&self.output);
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+
+use crate::{
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, RESERVED_LINES_COUNT},
+};
+
+#[derive(Clone)]
+pub struct OnConfirm(fn(ctx: &ModeContext, payload: String));
+impl Default for OnConfirm {
+    fn default() -> Self {
+        Self(|_ctx: &ModeContext, _payload: String| {})
+    }
+}
+
+impl fmt::Debug for OnConfirm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "on_confirm: fn")
+    }
+}
+
+// an answer among several instead of yes, like the mode of a reset
+#[derive(Clone, Debug)]
+pub struct Choice {
+    pub key: char,
+    pub name: String,
+    pub on_choose: OnConfirm,
+}
+impl Choice {
+    pub fn new<S: Into<String>>(key: char, name: S, on_choose: fn(ctx: &ModeContext, payload: String)) -> Self {
+        Self { key, name: name.into(), on_choose: OnConfirm(on_choose) }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModeInfo {
+    pub message: String, // what exactly is going to happen
+    pub payload: String, // handed back to on_confirm, e.g. the revision to reset to
+    pub on_confirm: OnConfirm,
+    pub choices: Vec<Choice>,
+    pub default_choice: Option<char>, // chosen with [enter]
+}
+impl ModeInfo {
+    pub fn new(message: String, payload: String, on_confirm: fn(ctx: &ModeContext, payload: String)) -> Self {
+        Self { message, payload, on_confirm: OnConfirm(on_confirm), choices: Vec::new(), default_choice: None }
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    output: Output,
+    payload: String,
+    on_confirm: OnConfirm,
+    choices: Vec<Choice>,
+    default_choice: Option<char>,
+    help: String, // the keys of the choices
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, _ctx: &ModeContext, info: ModeChangeInfo) {
+        let mode_info = as_variant!(info.info.unwrap(), super::ModeInfo::Confirm).unwrap();
+        self.output.set(mode_info.message);
+        self.payload = mode_info.payload;
+        self.on_confirm = mode_info.on_confirm;
+        self.choices = mode_info.choices;
+        self.default_choice = mode_info.default_choice;
+
+        let mut help: Vec<_> = self.choices.iter().map(|c| format!("[{}]{}", c.key, c.name)).collect();
+        if let Some(choice) = self.choices.iter().find(|c| Some(c.key) == self.default_choice) {
+            help.push(format!("[enter]{}", choice.name));
+        }
+        help.push("[n]no".to_owned());
+        self.help = help.join(" ");
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let chosen = match key {
+            Key::Char(c) => self.choices.iter().find(|choice| choice.key == c),
+            Key::Enter => self.choices.iter().find(|choice| Some(choice.key) == self.default_choice),
+            _ => None,
+        };
+        if let Some(choice) = chosen {
+            ctx.event_sender.send_mode_revert();
+            choice.on_choose.0(ctx, self.payload.clone());
+            return ModeStatus { pending_input: true };
+        }
+
+        match key {
+            Key::Char('y') | Key::Char('Y') if self.choices.is_empty() => {
+                ctx.event_sender.send_mode_revert();
+                self.on_confirm.0(ctx, self.payload.clone());
+            }
+            Key::Char('n') | Key::Char('N') | Key::Esc | Key::Left => ctx.event_sender.send_mode_revert(),
+            _ => {
+                let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 2);
+                self.output.on_key(available_height, key);
+            }
+        }
+
+        ModeStatus { pending_input: true }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, _response: ModeResponse) {}
+
+    fn is_waiting_response(&self) -> bool {
+        false
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        match self.choices.is_empty() {
+            true => ("confirm", "[y]yes [n]no", "[Esc]cancel [arrows]scroll"),
+            false => ("confirm", &self.help, "[Esc]cancel [arrows]scroll"),
+        }
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        match &self.choices[..] {
+            [] => drawer.fmt(format_args!("{}are you sure? [y/n]", Color::DarkYellow)),
+            [init @ .., last] => {
+                let names: Vec<_> = init.iter().map(|c| c.name.as_str()).collect();
+                let keys: Vec<_> = self.choices.iter().map(|c| c.key.to_string()).collect();
+                drawer.fmt(format_args!(
+                    "{}{} or {}? [{}/n]",
+                    Color::DarkYellow,
+                    names.join(", "),
+                    last.name,
+                    keys.join("/")
+                ));
+            }
+        }
+        drawer.next_line();
+        drawer.next_line();
+        drawer.fmt(format_args!("{}", Color::White));
+        drawer.output(&self.output);
+    }
+}
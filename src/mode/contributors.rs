@@ -0,0 +1,168 @@
+use std::thread;
+
+use crate::{
+    backend::{AuthorEntry, BackendResult, LogFilter},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<AuthorEntry>>),
+    Path(String),
+    DateRange(String),
+}
+
+impl SelectEntryDraw for AuthorEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.fmt(format_args!("{}{:>6}{} {}", Color::DarkYellow, self.commit_count, Color::White, self.name));
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    entries: Vec<AuthorEntry>, // the most commits first
+    log_filter: LogFilter,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+impl Mode {
+    fn refresh(&mut self, ctx: &ModeContext) {
+        self.waiting = true;
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.shortlog(&log_filter);
+            ctx.event_sender.send_response(ModeResponse::Contributors(Response::Refresh(result)));
+        });
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let Some(ModeInfo::Log(log_filter)) = info.info {
+            self.log_filter = LogFilter { author: None, ..log_filter };
+        }
+        self.refresh(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.filter.filter(self.entries.iter());
+            self.select.saturate_cursor(self.filter.visible_indices().len());
+
+            return ModeStatus { pending_input: true };
+        }
+
+        // the scope line sits above the filter and the list
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+
+        let current_entry_index = self.filter.get_visible_index(self.select.cursor);
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Enter => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let author = self.entries[current_entry_index].name.clone();
+                    let log_filter = LogFilter { author: Some(author), ..self.log_filter.clone() };
+                    ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::log(ModeKind::Contributors, log_filter));
+                }
+            }
+            Key::Char('a') => {
+                let log_filter = self.log_filter.clone();
+                ctx.event_sender
+                    .send_mode_change(ModeKind::Activity, ModeChangeInfo::log(ModeKind::Contributors, log_filter));
+            }
+            Key::Char('p') if !self.waiting => {
+                let not_empty = false;
+                let placeholder = "type in the path to count commits in, empty for the whole repository...";
+                let on_submit = |ctx: &ModeContext, path: String| {
+                    ctx.event_sender.send_response(ModeResponse::Contributors(Response::Path(path)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::Contributors, not_empty, placeholder, on_submit),
+                );
+            }
+            Key::Char('d') if !self.waiting => {
+                let not_empty = false;
+                let placeholder = "type in a date range like '2024-01-01..2024-06-30' or '3 months ago..', empty for all...";
+                let on_submit = |ctx: &ModeContext, range: String| {
+                    ctx.event_sender.send_response(ModeResponse::Contributors(Response::DateRange(range)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::Contributors, not_empty, placeholder, on_submit),
+                );
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Contributors).unwrap();
+        let non_empty = |text: &str| Some(text.trim().to_owned()).filter(|t| !t.is_empty());
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(entries) => {
+                        self.entries = entries;
+                        self.output.set(String::new());
+                    }
+                    Err(error) => {
+                        self.entries = Vec::new();
+                        self.output.set(error);
+                    }
+                }
+                self.filter.filter(self.entries.iter());
+                self.select.saturate_cursor(self.filter.visible_indices().len());
+            }
+            Response::Path(path) => {
+                self.log_filter.path = non_empty(&path);
+                self.refresh(ctx);
+            }
+            Response::DateRange(range) => {
+                // a single date is taken as the start of the range
+                let (since, until) = range.split_once("..").unwrap_or((&range, ""));
+                self.log_filter.since = non_empty(since);
+                self.log_filter.until = non_empty(until);
+                self.refresh(ctx);
+            }
+        }
+    }
+
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("contributors", "[enter]author log [a]activity [p]path [d]date range", "[Left]back [arrows]move [ctrl+f]filter")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        drawer.fmt(format_args!("{}{}", Color::DarkYellow, self.log_filter.description()));
+        drawer.next_line();
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text().is_empty() {
+            drawer.select_menu(
+                &self.select,
+                1 + filter_line_count,
+                false,
+                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+            );
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer},
+};
+
+const DEFAULT_TYPES: &[&str] =
+    &["feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Field {
+    Type,
+    Scope,
+    Subject,
+    Body,
+    Breaking,
+}
+impl Field {
+    const ALL: [Field; 5] = [Field::Type, Field::Scope, Field::Subject, Field::Body, Field::Breaking];
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Type => "type",
+            Self::Scope => "scope",
+            Self::Subject => "subject",
+            Self::Body => "body",
+            Self::Breaking => "breaking change",
+        }
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    types: Vec<String>,
+    type_index: usize,
+    field: usize,
+    scope: ReadLine,
+    subject: ReadLine,
+    body: ReadLine,
+    breaking: ReadLine,
+}
+impl Mode {
+    fn readline(&mut self, field: Field) -> Option<&mut ReadLine> {
+        match field {
+            Field::Type => None,
+            Field::Scope => Some(&mut self.scope),
+            Field::Subject => Some(&mut self.subject),
+            Field::Body => Some(&mut self.body),
+            Field::Breaking => Some(&mut self.breaking),
+        }
+    }
+
+    // type(scope)!: subject, then the body and the breaking change footer as separate paragraphs
+    fn message(&self) -> String {
+        let mut message = self.types.get(self.type_index).cloned().unwrap_or_default();
+        if !self.scope.input().trim().is_empty() {
+            message.push_str(&format!("({})", self.scope.input().trim()));
+        }
+        if !self.breaking.input().trim().is_empty() {
+            message.push('!');
+        }
+        message.push_str(": ");
+        message.push_str(self.subject.input().trim());
+
+        if !self.body.input().trim().is_empty() {
+            message.push_str("\n\n");
+            message.push_str(self.body.input().trim());
+        }
+        if !self.breaking.input().trim().is_empty() {
+            message.push_str("\n\nBREAKING CHANGE: ");
+            message.push_str(self.breaking.input().trim());
+        }
+        message
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        self.types = ctx.config.get_list("commit.conventional_types").into_iter().map(String::from).collect();
+        if self.types.is_empty() {
+            self.types = DEFAULT_TYPES.iter().map(|&t| t.to_owned()).collect();
+        }
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let field = Field::ALL[self.field];
+        match key {
+            Key::Esc => ctx.event_sender.send_mode_revert(),
+            Key::Enter => {
+                if !self.subject.input().trim().is_empty() {
+                    let message = self.message();
+                    ctx.event_sender.send_mode_revert();
+                    ctx.event_sender.send_response(ModeResponse::Status(status::Response::Commit(message)));
+                }
+            }
+            Key::Tab | Key::Down => self.field = (self.field + 1) % Field::ALL.len(),
+            Key::Up => self.field = (self.field + Field::ALL.len() - 1) % Field::ALL.len(),
+            Key::Right if field == Field::Type => self.type_index = (self.type_index + 1) % self.types.len(),
+            Key::Left if field == Field::Type => {
+                self.type_index = (self.type_index + self.types.len() - 1) % self.types.len()
+            }
+            Key::Ctrl('j') if field == Field::Body => self.body.new_line(),
+            _ => {
+                if let Some(readline) = self.readline(field) {
+                    readline.on_key(key);
+                }
+            }
+        }
+
+        ModeStatus { pending_input: true }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, _response: ModeResponse) {}
+
+    fn is_waiting_response(&self) -> bool {
+        false
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("conventional commit", "[enter]commit [tab]next field [left/right]change type [ctrl+j]new line", "[Esc]cancel")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        const NAME_WIDTH: usize = 16;
+
+        for (i, field) in Field::ALL.iter().enumerate() {
+            let focused = i == self.field;
+            let name_color = if focused { Color::DarkYellow } else { Color::DarkGray };
+            drawer.fmt(format_args!("{}{:<width$}{}", name_color, field.name(), Color::White, width = NAME_WIDTH));
+
+            let readline = match field {
+                Field::Type => {
+                    let type_name = self.types.get(self.type_index).map(String::as_str).unwrap_or("");
+                    if focused {
+                        drawer.fmt(format_args!("< {} >", type_name));
+                    } else {
+                        drawer.str(type_name);
+                    }
+                    drawer.next_line();
+                    continue;
+                }
+                Field::Scope => &self.scope,
+                Field::Subject => &self.subject,
+                Field::Body => &self.body,
+                Field::Breaking => &self.breaking,
+            };
+            if focused {
+                drawer.readline(readline, "");
+            } else {
+                drawer.str(&readline.input().replace('\n', " "));
+            }
+            drawer.next_line();
+        }
+
+        drawer.next_line();
+        drawer.fmt(format_args!("{}preview:", Color::DarkGray));
+        drawer.next_line();
+        drawer.output(&Output::new(self.message()));
+    }
+}
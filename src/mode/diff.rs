@@ -1,13 +1,28 @@
 use crate::{
+    backend::{FileStatus, RevisionEntry},
     mode::*,
     platform::Key,
-    ui::{Drawer, RESERVED_LINES_COUNT},
+    ui::{glyph, Drawer, RESERVED_LINES_COUNT},
 };
+use std::{collections::BTreeSet, fs, thread};
 
 pub enum Response {
     Refresh(String),
+    EditorError(String),
+    Stage(Result<(), String>),
+    HunkEdited(Result<(), String>),
+    Copied(&'static str, Result<(), String>), // what was copied
 }
 
+// in the git directory like the one of "git add --edit", not where other users can read or replace it
+const EDIT_HUNK_FILE_NAME: &str = "GITUSE_EDIT_HUNK.patch";
+const EDIT_HUNK_HELP: &str = "\
+# Edit the hunk, it is staged once the editor is closed.
+# To leave a removed line out, turn its '-' into ' '.
+# To leave an added line out, delete it.
+# Lines starting with '#' are ignored, an empty hunk stages nothing.
+";
+
 #[derive(Clone, Debug)]
 enum State {
     Idle,
@@ -19,30 +34,176 @@ impl Default for State {
     }
 }
 
+// a hunk and where it is in the formatted output
+#[derive(Clone, Debug)]
+struct Hunk {
+    output_line: usize, // the "--- file:Line n ---" header
+    first_line: usize,  // the first diff line, after the optional function heading
+    filename: String,
+    deleted: bool,
+    old_line_number: u32,
+    line_number: u32,
+    lines: Vec<String>, // starting with ' ', '+', '-' or '\'
+}
+impl Hunk {
+    fn line(&self, output_line: usize) -> Option<&str> {
+        self.lines.get(output_line.checked_sub(self.first_line)?).map(String::as_str)
+    }
+
+    // the line in the current file, removed lines point at the line following them
+    fn file_line_number(&self, output_line: usize) -> u32 {
+        let count = output_line.saturating_sub(self.first_line).min(self.lines.len());
+        let added = self.lines[..count].iter().filter(|line| !line.starts_with(['-', '\\'])).count();
+        self.line_number + added as u32
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Mode {
     state: State,
     output: Output,
+    raw: String, // the diff as git printed it, what is copied
+    hunks: Vec<Hunk>,
+    message: Option<String>,
     from: ModeKind,
+    cursor: usize,             // only moves in working tree diffs, other diffs just scroll
+    selected: BTreeSet<usize>, // output lines picked for staging
+    edited_hunk: Option<Hunk>, // the hunk open in the editor
+    search: Search,
 }
 
 impl ModeTrait for Mode {
-    fn on_enter(&mut self, _ctx: &ModeContext, info: ModeChangeInfo) {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
         if let State::Waiting = self.state {
             return;
         }
         self.state = State::Waiting;
         self.from = info.from;
         self.output.set(String::new());
+        self.raw.clear();
+        self.hunks.clear();
+        self.message = None;
+        self.cursor = 0;
+        self.selected.clear();
+        self.search = Search::default();
+
+        if let Some(ModeInfo::Diff(revision)) = info.info {
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let output = match ctx.backend.diff(revision.as_deref(), &[]) {
+                    Ok(output) => output,
+                    Err(error) => error,
+                };
+                ctx.event_sender.send_response(ModeResponse::Diff(Response::Refresh(output)));
+            });
+        }
     }
 
     fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height =
+            (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + self.search.is_searching() as usize);
+        if self.search.has_focus() {
+            self.search.on_key(key);
+            self.find();
+            self.show_line(self.search.current_line(), available_height);
+
+            return ModeStatus { pending_input: true };
+        }
+
         match self.state {
             State::Idle => {
-                if self.output.line_count() > 1 {
-                    let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+                self.message = None;
+                if self.is_working_tree() {
+                    self.move_cursor(available_height, key);
+                } else if self.output.line_count() > 1 {
                     self.output.on_key(available_height, key);
                 }
+
+                match key {
+                    Key::Char('/') => self.search.enter(),
+                    Key::Char('n') => {
+                        let line = self.search.next_match();
+                        self.show_line(line, available_height);
+                    }
+                    Key::Char('N') => {
+                        let line = self.search.previous_match();
+                        self.show_line(line, available_height);
+                    }
+                    Key::Char('y') => {
+                        copy_to_clipboard(ctx, self.raw.clone(), |result| {
+                            ModeResponse::Diff(Response::Copied("copied the diff", result))
+                        });
+                    }
+                    Key::Char('Y') => {
+                        if let Some(file_diff) = self.hovered_file_diff() {
+                            copy_to_clipboard(ctx, file_diff.to_owned(), |result| {
+                                ModeResponse::Diff(Response::Copied("copied the diff of the hovered file", result))
+                            });
+                        }
+                    }
+                    Key::Char('e') => {
+                        let line = self.hovered_line();
+                        match self.hovered_hunk() {
+                            Some(hunk) if hunk.deleted => self.message = Some(format!("'{}' was deleted", hunk.filename)),
+                            Some(hunk) => {
+                                let line_number = hunk.file_line_number(line) as _;
+                                ctx.event_sender.send_editor(hunk.filename.clone(), line_number, |ctx, result| {
+                                    if let Err(error) = result {
+                                        ctx.event_sender.send_response(ModeResponse::Diff(Response::EditorError(error)));
+                                    }
+                                });
+                            }
+                            None => (),
+                        }
+                    }
+                    Key::Char(' ') if self.is_working_tree() => {
+                        let changed = self
+                            .hovered_hunk()
+                            .and_then(|hunk| hunk.line(self.cursor))
+                            .is_some_and(|line| line.starts_with(['+', '-']));
+                        if changed && !self.selected.remove(&self.cursor) {
+                            self.selected.insert(self.cursor);
+                        }
+                    }
+                    Key::Char('E') if self.is_working_tree() => {
+                        if let Some(hunk) = self.hovered_hunk().cloned() {
+                            let mut text = String::from(EDIT_HUNK_HELP);
+                            for line in &hunk.lines {
+                                text.push_str(line);
+                                text.push('\n');
+                            }
+
+                            let path = ctx.backend.git_dir().join(EDIT_HUNK_FILE_NAME);
+                            match fs::write(&path, text) {
+                                Ok(()) => {
+                                    self.edited_hunk = Some(hunk);
+                                    ctx.event_sender.send_editor(path.to_string_lossy().into(), 0, |ctx, result| {
+                                        ctx.event_sender.send_response(ModeResponse::Diff(Response::HunkEdited(result)));
+                                    });
+                                }
+                                Err(error) => self.message = Some(format!("could not write {:?}: {}", path, error)),
+                            }
+                        }
+                    }
+                    Key::Char('a') if self.is_working_tree() => {
+                        let mut selected = self.selected.clone();
+                        if selected.is_empty() {
+                            selected.insert(self.cursor);
+                        }
+                        match partial_patch(&self.hunks, &selected) {
+                            Some(patch) => {
+                                self.state = State::Waiting;
+                                let ctx = ctx.clone();
+                                thread::spawn(move || {
+                                    let result = ctx.backend.stage_patch(&patch);
+                                    ctx.event_sender.send_response(ModeResponse::Diff(Response::Stage(result)));
+                                });
+                            }
+                            None => self.message = Some("only added or removed lines can be staged".to_owned()),
+                        }
+                    }
+                    _ => (),
+                }
             }
             _ => (),
         }
@@ -50,7 +211,7 @@ impl ModeTrait for Mode {
         ModeStatus { pending_input: false }
     }
 
-    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
         let response = as_variant!(response, ModeResponse::Diff).unwrap();
         match response {
             Response::Refresh(info) => {
@@ -58,10 +219,68 @@ impl ModeTrait for Mode {
                     self.state = State::Idle;
                 }
                 if let State::Idle = self.state {
-                    let info = format_files_diff(&info);
-                    self.output.set(info);
+                    let (text, hunks) = format_files_diff(&info);
+                    self.output.set(text);
+                    self.raw = info;
+                    self.hunks = hunks;
+                    self.selected.clear();
+                    if self.search.is_searching() {
+                        self.find();
+                    }
+                    if self.is_working_tree() {
+                        self.cursor = self.cursor.min(self.output.line_count().saturating_sub(1));
+                        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+                        self.output.scroll_to_line(self.cursor, available_height);
+                    }
                 }
             }
+            Response::EditorError(error) => self.message = Some(error),
+            Response::HunkEdited(result) => {
+                let hunk = match self.edited_hunk.take() {
+                    Some(hunk) => hunk,
+                    None => return,
+                };
+                let path = ctx.backend.git_dir().join(EDIT_HUNK_FILE_NAME);
+                let text =
+                    result.and_then(|()| fs::read_to_string(&path).map_err(|e| format!("could not read {:?}: {}", path, e)));
+                let _ = fs::remove_file(&path);
+
+                match text.and_then(|text| edited_patch(&hunk, &text)) {
+                    Ok(Some(patch)) => {
+                        self.state = State::Waiting;
+                        let ctx = ctx.clone();
+                        thread::spawn(move || {
+                            let result = ctx.backend.stage_patch(&patch);
+                            ctx.event_sender.send_response(ModeResponse::Diff(Response::Stage(result)));
+                        });
+                    }
+                    Ok(None) => (),
+                    Err(error) => self.message = Some(error),
+                }
+            }
+            Response::Copied(copied, result) => {
+                self.message = Some(result.map_or_else(|error| error, |()| copied.to_owned()))
+            }
+            Response::Stage(Err(error)) => {
+                self.state = State::Idle;
+                self.message = Some(error);
+            }
+            Response::Stage(Ok(())) => {
+                // only the files in view, the rest of the working tree was not part of this diff
+                let mut entries: Vec<_> = self.hunks.iter().map(|hunk| &hunk.filename).collect();
+                entries.dedup();
+                let entries: Vec<_> =
+                    entries.into_iter().map(|filename| RevisionEntry::new(filename.clone(), FileStatus::Modified)).collect();
+
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let output = match ctx.backend.diff(None, &entries) {
+                        Ok(output) => output,
+                        Err(error) => error,
+                    };
+                    ctx.event_sender.send_response(ModeResponse::Diff(Response::Refresh(output)));
+                });
+            }
         }
     }
 
@@ -72,48 +291,300 @@ impl ModeTrait for Mode {
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.search.has_focus()
+    }
+
     fn header(&self) -> (&str, &str, &str) {
-        ("details", "", "[Left]back [arrows]move")
+        let right_help = "[/]search [n]next match [N]previous match [Left]back [arrows]move";
+        if self.is_working_tree() {
+            ("details", "[e]open in editor [y]copy [Y]copy file [space]select line [a]stage lines [E]edit hunk", right_help)
+        } else {
+            ("details", "[e]open in editor [y]copy [Y]copy file", right_help)
+        }
     }
 
     fn draw(&self, drawer: &mut Drawer) {
         //log(format!("start to draw diff: \n"));
-        drawer.diff_format(&self.output);
+        let mut header_height = 0;
+        if let Some(message) = &self.message {
+            drawer.str(message);
+            drawer.next_line();
+            header_height += 1;
+        }
+        header_height += drawer.search(&self.search);
+        let cursor = Some(self.cursor).filter(|_| self.is_working_tree());
+        let searched = self.search.as_str();
+        drawer.diff_format(&self.output, header_height, cursor, |line| self.selected.contains(&line), searched);
     }
 }
 
+impl Mode {
+    // the working tree diff opened from status, its lines can be staged
+    fn is_working_tree(&self) -> bool {
+        self.from == ModeKind::Status
+    }
+
+    fn hovered_line(&self) -> usize {
+        if self.is_working_tree() {
+            self.cursor
+        } else {
+            self.output.scroll()
+        }
+    }
+
+    // the hunk of the hovered line, or the first hunk of the file whose header is there
+    fn hovered_hunk(&self) -> Option<&Hunk> {
+        let line = self.hovered_line();
+        let on_file_header = match self.output.text().lines().nth(line) {
+            Some(text) => text.starts_with(DIFF_FORMAT_FILE_HEADER_LINE) || text.starts_with(DIFF_FORMAT_FILE_HEADER_CONTENT),
+            None => false,
+        };
+        if on_file_header {
+            self.hunks.iter().find(|hunk| hunk.output_line >= line)
+        } else {
+            self.hunks.iter().rev().find(|hunk| hunk.output_line <= line)
+        }
+    }
+
+    // the part of the git diff for the file of the hovered line, the files are in the same order in both
+    fn hovered_file_diff(&self) -> Option<&str> {
+        // the hovered line may be the one above the name of the file
+        let file_headers = self.output.text().lines().take(self.hovered_line() + 2);
+        let file_index = file_headers.filter(|l| l.starts_with(DIFF_FORMAT_FILE_HEADER_CONTENT)).count().checked_sub(1)?;
+        split_files(&self.raw).into_iter().nth(file_index)
+    }
+
+    // the text the lines show is searched, without the markers of the headers, from the hovered line on
+    fn find(&mut self) {
+        let markers = [DIFF_FORMAT_FILE_HEADER_LINE, DIFF_FORMAT_FILE_HEADER_CONTENT, DIFF_FORMAT_LINE_HEADER];
+        let lines = self.output.text().lines().map(|line| markers.iter().find_map(|m| line.strip_prefix(m)).unwrap_or(line));
+        self.search.find(lines, self.hovered_line());
+    }
+
+    fn show_line(&mut self, line: Option<usize>, available_height: usize) {
+        if let Some(line) = line {
+            if self.is_working_tree() {
+                self.cursor = line;
+            }
+            self.output.scroll_to_line(line, available_height);
+        }
+    }
+
+    fn move_cursor(&mut self, available_height: usize, key: Key) {
+        let half_height = available_height / 2;
+        self.cursor = match key {
+            Key::Down => self.cursor + 1,
+            Key::Up => self.cursor.saturating_sub(1),
+            Key::Home => 0,
+            Key::End => usize::MAX,
+            Key::PageDown => self.cursor + half_height,
+            Key::PageUp => self.cursor.saturating_sub(half_height),
+            _ => return,
+        };
+        self.cursor = self.cursor.min(self.output.line_count().saturating_sub(1));
+        self.output.scroll_to_line(self.cursor, available_height);
+    }
+}
+
+// a patch for the index with only the selected lines, unselected removals stay as context and unselected additions are left out
+fn partial_patch(hunks: &[Hunk], selected: &BTreeSet<usize>) -> Option<String> {
+    let mut patch = String::new();
+    let mut filename: Option<&str> = None;
+    let mut offset = 0i64; // lines added minus removed by the previous hunks of the file
+
+    for hunk in hunks {
+        let is_selected = |i: usize| selected.contains(&(hunk.first_line + i));
+        let has_change = hunk.lines.iter().enumerate().any(|(i, line)| is_selected(i) && line.starts_with(['+', '-']));
+        if !has_change {
+            continue;
+        }
+
+        if filename != Some(&hunk.filename) {
+            filename = Some(&hunk.filename);
+            offset = 0;
+            patch.push_str(&patch_header(&hunk.filename));
+        }
+
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0, 0);
+        let mut kept = true; // whether the previous line is in the patch, "\ No newline" belongs to it
+        for (i, line) in hunk.lines.iter().enumerate() {
+            match line.chars().next() {
+                Some('+') if is_selected(i) => new_count += 1,
+                Some('+') => {
+                    kept = false;
+                    continue;
+                }
+                Some('-') if is_selected(i) => old_count += 1,
+                Some('-') => {
+                    let no_newline = hunk.lines.get(i + 1).is_some_and(|next| next.starts_with('\\'));
+                    let added_after =
+                        hunk.lines.iter().enumerate().skip(i).any(|(j, l)| is_selected(j) && l.starts_with('+'));
+                    if no_newline && added_after {
+                        // the last line stays but needs a newline before the added lines
+                        body.push_str(&format!("{}\n{}\n+{}\n", line, hunk.lines[i + 1], &line[1..]));
+                        kept = false;
+                    } else {
+                        body.push_str(&format!(" {}\n", &line[1..]));
+                        kept = true;
+                    }
+                    old_count += 1;
+                    new_count += 1;
+                    continue;
+                }
+                Some('\\') if !kept => continue,
+                Some('\\') => (),
+                _ => {
+                    old_count += 1;
+                    new_count += 1;
+                }
+            }
+            body.push_str(line);
+            body.push('\n');
+            kept = true;
+        }
+
+        let new_line_number = hunk.old_line_number as i64 + offset;
+        offset += new_count - old_count;
+        patch.push_str(&hunk_header(hunk.old_line_number as _, old_count, new_line_number, new_count));
+        patch.push_str(&body);
+    }
+
+    filename.map(|_| patch)
+}
+
+// the patch for the hunk as it was edited, none when no change was left in it
+fn edited_patch(hunk: &Hunk, text: &str) -> Result<Option<String>, String> {
+    let mut body = String::new();
+    let (mut old_count, mut new_count) = (0, 0);
+    let mut has_change = false;
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        // editors may strip the trailing space of empty context lines
+        let line = if line.is_empty() { " " } else { line };
+        match line.chars().next() {
+            Some(' ') => {
+                old_count += 1;
+                new_count += 1;
+            }
+            Some('-') => old_count += 1,
+            Some('+') => new_count += 1,
+            Some('\\') => (),
+            _ => return Err(format!("the edited hunk has an invalid line: '{}'", line)),
+        }
+        has_change |= line.starts_with(['+', '-']);
+        body.push_str(line);
+        body.push('\n');
+    }
+
+    if !has_change {
+        return Ok(None);
+    }
+    let mut patch = patch_header(&hunk.filename);
+    patch.push_str(&hunk_header(hunk.old_line_number as _, old_count, hunk.old_line_number as _, new_count));
+    patch.push_str(&body);
+    Ok(Some(patch))
+}
+
+// each file of a git diff starts with its "diff --git" line, or a "Submodule" line for submodules
+fn split_files(diff: &str) -> Vec<&str> {
+    let mut starts: Vec<_> = diff
+        .match_indices("diff --git ")
+        .chain(diff.match_indices("Submodule "))
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || diff[..i].ends_with('\n'))
+        .collect();
+    starts.sort_unstable();
+    starts.push(diff.len());
+    starts.windows(2).map(|w| &diff[w[0]..w[1]]).collect()
+}
+
+fn patch_header(filename: &str) -> String {
+    format!("diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n", filename)
+}
+
+fn hunk_header(old_line_number: i64, old_count: i64, new_line_number: i64, new_count: i64) -> String {
+    format!("@@ -{},{} +{},{} @@\n", old_line_number, old_count, new_line_number, new_count)
+}
+
 pub struct LineDiff {
+    old_line_number: u32,
     line_number: u32,
+    heading: bool, // the text starts with the function name git puts after the hunk range
     text: String,
 }
 impl LineDiff {
-    fn new(line_number: u32) -> Self {
-        Self { line_number, text: String::new() }
+    fn new(old_line_number: u32, line_number: u32) -> Self {
+        Self { old_line_number, line_number, heading: false, text: String::new() }
+    }
+}
+
+// the byte ranges of an added line that git would call whitespace errors: trailing whitespace and an indentation
+// mixing tabs and spaces, or the whole "\ No newline at end of file" line when it follows an added line
+pub fn whitespace_errors(line: &str, previous_line: Option<&str>) -> Vec<(usize, usize)> {
+    if line.starts_with('\\') {
+        return match previous_line {
+            Some(previous_line) if previous_line.starts_with('+') => vec![(0, line.len())],
+            _ => Vec::new(),
+        };
+    }
+    let content = match line.strip_prefix('+') {
+        Some(content) if !content.is_empty() => content,
+        _ => return Vec::new(),
+    };
+    // a line with only whitespace is trailing whitespace as a whole
+    if content.trim().is_empty() {
+        return vec![(1, line.len())];
+    }
+
+    let mut errors = Vec::new();
+    let indentation = &content[..content.len() - content.trim_start_matches([' ', '\t']).len()];
+    if indentation.contains(' ') && indentation.contains('\t') {
+        errors.push((1, 1 + indentation.len()));
     }
+    let trimmed_len = content.trim_end().len();
+    if trimmed_len < content.len() {
+        errors.push((1 + trimmed_len, line.len()));
+    }
+    errors
 }
 
 pub const DIFF_FORMAT_FILE_HEADER_LINE: &str = "@@@L";
 pub const DIFF_FORMAT_FILE_HEADER_CONTENT: &str = "@@@H";
 pub const DIFF_FORMAT_LINE_HEADER: &str = "@@@N";
+const LFS_POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/";
 
 #[derive(Debug, Clone)]
 pub enum FileMode {
     Modified,
     Deleted,
+    Renamed(String),   // from the path
+    Copied(String),    // from the path
+    Submodule(String), // like "abc1234 -> def5678" or "contains modified content"
 }
 
 pub struct FileDiff {
     filename: String,
     mode: FileMode,
     lines: Vec<LineDiff>,
+    lfs: bool,                  // the diff is between lfs pointer files, not the actual content
+    similarity: String,         // like "87%" for renames and copies
+    submodule_log: Vec<String>, // the subjects of the commits a submodule moved by
 }
 impl FileDiff {
     fn new<S: Into<String>>(filename: S, mode: FileMode) -> Self {
-        Self { filename: filename.into(), mode, lines: Vec::new() }
+        Self {
+            filename: filename.into(),
+            mode,
+            lines: Vec::new(),
+            lfs: false,
+            similarity: String::new(),
+            submodule_log: Vec::new(),
+        }
     }
 
-    fn new_line(&mut self, line_number: u32) {
-        let line_diff = LineDiff::new(line_number);
+    fn new_line(&mut self, old_line_number: u32, line_number: u32) {
+        let line_diff = LineDiff::new(old_line_number, line_number);
         self.lines.push(line_diff);
     }
 }
@@ -135,33 +606,89 @@ impl FilesDiff {
         file_diff.mode = mode;
     }
 
-    fn new_line(&mut self, line_number: u32) {
-        self.files.last_mut().unwrap().new_line(line_number);
+    fn new_line(&mut self, old_line_number: u32, line_number: u32) {
+        self.files.last_mut().unwrap().new_line(old_line_number, line_number);
     }
 
     fn add_text(&mut self, text: &str) {
         let file_diff = self.files.last_mut().unwrap();
+        if text.trim_start_matches(['+', '-', ' ']).starts_with(LFS_POINTER_VERSION) {
+            file_diff.lfs = true;
+        }
         let line_diff = file_diff.lines.last_mut().unwrap();
         line_diff.text.push_str(text);
     }
 
-    fn output(&self) -> String {
+    // the extended header lines git writes before "---" for renames and copies
+    fn add_header_line(&mut self, line: &str) {
+        let file_diff = self.files.last_mut().unwrap();
+        if let Some(similarity) = line.strip_prefix("similarity index ") {
+            file_diff.similarity = similarity.to_owned();
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file_diff.mode = FileMode::Renamed(from.to_owned());
+        } else if let Some(from) = line.strip_prefix("copy from ") {
+            file_diff.mode = FileMode::Copied(from.to_owned());
+        }
+    }
+
+    fn add_submodule_log(&mut self, line: &str) {
+        self.files.last_mut().unwrap().submodule_log.push(line.to_owned());
+    }
+
+    fn add_heading(&mut self, text: &str) {
+        self.add_text(text);
+        self.files.last_mut().unwrap().lines.last_mut().unwrap().heading = true;
+    }
+
+    fn output(&self) -> (String, Vec<Hunk>) {
         let mut text = String::new();
+        let mut hunks = Vec::new();
+        let mut line_count = 0;
         for file_diff in self.files.iter() {
             text.push_str(&format!("{}\n", DIFF_FORMAT_FILE_HEADER_LINE));
-            text.push_str(&format!("{}{:?}: {}\n", DIFF_FORMAT_FILE_HEADER_CONTENT, file_diff.mode, file_diff.filename));
+            let lfs = if file_diff.lfs { " (lfs pointer)" } else { "" };
+            let (filename, similarity) = (&file_diff.filename, &file_diff.similarity);
+            let header = match &file_diff.mode {
+                FileMode::Renamed(from) => {
+                    format!("Renamed: {} {} {} ({}){}", from, glyph("→", "->"), filename, similarity, lfs)
+                }
+                FileMode::Copied(from) => {
+                    format!("Copied: {} {} {} ({}){}", from, glyph("→", "->"), filename, similarity, lfs)
+                }
+                FileMode::Submodule(change) => format!("Submodule {}: {}", filename, change),
+                mode => format!("{:?}: {}{}", mode, filename, lfs),
+            };
+            text.push_str(&format!("{}{}\n", DIFF_FORMAT_FILE_HEADER_CONTENT, header));
             text.push_str(&format!("{}\n", DIFF_FORMAT_FILE_HEADER_LINE));
+            line_count += 3;
+
+            for line in file_diff.submodule_log.iter() {
+                text.push_str(line);
+                text.push('\n');
+                line_count += 1;
+            }
 
             for line_diff in file_diff.lines.iter() {
+                let heading_count = line_diff.heading as usize;
+                hunks.push(Hunk {
+                    output_line: line_count,
+                    first_line: line_count + 1 + heading_count,
+                    filename: file_diff.filename.clone(),
+                    deleted: matches!(file_diff.mode, FileMode::Deleted),
+                    old_line_number: line_diff.old_line_number,
+                    line_number: line_diff.line_number,
+                    lines: line_diff.text.lines().skip(heading_count).map(String::from).collect(),
+                });
                 text.push_str(&format!(
                     "{}@--- {}:Line {} ---@\n",
                     DIFF_FORMAT_LINE_HEADER, file_diff.filename, line_diff.line_number
                 ));
                 text.push_str(&line_diff.text);
+                line_count += 1 + line_diff.text.lines().count();
             }
         }
 
-        text
+        (text, hunks)
     }
 }
 
@@ -172,8 +699,10 @@ enum ParseState {
     FileMode(FileMode),
     FileContent,
     FileEnd,
-    LineHeader(u32), // line number
+    LineHeader(u32, u32), // old and new line number
     LineContent,
+    Submodule(String, FileMode), // path
+    SubmoduleLog,
 }
 
 enum ParseEvent {
@@ -181,12 +710,17 @@ enum ParseEvent {
     FileDiffMode(FileMode),
     FileDiffContent,
     FileDiffEnd,
-    LineDiffStart(u32), // line number
+    LineDiffStart(u32, u32), // old and new line number
     LineDiffContent,
+    Submodule(String, FileMode), // path
 }
 impl ParseEvent {
     fn new(state: &ParseState, line: &str) -> Self {
-        if line.starts_with("diff --git") {
+        if let Some(summary) = line.strip_prefix("Submodule ") {
+            // with --submodule=log: "Submodule path abc1234..def5678 (rewind):" followed by "  > subject" lines
+            let (path, change) = parse_submodule_summary(summary);
+            return Self::Submodule(path, FileMode::Submodule(change));
+        } else if line.starts_with("diff --git") {
             // diff --git a/xxx/xxx.c b/xxx/xxx.c
             let pos = line.find(" b/").unwrap();
             let filename = line.get(pos + 3..).unwrap();
@@ -198,17 +732,12 @@ impl ParseEvent {
             // +++ b/xxx/xxx.c
             return Self::FileDiffEnd;
         } else if line.starts_with("@@ ") {
-            // @@ -xx,xx +xx,xx @@
-            let pos = line.find(" +").unwrap();
-            let line_number = line.get(pos + 2..).unwrap();
-            let line_number = match line_number.find(",") {
-                Some(pos) => {
-                    let line_number = line_number.get(..pos).unwrap();
-                    line_number.parse::<u32>().unwrap()
-                }
-                None => 0,
+            // @@ -xx,xx +xx,xx @@, the counts are left out for single lines
+            let start = |prefix: char| {
+                let range = line.split(' ').find_map(|range| range.strip_prefix(prefix));
+                range.and_then(|range| range.split(',').next()?.parse::<u32>().ok()).unwrap_or(0)
             };
-            return Self::LineDiffStart(line_number);
+            return Self::LineDiffStart(start('-'), start('+'));
         }
 
         match state {
@@ -228,6 +757,9 @@ impl ParseEvent {
 impl ParseState {
     fn line(&mut self, line: &str) -> Self {
         let parse_event = ParseEvent::new(self, line);
+        if let ParseEvent::Submodule(path, mode) = parse_event {
+            return Self::Submodule(path, mode);
+        }
         match self {
             ParseState::Start => {
                 if let ParseEvent::FileDiffStart(filename, mode) = parse_event {
@@ -244,21 +776,20 @@ impl ParseState {
                 }
             }
             ParseState::FileMode(_) => Self::FileContent,
-            ParseState::FileContent => {
-                if let ParseEvent::FileDiffEnd = parse_event {
-                    Self::FileEnd
-                } else {
-                    Self::FileContent
-                }
-            }
+            ParseState::FileContent => match parse_event {
+                ParseEvent::FileDiffEnd => Self::FileEnd,
+                // files renamed or copied without a change have no content
+                ParseEvent::FileDiffStart(filename, mode) => Self::FileHeader(filename, mode),
+                _ => Self::FileContent,
+            },
             ParseState::FileEnd => {
-                if let ParseEvent::LineDiffStart(line_number) = parse_event {
-                    Self::LineHeader(line_number)
+                if let ParseEvent::LineDiffStart(old_line_number, line_number) = parse_event {
+                    Self::LineHeader(old_line_number, line_number)
                 } else {
                     panic!("Invalid!\n");
                 }
             }
-            ParseState::LineHeader(_) => {
+            ParseState::LineHeader(..) => {
                 if let ParseEvent::LineDiffContent = parse_event {
                     Self::LineContent
                 } else {
@@ -270,25 +801,35 @@ impl ParseState {
                     Self::LineContent
                 } else if let ParseEvent::FileDiffStart(filename, mode) = parse_event {
                     Self::FileHeader(filename, mode)
-                } else if let ParseEvent::LineDiffStart(line_number) = parse_event {
-                    Self::LineHeader(line_number)
+                } else if let ParseEvent::LineDiffStart(old_line_number, line_number) = parse_event {
+                    Self::LineHeader(old_line_number, line_number)
                 } else {
                     panic!("Invalid!\n");
                 }
             }
+            ParseState::Submodule(..) | ParseState::SubmoduleLog => {
+                if let ParseEvent::FileDiffStart(filename, mode) = parse_event {
+                    Self::FileHeader(filename, mode)
+                } else {
+                    Self::SubmoduleLog
+                }
+            }
         }
     }
 
     fn output(&mut self, line: &str, files_diff: &mut FilesDiff) {
         match self {
             ParseState::FileHeader(filename, mode) => files_diff.new_file(filename.clone(), mode.clone()),
+            ParseState::Submodule(path, mode) => files_diff.new_file(path.clone(), mode.clone()),
+            ParseState::SubmoduleLog => files_diff.add_submodule_log(line),
             ParseState::FileMode(mode) => files_diff.file_mode(mode.clone()),
-            ParseState::LineHeader(line_number) => {
-                files_diff.new_line(*line_number);
+            ParseState::FileContent => files_diff.add_header_line(line),
+            ParseState::LineHeader(old_line_number, line_number) => {
+                files_diff.new_line(*old_line_number, *line_number);
                 // the line content after "@@ -xx,xx +xx,xx @@"
                 if let Some(pos) = line.find(" @@ ") {
                     let text = format!("{}\n", line.get(pos + 4..).unwrap());
-                    files_diff.add_text(&text);
+                    files_diff.add_heading(&text);
                 }
             }
             ParseState::LineContent => {
@@ -300,7 +841,31 @@ impl ParseState {
     }
 }
 
-fn format_files_diff(text: &str) -> String {
+// the path and the change of "path abc1234..def5678 (new submodule):", or of "path contains modified content"
+fn parse_submodule_summary(summary: &str) -> (String, String) {
+    let summary = summary.trim_end_matches(':');
+    let words: Vec<_> = summary.split(' ').collect();
+    let is_range = |word: &&str| word.contains("..") && word.chars().all(|c| c.is_ascii_hexdigit() || c == '.');
+    let (path_len, change) = match words.iter().position(is_range) {
+        Some(i) => {
+            let (from, to) = words[i].split_once("...").or_else(|| words[i].split_once("..")).unwrap_or_default();
+            let mut change = format!("{} -> {}", from, to);
+            for word in &words[i + 1..] {
+                change.push(' ');
+                change.push_str(word);
+            }
+            (i, change)
+        }
+        // "contains modified content" or "contains untracked content"
+        None => match words.iter().position(|&w| w == "contains") {
+            Some(i) => (i, words[i..].join(" ")),
+            None => (words.len(), String::new()),
+        },
+    };
+    (words[..path_len].join(" "), change)
+}
+
+fn format_files_diff(text: &str) -> (String, Vec<Hunk>) {
     let mut files_diff = FilesDiff::new();
     let mut parse_state = ParseState::Start;
     for line in text.lines() {
@@ -0,0 +1,114 @@
+use std::thread;
+
+use crate::{
+    backend::BackendResult,
+    mode::*,
+    platform::Key,
+    syntax,
+    ui::{Drawer, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Content(BackendResult<String>),
+}
+
+#[derive(Default, Clone, Debug)]
+enum State {
+    #[default]
+    Idle,
+    Waiting,
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    state: State,
+    revision: String,
+    path: String,
+    line: usize,
+    output: Output,
+    error: bool, // the output is an error message, not the file content
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let State::Waiting = self.state {
+            return;
+        }
+        self.state = State::Waiting;
+
+        if let Some(ModeInfo::FileView(revision, path, line)) = info.info {
+            self.revision = revision;
+            self.path = path;
+            self.line = line;
+        }
+        self.output.set(String::new());
+
+        let ctx = ctx.clone();
+        let revision = self.revision.clone();
+        let path = self.path.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.file_content(&revision, &path);
+            ctx.event_sender.send_response(ModeResponse::FileView(Response::Content(result)));
+        });
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+        self.output.on_key(available_height, key);
+
+        match key {
+            Key::Char('h') => {
+                let path = self.path.clone();
+                ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::file_history(ModeKind::FileView, path));
+            }
+            Key::Char('B') => {
+                let (revision, path) = (self.revision.clone(), self.path.clone());
+                ctx.event_sender.send_mode_change(ModeKind::Blame, ModeChangeInfo::blame(ModeKind::FileView, revision, path));
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::FileView).unwrap();
+        match response {
+            Response::Content(result) => {
+                self.state = State::Idle;
+                self.error = result.is_err();
+                match result {
+                    Ok(content) => self.output.set(content),
+                    Err(error) => self.output.set(error),
+                }
+                // the line is brought to the middle of the screen
+                if self.line > 0 && !self.error {
+                    let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+                    let last_line = self.output.line_count().saturating_sub(1);
+                    self.output.scroll_to_line((self.line - 1 + available_height / 2).min(last_line), available_height);
+                }
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        match self.state {
+            State::Idle => false,
+            State::Waiting => true,
+        }
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("file view", "[h]file history [B]blame", "[Left]back [arrows]scroll")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        if self.error {
+            drawer.output(&self.output);
+        } else {
+            drawer.fmt(format_args!("{}:{}", self.revision, self.path));
+            drawer.next_line();
+            drawer.source(&self.output, syntax::syntax_for_path(&self.path));
+        }
+    }
+}
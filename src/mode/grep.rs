@@ -0,0 +1,202 @@
+use std::thread;
+
+use crate::{
+    backend::{BackendResult, GrepEntry},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<GrepEntry>>),
+    Pattern(String),
+    Revision(String),
+    EditorError(String),
+}
+
+impl SelectEntryDraw for GrepEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.fmt(format_args!("{}{}:{}{} ", Color::DarkYellow, self.path, self.line, Color::White));
+        drawer.str(self.text.trim());
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    pattern: String,
+    revision: Option<String>, // the working tree when none
+    entries: Vec<GrepEntry>,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+impl Mode {
+    fn search(&mut self, ctx: &ModeContext) {
+        self.waiting = true;
+        let pattern = self.pattern.clone();
+        let revision = self.revision.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.grep(&pattern, revision.as_deref());
+            ctx.event_sender.send_response(ModeResponse::Grep(Response::Refresh(result)));
+        });
+    }
+
+    fn input(&self, ctx: &ModeContext, not_empty: bool, placeholder: &str, on_submit: fn(&ModeContext, String)) {
+        ctx.event_sender.send_mode_change(
+            ModeKind::MessageInput,
+            ModeChangeInfo::message_input(ModeKind::Grep, not_empty, placeholder, on_submit),
+        );
+    }
+
+    fn input_pattern(&self, ctx: &ModeContext) {
+        self.input(ctx, true, "type in the pattern to search for...", |ctx, pattern| {
+            ctx.event_sender.send_response(ModeResponse::Grep(Response::Pattern(pattern)));
+        });
+    }
+
+    fn open_editor(&self, ctx: &ModeContext, entry: &GrepEntry) {
+        ctx.event_sender.send_editor(entry.path.clone(), entry.line, |ctx, result| {
+            if let Err(error) = result {
+                ctx.event_sender.send_response(ModeResponse::Grep(Response::EditorError(error)));
+            }
+        });
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        // bare repositories have no working tree to search
+        if ctx.backend.is_bare() {
+            self.revision = Some("HEAD".to_owned());
+        }
+        self.input_pattern(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.filter.filter(self.entries.iter());
+            self.select.saturate_cursor(self.filter.visible_indices().len());
+
+            return ModeStatus { pending_input: true };
+        }
+
+        // the searched pattern sits above the filter and the results
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+
+        let current_entry_index = self.filter.get_visible_index(self.select.cursor);
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Enter => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let entry = &self.entries[current_entry_index];
+                    match &self.revision {
+                        Some(revision) => ctx.event_sender.send_mode_change(
+                            ModeKind::FileView,
+                            ModeChangeInfo::file_view_at(ModeKind::Grep, revision.clone(), entry.path.clone(), entry.line),
+                        ),
+                        None => self.open_editor(ctx, entry),
+                    }
+                }
+            }
+            Key::Char('e') => {
+                if let Some(current_entry_index) = current_entry_index {
+                    self.open_editor(ctx, &self.entries[current_entry_index]);
+                }
+            }
+            Key::Char('n') if !self.waiting => self.input_pattern(ctx),
+            Key::Char('r') if !self.waiting => {
+                self.input(ctx, false, "type in the revision to search, empty for the working tree...", |ctx, revision| {
+                    ctx.event_sender.send_response(ModeResponse::Grep(Response::Revision(revision)));
+                });
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Grep).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(entries) if entries.is_empty() => {
+                        self.entries = entries;
+                        self.output.set("no matches!".to_owned());
+                    }
+                    Ok(entries) => {
+                        self.entries = entries;
+                        self.output.set(String::new());
+                    }
+                    Err(error) => {
+                        self.entries = Vec::new();
+                        self.output.set(error);
+                    }
+                }
+                self.filter.filter(self.entries.iter());
+                self.select = SelectMenu::default();
+            }
+            Response::Pattern(pattern) => {
+                self.pattern = pattern;
+                self.search(ctx);
+            }
+            Response::Revision(revision) => {
+                let revision = revision.trim();
+                self.revision = match revision.is_empty() {
+                    true if ctx.backend.is_bare() => Some("HEAD".to_owned()),
+                    true => None,
+                    false => Some(revision.to_owned()),
+                };
+                if !self.pattern.is_empty() {
+                    self.search(ctx);
+                }
+            }
+            Response::EditorError(error) => self.output.set(error),
+        }
+    }
+
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("grep", "[n]new search [r]revision [enter]open [e]open in editor", "[Left]back [arrows]move [ctrl+f]filter")
+    }
+
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        match self.filter.get_visible_index(self.select.cursor) {
+            Some(current_entry_index) => {
+                let entry = &self.entries[current_entry_index];
+                vec![("file", entry.path.clone()), ("line", entry.line.to_string())]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        let revision = self.revision.as_deref().unwrap_or("working tree");
+        drawer.fmt(format_args!("{}'{}' in {}", Color::DarkYellow, self.pattern, revision));
+        drawer.next_line();
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text().is_empty() {
+            drawer.select_menu(
+                &self.select,
+                1 + filter_line_count,
+                false,
+                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+            );
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
@@ -1,13 +1,48 @@
 use crate::{
-    backend::{Backend, BackendResult, LogEntry},
+    backend::{Backend, BackendResult, CiStatus, LogEntry, LogFilter, ResetMode},
+    config::Config,
     mode::*,
     platform::Key,
-    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+    ui::{glyph, Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+use std::{
+    sync::{Arc, Weak},
+    thread,
+    time::Duration,
 };
-use std::thread;
 
 pub enum Response {
     Refresh(BackendResult<(usize, Vec<LogEntry>)>),
+    CiStatus(String, CiStatus),
+    Reset(String, ResetMode),
+    ResetPreview(BackendResult<(String, bool)>), // the commits discarded, whether there are local changes to stash
+    StashAndReset(String),
+    Deepen(String),
+    PushPreview(BackendResult<String>),
+    RebaseOnto(String),       // the upstream, the commit to rebase onto was hovered when asking for it
+    Describe(String, String), // the revision and its description
+    AllRefs(String, BackendResult<String>),
+    Preview(String, String), // the revision and its full message
+    Notes(BackendResult<Vec<String>>),
+    Copied(BackendResult<()>),
+    EditNote(String, BackendResult<String>), // the revision and its current note
+    Note(String),
+    RemoveNote(String),
+    Push,
+    ForcePush,
+    Follow,
+    OperationFailed(WorkTreeOperation, String),
+    Autostash,
+    SquashPreview(BackendResult<(String, usize)>), // the combined message, how many of the commits are pushed
+    EditSquashMessage(String),
+    Squash(String),
+    JumpToDate(String),
+    Jump(BackendResult<(String, usize, Vec<LogEntry>)>), // the commit to hover, the entries loaded until it
+    Restore(ListSession, usize, BackendResult<Option<Vec<LogEntry>>>), // the entries loaded until the hovered one
+    ArchivePath(String),
+    Archive(String), // the prefix, the path was asked for first
+    Archived(BackendResult<()>),
+    Prefetch(String), // the commit hovered a moment ago
 }
 
 #[derive(Clone, Debug)]
@@ -18,7 +53,18 @@ enum WaitOperation {
     Fetch,
     Pull,
     Push,
+    PushPreview,
     Reset,
+    ResetPreview,
+    Fixup,
+    Autosquash,
+    Rebase,
+    Note,
+    SquashPreview,
+    Squash,
+    Jump,
+    Archive,
+    Restore,
 }
 
 #[derive(Clone, Debug)]
@@ -32,8 +78,72 @@ impl Default for State {
     }
 }
 
-impl SelectEntryDraw for LogEntry {
+const SHALLOW_MARKER: &str = "[shallow boundary] ";
+const NOTE_MARKER: &str = "[note] ";
+const DEFAULT_FOLLOW_INTERVAL: u64 = 5; // seconds
+const JUMP_PAGE_LEN: usize = 500; // commits loaded at once while looking for the one to jump to
+const PREFETCH_DELAY: Duration = Duration::from_millis(300); // hovered this long, a commit is about to be opened
+
+#[derive(Clone, Copy, Debug)]
+enum Column {
+    Graph,
+    Hash, // followed by the describe of the hovered commit and the ci status
+    Date,
+    Author,
+    Refs,
+    Message, // preceded by the shallow and note markers, takes the width left by the others
+}
+
+const DEFAULT_COLUMNS: &[(Column, Option<usize>)] = &[
+    (Column::Graph, None),
+    (Column::Hash, None),
+    (Column::Date, None),
+    (Column::Author, None),
+    (Column::Refs, None),
+    (Column::Message, None),
+];
+const MAX_AUTHOR_CHAR_COUNT: usize = 18; // when "log.columns" gives no width for the author
+
+// "log.columns" lists the columns in order, each with an optional maximum width like "author:10",
+// unknown names are skipped
+fn columns(config: &Config) -> Vec<(Column, Option<usize>)> {
+    let mut columns = Vec::new();
+    for column in config.get_list("log.columns") {
+        let (name, width) = match column.split_once(':') {
+            Some((name, width)) => (name.trim(), width.trim().parse().ok()),
+            None => (column, None),
+        };
+        let column = match name {
+            "graph" => Column::Graph,
+            "hash" => Column::Hash,
+            "date" => Column::Date,
+            "author" => Column::Author,
+            "refs" => Column::Refs,
+            "message" => Column::Message,
+            _ => continue,
+        };
+        columns.push((column, width));
+    }
+    match columns.is_empty() {
+        true => DEFAULT_COLUMNS.to_vec(),
+        false => columns,
+    }
+}
+
+// the refs excluded with "log.decorate_exclude" are shown once asked for
+fn shown_refs(entry: &LogEntry) -> &str {
+    entry.all_refs.as_deref().unwrap_or(&entry.refs)
+}
+
+// an entry as the list draws it
+struct LogLine<'a> {
+    entry: &'a LogEntry,
+    full_hash: bool,
+}
+
+impl SelectEntryDraw for LogLine<'_> {
     fn draw(&self, drawer: &mut Drawer, hovered: bool, full: bool) -> usize {
+        let entry = self.entry;
         fn color(color: Color, hovered: bool) -> Color {
             if hovered {
                 Color::White
@@ -42,28 +152,66 @@ impl SelectEntryDraw for LogEntry {
             }
         }
 
-        const MAX_AUTHOR_CHAR_COUNT: usize = 18;
-        let author = match self.author.char_indices().nth(MAX_AUTHOR_CHAR_COUNT) {
-            Some((i, _)) => &self.author[..i],
-            None => &self.author,
-        };
-
-        let mut total_chars = self.graph.chars().count()
-            + 1
-            + self.hash.chars().count()
-            + 1
-            + self.date.chars().count()
-            + 1
-            + author.chars().count()
-            + 1;
-
-        if !self.refs.is_empty() {
-            total_chars += self.refs.chars().count() + 3;
+        // every column but the message, which is inserted at its index once the width left for it is known
+        let mut segments = Vec::new();
+        let mut message_column = None;
+        for (column, width) in columns(&drawer.config) {
+            let cut = |text: &str| truncate(text, width.unwrap_or(usize::MAX)).to_owned();
+            match column {
+                Column::Graph => segments.push((Color::White, format!("{} ", cut(&entry.graph)))),
+                Column::Hash => {
+                    // the width abbreviates the full hash, git abbreviates it otherwise
+                    let hash = match (self.full_hash, width) {
+                        (true, _) => &entry.hash[..],
+                        (false, Some(width)) => truncate(&entry.hash, width),
+                        (false, None) => &entry.short_hash[..],
+                    };
+                    segments.push((Color::DarkYellow, format!("{} ", hash)));
+                    // only the hovered commit shows where it sits relative to the tags
+                    match &entry.describe {
+                        Some(describe) if hovered && !describe.is_empty() => {
+                            segments.push((Color::DarkYellow, format!("[{}] ", describe)))
+                        }
+                        _ => (),
+                    }
+                    match entry.ci_status {
+                        Some(CiStatus::Success) => segments.push((Color::DarkGreen, glyph("\u{2713} ", "+ ").to_owned())),
+                        Some(CiStatus::Failure) => segments.push((Color::DarkRed, glyph("\u{2717} ", "x ").to_owned())),
+                        Some(CiStatus::Pending) => segments.push((Color::DarkYellow, glyph("\u{25cf} ", "* ").to_owned())),
+                        None => (),
+                    }
+                }
+                Column::Date => segments.push((Color::DarkBlue, format!("{} ", cut(&entry.date)))),
+                Column::Author => {
+                    let author = truncate(&entry.author, width.unwrap_or(MAX_AUTHOR_CHAR_COUNT));
+                    segments.push((Color::DarkGreen, format!("{} ", author)));
+                }
+                Column::Refs if shown_refs(entry).is_empty() => (),
+                Column::Refs => segments.push((Color::DarkRed, format!("({}) ", cut(shown_refs(entry))))),
+                Column::Message => {
+                    if entry.shallow {
+                        segments.push((Color::DarkMagenta, SHALLOW_MARKER.to_owned()));
+                    }
+                    if entry.note {
+                        segments.push((Color::DarkGray, NOTE_MARKER.to_owned()));
+                    }
+                    message_column = Some((segments.len(), width));
+                }
+            }
         }
+        // like the other columns, the message is followed by a space when it is not the last one
+        let message_separator = match message_column {
+            Some((index, _)) if index < segments.len() => " ",
+            _ => "",
+        };
+        let total_chars: usize =
+            segments.iter().map(|(_, text)| text.chars().count()).sum::<usize>() + message_separator.len();
 
-        let (line_count, message) = if full {
+        let (line_count, message) = if message_column.is_none() {
+            (0, "")
+        } else if full {
             let mut line_count = 0;
-            for line in self.message.lines() {
+            for line in entry.message.lines() {
                 let mut x = 0;
                 for _ in line.chars() {
                     if x >= drawer.viewport_size.0 as _ {
@@ -74,50 +222,43 @@ impl SelectEntryDraw for LogEntry {
 
                 line_count += 1;
             }
-            (line_count, &self.message[..])
+            (line_count, &entry.message[..])
         } else {
             let available_width = (drawer.viewport_size.0 as usize).saturating_sub(total_chars);
-            let message = self.message.lines().next().unwrap_or("");
-            let message = match message.char_indices().nth(available_width) {
-                Some((i, _)) => &message[..i],
-                None => &message,
+            let available_width = match message_column {
+                Some((_, Some(width))) => width.min(available_width),
+                _ => available_width,
             };
-            (0, message)
+            (0, truncate(entry.message.lines().next().unwrap_or(""), available_width))
         };
 
-        let (refs_begin, refs_end) = match &self.refs[..] {
-            "" => ("", ""),
-            _ => ("(", ") "),
-        };
-
-        drawer.fmt(format_args!(
-            "{}{} {}{} {}{} {}{} {}{}{}{}{}",
-            color(Color::White, hovered),
-            &self.graph,
-            color(Color::DarkYellow, hovered),
-            &self.hash,
-            color(Color::DarkBlue, hovered),
-            &self.date,
-            color(Color::DarkGreen, hovered),
-            author,
-            color(Color::DarkRed, hovered),
-            refs_begin,
-            &self.refs,
-            refs_end,
-            color(Color::White, hovered),
-        ));
-
-        if full {
-            drawer.next_line();
+        // the full message goes below the other columns
+        for (i, (segment_color, text)) in segments.iter().enumerate() {
+            if !full && matches!(message_column, Some((index, _)) if index == i) {
+                drawer.fmt(format_args!("{}", color(Color::White, hovered)));
+                drawer.message_line(message);
+                drawer.str(message_separator);
+            }
+            drawer.fmt(format_args!("{}{}", color(*segment_color, hovered), text));
         }
+        drawer.fmt(format_args!("{}", color(Color::White, hovered)));
 
-        let mut lines = message.lines();
-        if let Some(line) = lines.next() {
-            drawer.str(line);
-        }
-        for line in lines {
-            drawer.next_line();
-            drawer.str(line);
+        match message_column {
+            Some((index, _)) if full || index == segments.len() => {
+                if full {
+                    drawer.next_line();
+                }
+
+                let mut lines = message.lines();
+                if let Some(line) = lines.next() {
+                    drawer.message_line(line);
+                }
+                for line in lines {
+                    drawer.next_line();
+                    drawer.message_line(line);
+                }
+            }
+            _ => (),
         }
 
         1 + line_count
@@ -132,12 +273,379 @@ pub struct Mode {
     select: SelectMenu,
     filter: Filter,
     show_full_hovered_message: bool,
+    log_filter: LogFilter, // only the history of a file or an author
+    rebase_onto: Option<String>,
+    note_revision: Option<String>, // the commit whose note is being edited
+    show_notes: bool,
+    restore: Option<ListSession>,         // applied once the first entries are loaded
+    follow: Option<Arc<()>>,              // the thread asking for refreshes stops once it is dropped
+    range_end: Option<String>,            // the commit marked as one end of a range
+    autostash: Option<WorkTreeOperation>, // run again around a stash once confirmed
+    show_preview: bool,
+    full_hash: bool,           // only how hashes are shown, operations always use the full ones
+    jump_date: Option<String>, // the jump waits for the refresh going on, as it continues from its entries
+    archive: Option<ArchiveExport>,
+    prefetch: Option<String>, // the commit whose details are fetched if it is still hovered after a moment
 }
+impl Mode {
+    fn current_branch(&self) -> &str {
+        self.entries.iter().find_map(|e| e.refs.split(", ").find_map(|r| r.strip_prefix("HEAD -> "))).unwrap_or("HEAD")
+    }
+
+    // described lazily, one hovered commit at a time, as describe walks the history
+    fn describe_hovered(&mut self, ctx: &ModeContext) {
+        if !ctx.config.get_bool("log.describe", true) {
+            return;
+        }
+        let entry = match self.filter.get_visible_index(self.select.cursor) {
+            Some(index) if self.entries[index].describe.is_none() => &mut self.entries[index],
+            _ => return,
+        };
+        entry.describe = Some(String::new()); // so it is not requested again while waiting
+        let revision = entry.hash.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            if let Ok(describe) = ctx.backend.describe(&revision) {
+                ctx.event_sender.send_response(ModeResponse::Log(Response::Describe(revision, describe)));
+            }
+        });
+    }
+
+    fn toggle_all_refs(&mut self, ctx: &ModeContext, current_entry_index: Option<usize>) {
+        let entry = match current_entry_index {
+            Some(index) if !self.entries[index].hash.is_empty() => &mut self.entries[index],
+            _ => return,
+        };
+        if entry.all_refs.take().is_some() {
+            return;
+        }
+        entry.all_refs = Some(entry.refs.clone()); // until all of them arrive
+        let revision = entry.hash.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let refs = ctx.backend.refs(&revision);
+            ctx.event_sender.send_response(ModeResponse::Log(Response::AllRefs(revision, refs)));
+        });
+    }
+
+    // the bottom third of the body, below the list
+    fn preview_height(&self, viewport_height: u16) -> usize {
+        match self.show_preview {
+            true => (viewport_height as usize).saturating_sub(RESERVED_LINES_COUNT) / 3,
+            false => 0,
+        }
+    }
+
+    // fetched one hovered commit at a time, like describe
+    fn preview_hovered(&mut self, ctx: &ModeContext) {
+        if !self.show_preview {
+            return;
+        }
+        let entry = match self.filter.get_visible_index(self.select.cursor) {
+            Some(index) if self.entries[index].full_message.is_none() => &mut self.entries[index],
+            _ => return,
+        };
+        entry.full_message = Some(String::new()); // so it is not requested again while waiting
+        let revision = entry.hash.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let message = match ctx.backend.revision_details(&revision, &date_format(&ctx.config)) {
+                Ok(info) => {
+                    let message = match info.notes.is_empty() {
+                        true => info.message.clone(),
+                        false => format!("{}\n\nNotes:\n{}", info.message, info.notes),
+                    };
+                    ctx.prefetched.insert(revision.clone(), info);
+                    message
+                }
+                Err(error) => error,
+            };
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Preview(revision, message)));
+        });
+    }
+
+    // the details are fetched ahead so that opening the commit does not wait for them
+    fn prefetch_hovered(&mut self, ctx: &ModeContext) {
+        if !ctx.config.get_bool("log.prefetch", true) {
+            return;
+        }
+        let revision = match self.filter.get_visible_index(self.select.cursor) {
+            Some(index) if !self.entries[index].hash.is_empty() => self.entries[index].hash.clone(),
+            _ => {
+                self.prefetch = None;
+                return;
+            }
+        };
+        if self.prefetch.as_ref() == Some(&revision) || ctx.prefetched.contains(&revision) {
+            return;
+        }
+        self.prefetch = Some(revision.clone());
+        // only the sender, a thread holding the context is one the headless application waits for
+        let event_sender = ctx.event_sender.clone();
+        thread::spawn(move || {
+            thread::sleep(PREFETCH_DELAY);
+            event_sender.send_response(ModeResponse::Log(Response::Prefetch(revision)));
+        });
+    }
+
+    fn draw_preview(&self, drawer: &mut Drawer, top_line: usize, height: usize) {
+        let entry = match self.filter.get_visible_index(self.select.cursor) {
+            Some(index) => &self.entries[index],
+            None => return,
+        };
+        let width = drawer.viewport_size.0 as usize;
+        drawer.move_to_line(top_line);
+        drawer.fmt(format_args!("{}{}", Color::DarkGray, glyph("\u{2500}", "-").repeat(width)));
+        drawer.next_line();
+
+        let describe = match &entry.describe {
+            Some(describe) if !describe.is_empty() => format!(" [{}]", describe),
+            _ => String::new(),
+        };
+        let refs = match shown_refs(entry) {
+            "" => String::new(),
+            refs => format!(" ({})", refs),
+        };
+        let used_width = entry.hash.chars().count() + entry.date.chars().count() + entry.author.chars().count() + 2;
+        let extra = format!("{}{}", describe, refs);
+        drawer.fmt(format_args!(
+            "{}{} {}{} {}{}{}{}{}",
+            Color::DarkYellow,
+            entry.hash,
+            Color::DarkBlue,
+            entry.date,
+            Color::DarkGreen,
+            entry.author,
+            Color::DarkRed,
+            truncate(&extra, width.saturating_sub(used_width)),
+            Color::White,
+        ));
+        drawer.next_line();
+
+        // the subject from the list until the full message arrives
+        let message = match &entry.full_message {
+            Some(message) if !message.is_empty() => message,
+            _ => &entry.message,
+        };
+        for line in message.lines().take(height.saturating_sub(2)) {
+            drawer.message_line(truncate(&line.replace('\t', "    "), width));
+            drawer.next_line();
+        }
+    }
+
+    // the first press marks one end, the second opens the commits from one end to the other, both included
+    fn mark_range_end(&mut self, ctx: &ModeContext, current_entry_index: Option<usize>) {
+        let index = match current_entry_index {
+            Some(index) => index,
+            None => return,
+        };
+        let other_index = match self.range_end.take() {
+            Some(hash) => self.entries.iter().position(|e| e.hash == hash),
+            None => None,
+        };
+        let other_index = match other_index {
+            Some(other_index) if other_index != index => other_index,
+            Some(_) => return, // marked again, the mark is removed
+            None => {
+                self.range_end = Some(self.entries[index].hash.clone());
+                return;
+            }
+        };
+
+        // newest first, the range starts from the parent of the oldest commit
+        let (newest, oldest) = (index.min(other_index), index.max(other_index));
+        let range = format!("{}~..{}", self.entries[oldest].hash, self.entries[newest].hash);
+        let log_filter = LogFilter { range: Some(range), ..self.log_filter.clone() };
+        ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::log(ModeKind::Log, log_filter));
+    }
+
+    fn edit_squash_message(&self, ctx: &ModeContext, message: String) {
+        let not_empty = true;
+        let placeholder = "type in the message of the squashed commit...";
+        let on_submit = |ctx: &ModeContext, message: String| {
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Squash(message)));
+        };
+        ctx.event_sender.send_mode_change(
+            ModeKind::MessageInput,
+            ModeChangeInfo::message_input(ModeKind::Log, not_empty, placeholder, on_submit).with_initial_message(message),
+        );
+    }
+
+    fn toggle_follow(&mut self, ctx: &ModeContext) {
+        if self.follow.take().is_some() {
+            return;
+        }
+        let seconds = ctx.config.get("log.follow_interval").and_then(|s| s.parse().ok()).filter(|&s| s > 0);
+        let interval = Duration::from_secs(seconds.unwrap_or(DEFAULT_FOLLOW_INTERVAL));
+        let follow = Arc::new(());
+        let token = Arc::downgrade(&follow);
+        self.follow = Some(follow);
+        self.refresh_following(ctx);
+
+        let event_sender = ctx.event_sender.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if Weak::upgrade(&token).is_none() {
+                break;
+            }
+            event_sender.send_response(ModeResponse::Log(Response::Follow));
+        });
+    }
+
+    // the newest commits, fetched first when asked to so that the pushes of others show up
+    fn refresh_following(&mut self, ctx: &ModeContext) {
+        self.state = State::Waiting(WaitOperation::Refresh);
+        let fetch = ctx.config.get_bool("log.follow_fetch", false) && !ctx.config.get_bool("read_only", false);
+        request(ctx, self.log_filter.clone(), move |b| if fetch { b.fetch() } else { Ok(()) });
+    }
+
+    // an operation refused because of local changes is offered again around a stash
+    fn run_work_tree_operation(&mut self, ctx: &ModeContext, operation: WorkTreeOperation, autostash: bool) {
+        self.state = match operation {
+            WorkTreeOperation::Checkout(_) => State::Waiting(WaitOperation::Checkout),
+            WorkTreeOperation::Merge(_) => State::Waiting(WaitOperation::Merge),
+            WorkTreeOperation::Pull => State::Waiting(WaitOperation::Pull),
+        };
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            use std::ops::Deref;
+
+            let result = match operation.run(ctx.backend.deref(), autostash) {
+                Err(error) if !autostash => {
+                    ctx.event_sender.send_response(ModeResponse::Log(Response::OperationFailed(operation, error)));
+                    return;
+                }
+                result => result,
+            };
+            let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+            let result = result.and_then(|_| log_page(&ctx, 0, available_height, &log_filter));
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Refresh(result)));
+        });
+    }
+
+    fn remove_note(&mut self, ctx: &ModeContext, revision: String) {
+        self.state = State::Waiting(WaitOperation::Note);
+        ctx.prefetched.remove(&revision);
+        request(ctx, self.log_filter.clone(), move |b| b.remove_note(&revision));
+    }
+
+    fn rebase(&mut self, ctx: &ModeContext, onto: String, upstream: Option<String>) {
+        self.state = State::Waiting(WaitOperation::Rebase);
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            use std::ops::Deref;
+
+            let result = ctx.backend.rebase(&onto, upstream.as_deref());
+            let conflicts = result.is_err() && has_conflicts(ctx.backend.deref());
+
+            let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+            let result = result.and_then(|_| log_page(&ctx, 0, available_height, &log_filter));
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Refresh(result)));
+            if conflicts {
+                ctx.event_sender.send_mode_change(ModeKind::Status, ModeChangeInfo::new(ModeKind::Log));
+            }
+        });
+    }
+
+    // from the top, after the log filter changed
+    fn reload(&mut self, ctx: &ModeContext) {
+        self.select = SelectMenu::default();
+        self.state = State::Waiting(WaitOperation::Refresh);
+        request(ctx, self.log_filter.clone(), |_| Ok(()));
+    }
+
+    // the commit is found by git, the log is then loaded until it
+    fn jump_to_date(&mut self, ctx: &ModeContext, date: String) {
+        self.state = State::Waiting(WaitOperation::Jump);
+        let start = self.entries.len();
+        // the lines only drawing the graph are entries without a commit
+        let skip = self.entries.iter().filter(|e| !e.hash.is_empty()).count();
+        let hashes: Vec<_> = self.entries.iter().map(|e| e.hash.clone()).collect();
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let find = || {
+                let hash = match ctx.backend.first_commit_until(&date, &log_filter)? {
+                    Some(hash) => hash,
+                    None => return Err(format!("no commit at or before '{}'", date)),
+                };
+                if hashes.contains(&hash) {
+                    return Ok((hash, start, Vec::new()));
+                }
+                match load_until(&ctx, skip, &hash, &log_filter)? {
+                    Some(entries) => Ok((hash, start, entries)),
+                    None => Err(format!("the commit at or before '{}' is not in the log", date)),
+                }
+            };
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Jump(find())));
+        });
+    }
+
+    // the hovered commit of the last session can be further than the first page, the log is loaded until it
+    fn restore_session(&mut self, ctx: &ModeContext, session: ListSession) {
+        if session.hovered.is_empty() || self.entries.iter().any(|e| e.hash == session.hovered) {
+            session.restore(&mut self.select, &mut self.filter, &self.entries, |e| &e.hash);
+            return;
+        }
+
+        self.state = State::Waiting(WaitOperation::Restore);
+        let start = self.entries.len();
+        let skip = self.entries.iter().filter(|e| !e.hash.is_empty()).count();
+        let log_filter = self.log_filter.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = load_until(&ctx, skip, &session.hovered, &log_filter);
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Restore(session, start, result)));
+        });
+    }
+
+    // asks how much of the commits left behind is kept, unless the default mode is used without asking
+    fn reset(&mut self, ctx: &ModeContext, revision: String) {
+        let mode = ctx.config.get("reset.mode").and_then(ResetMode::parse).unwrap_or(ResetMode::Mixed);
+        if !ctx.config.get_bool("confirm.reset", true) {
+            self.state = State::Waiting(WaitOperation::Reset);
+            request(ctx, self.log_filter.clone(), move |b| b.reset(&revision, mode));
+            return;
+        }
+
+        let message = format!(
+            "reset {} to {}\n\n\
+            soft: the changes of the commits left behind stay staged\n\
+            mixed: they stay in the working tree, unstaged\n\
+            hard: they are thrown away, along with the uncommitted changes",
+            self.current_branch(),
+            revision
+        );
+        fn send(ctx: &ModeContext, revision: String, mode: ResetMode) {
+            ctx.event_sender.send_response(ModeResponse::Log(Response::Reset(revision, mode)));
+        }
+        let choices = vec![
+            confirm::Choice::new('s', "soft", |ctx, revision| send(ctx, revision, ResetMode::Soft)),
+            confirm::Choice::new('m', "mixed", |ctx, revision| send(ctx, revision, ResetMode::Mixed)),
+            confirm::Choice::new('h', "hard", |ctx, revision| send(ctx, revision, ResetMode::Hard)),
+        ];
+        let default_choice = mode.as_str().chars().next();
+        ctx.event_sender.send_mode_change(
+            ModeKind::Confirm,
+            ModeChangeInfo::confirm(ModeKind::Log, message, revision, |_, _| ()).with_choices(choices, default_choice),
+        );
+    }
+}
+
 impl ModeTrait for Mode {
-    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
         if let State::Waiting(_) = self.state {
             return;
         }
+        match info.info {
+            Some(ModeInfo::Log(log_filter)) => self.log_filter = log_filter,
+            Some(ModeInfo::Session(session)) => self.restore = Some(session),
+            _ => (),
+        }
+        self.show_preview = ctx.config.get_bool("log.preview", false);
+        self.full_hash = ctx.config.get_bool("log.full_hash", false);
         self.state = State::Waiting(WaitOperation::Refresh);
 
         self.output.set(String::new());
@@ -145,7 +653,7 @@ impl ModeTrait for Mode {
         self.select.saturate_cursor(self.filter.visible_indices().len());
         self.show_full_hovered_message = false;
 
-        request(ctx, |_| Ok(()));
+        request(ctx, self.log_filter.clone(), |_| Ok(()));
     }
 
     fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
@@ -157,16 +665,25 @@ impl ModeTrait for Mode {
             return ModeStatus { pending_input: true };
         }
 
-        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+        // the marked end of a range is shown above the list
+        let range_line_count = self.range_end.is_some() as usize;
+        let preview_height = self.preview_height(ctx.viewport_size.1);
+        let available_height =
+            (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + range_line_count + preview_height);
         self.select.on_key(self.filter.visible_indices().len(), available_height, key);
 
+        self.describe_hovered(ctx);
+        self.preview_hovered(ctx);
+        self.prefetch_hovered(ctx);
+
         let current_entry_index = self.filter.get_visible_index(self.select.cursor);
         if matches!(self.state, State::Idle) && current_entry_index.map(|i| i + 1 == self.entries.len()).unwrap_or(false) {
             self.state = State::Waiting(WaitOperation::Refresh);
             let start = self.entries.len();
+            let log_filter = self.log_filter.clone();
             let ctx = ctx.clone();
             thread::spawn(move || {
-                let result = ctx.backend.log(start, available_height);
+                let result = log_page(&ctx, start, available_height, &log_filter);
                 ctx.event_sender.send_response(ModeResponse::Log(Response::Refresh(result)));
             });
         }
@@ -181,51 +698,245 @@ impl ModeTrait for Mode {
             self.show_full_hovered_message = !self.show_full_hovered_message;
         } else if let Key::Ctrl('f') = key {
             self.filter.enter();
+        } else if let Key::Char('y') | Key::Char('Y') = key {
+            if let Some(current_entry_index) = current_entry_index {
+                let entry = &self.entries[current_entry_index];
+                let text = if key == Key::Char('y') { &entry.hash } else { &entry.message };
+                copy_to_clipboard(ctx, text.clone(), |result| ModeResponse::Log(Response::Copied(result)));
+            }
+        } else if let Key::Char('o') = key {
+            self.show_preview = !self.show_preview;
+            self.preview_hovered(ctx);
+        } else if let Key::Char('E') = key {
+            self.toggle_all_refs(ctx, current_entry_index);
+        } else if let Key::Char('#') = key {
+            self.full_hash = !self.full_hash;
+        } else if let Key::Char('W') = key {
+            self.toggle_follow(ctx);
+        } else if let Key::Char('v') = key {
+            self.mark_range_end(ctx, current_entry_index);
+        } else if let (Key::Char('d'), Some(range)) = (key, &self.log_filter.range) {
+            let info = ModeChangeInfo::diff(ModeKind::Log, Some(range.clone()));
+            ctx.event_sender.send_mode_change(ModeKind::Diff, info);
+        } else if let Key::Char('i') = key {
+            if let Some(current_entry_index) = current_entry_index {
+                if let Err(error) = open_issue_references(&ctx.config, &self.entries[current_entry_index].message) {
+                    self.output.set(error);
+                }
+            }
         } else if let State::Idle = self.state {
             match key {
                 Key::Char('c') => {
                     if let Some(current_entry_index) = current_entry_index {
-                        let entry = &self.entries[current_entry_index];
-                        self.state = State::Waiting(WaitOperation::Checkout);
-                        let revision = entry.hash.clone();
-                        request(ctx, move |b| b.checkout(&revision));
+                        let operation = WorkTreeOperation::Checkout(self.entries[current_entry_index].hash.clone());
+                        self.run_work_tree_operation(ctx, operation, false);
                     }
                 }
                 Key::Char('r') => {
                     if let Some(current_entry_index) = current_entry_index {
-                        let entry = &self.entries[current_entry_index];
-                        self.state = State::Waiting(WaitOperation::Reset);
-                        let revision = entry.hash.clone();
-                        request(ctx, move |b| b.reset(&revision));
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        self.reset(ctx, revision);
                     }
                 }
                 Key::Char('R') => {
-                    self.state = State::Waiting(WaitOperation::Reset);
-                    request(ctx, move |b| b.reset(""));
+                    if ctx.config.get_bool("confirm.reset", true) {
+                        self.state = State::Waiting(WaitOperation::ResetPreview);
+                        let ctx = ctx.clone();
+                        thread::spawn(move || {
+                            let result = ctx.backend.reset_preview();
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::ResetPreview(result)));
+                        });
+                    } else {
+                        self.state = State::Waiting(WaitOperation::Reset);
+                        request(ctx, self.log_filter.clone(), |b| b.reset("", ResetMode::Hard));
+                    }
                 }
                 Key::Char('m') => {
                     if let Some(current_entry_index) = current_entry_index {
-                        let entry = &self.entries[current_entry_index];
-                        self.state = State::Waiting(WaitOperation::Merge);
-                        let revision = entry.hash.clone();
-                        request(ctx, move |b| b.merge(&revision));
+                        let operation = WorkTreeOperation::Merge(self.entries[current_entry_index].hash.clone());
+                        self.run_work_tree_operation(ctx, operation, false);
                     }
                 }
                 Key::Char('f') => {
                     self.state = State::Waiting(WaitOperation::Fetch);
-                    request(ctx, Backend::fetch);
-                }
-                Key::Char('p') => {
-                    self.state = State::Waiting(WaitOperation::Pull);
-                    request(ctx, Backend::pull);
+                    request(ctx, self.log_filter.clone(), Backend::fetch);
                 }
+                Key::Char('p') => self.run_work_tree_operation(ctx, WorkTreeOperation::Pull, false),
                 Key::Char('P') => {
-                    self.state = State::Waiting(WaitOperation::Push);
-                    request(ctx, Backend::push);
+                    if ctx.config.get_bool("confirm.push", true) {
+                        self.state = State::Waiting(WaitOperation::PushPreview);
+                        let ctx = ctx.clone();
+                        thread::spawn(move || {
+                            let result = ctx.backend.push_preview();
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::PushPreview(result)));
+                        });
+                    } else {
+                        self.state = State::Waiting(WaitOperation::Push);
+                        request(ctx, self.log_filter.clone(), Backend::push);
+                    }
+                }
+                Key::Char('A') => {
+                    let log_filter = self.log_filter.clone();
+                    ctx.event_sender.send_mode_change(ModeKind::Contributors, ModeChangeInfo::log(ModeKind::Log, log_filter));
+                }
+                Key::Char('V') => ctx.event_sender.send_mode_change(ModeKind::Signatures, ModeChangeInfo::new(ModeKind::Log)),
+                Key::Char('T') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        ctx.event_sender.send_mode_change(ModeKind::Tree, ModeChangeInfo::tree(ModeKind::Log, revision));
+                    }
+                }
+                Key::Char('H') => {
+                    let not_empty = true;
+                    let placeholder = "type in how many commits to deepen the history by...";
+                    let on_submit = |ctx: &ModeContext, count: String| {
+                        ctx.event_sender.send_response(ModeResponse::Log(Response::Deepen(count)));
+                    };
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::MessageInput,
+                        ModeChangeInfo::message_input(ModeKind::Log, not_empty, placeholder, on_submit),
+                    );
+                }
+                Key::Char('U') => {
+                    self.state = State::Waiting(WaitOperation::Fetch);
+                    request(ctx, self.log_filter.clone(), Backend::unshallow);
+                }
+                Key::Char('F') => {
+                    if ctx.config.get_bool("confirm.force_push", true) {
+                        let message = format!("force push {} (--force-with-lease)", self.current_branch());
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::Confirm,
+                            ModeChangeInfo::confirm(ModeKind::Log, message, String::new(), |ctx, _| {
+                                ctx.event_sender.send_response(ModeResponse::Log(Response::ForcePush));
+                            }),
+                        );
+                    } else {
+                        self.state = State::Waiting(WaitOperation::Push);
+                        request(ctx, self.log_filter.clone(), Backend::push_force);
+                    }
+                }
+                Key::Char('x') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        self.state = State::Waiting(WaitOperation::Fixup);
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        request(ctx, self.log_filter.clone(), move |b| b.commit_fixup(&revision));
+                    }
+                }
+                Key::Char('X') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        self.state = State::Waiting(WaitOperation::Autosquash);
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        request(ctx, self.log_filter.clone(), move |b| b.autosquash(&revision));
+                    }
+                }
+                Key::Char('B') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let onto = self.entries[current_entry_index].hash.clone();
+                        self.rebase(ctx, onto, None);
+                    }
+                }
+                Key::Char('O') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let onto = self.entries[current_entry_index].hash.clone();
+                        let not_empty = true;
+                        let placeholder = "type in the revision after which the transplanted commits start...";
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::MessageInput,
+                            ModeChangeInfo::message_input(ModeKind::Log, not_empty, placeholder, |ctx, upstream| {
+                                ctx.event_sender.send_response(ModeResponse::Log(Response::RebaseOnto(upstream)));
+                            }),
+                        );
+                        self.rebase_onto = Some(onto);
+                    }
+                }
+                Key::Char('n') => {
+                    self.show_notes = !self.show_notes;
+                    match self.show_notes {
+                        true => request_notes(ctx),
+                        false => self.entries.iter_mut().for_each(|e| e.note = false),
+                    }
+                }
+                Key::Char('N') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        let ctx = ctx.clone();
+                        thread::spawn(move || {
+                            let note = ctx.backend.note(&revision);
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::EditNote(revision, note)));
+                        });
+                    }
+                }
+                Key::Char('D') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let revision = self.entries[current_entry_index].hash.clone();
+                        if ctx.config.get_bool("confirm.remove_note", true) {
+                            let message = format!("remove the note of {}", revision);
+                            ctx.event_sender.send_mode_change(
+                                ModeKind::Confirm,
+                                ModeChangeInfo::confirm(ModeKind::Log, message, revision, |ctx, revision| {
+                                    ctx.event_sender.send_response(ModeResponse::Log(Response::RemoveNote(revision)));
+                                }),
+                            );
+                        } else {
+                            self.remove_note(ctx, revision);
+                        }
+                    }
+                }
+                Key::Char('C') => {
+                    if let Some(range) = self.log_filter.range.clone() {
+                        ctx.event_sender
+                            .send_mode_change(ModeKind::PatchSeries, ModeChangeInfo::patch_series(ModeKind::Log, range));
+                    }
+                }
+                Key::Char('Q') => {
+                    if let Some(range) = self.log_filter.range.clone() {
+                        self.state = State::Waiting(WaitOperation::SquashPreview);
+                        let ctx = ctx.clone();
+                        thread::spawn(move || {
+                            let result = ctx.backend.squash_preview(&range);
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::SquashPreview(result)));
+                        });
+                    }
+                }
+                Key::Char('1') => {
+                    self.log_filter.first_parent = !self.log_filter.first_parent;
+                    self.reload(ctx);
+                }
+                // all the commits, only the merges, then none of them
+                Key::Char('e') => {
+                    self.log_filter.merges = match self.log_filter.merges {
+                        None => Some(true),
+                        Some(true) => Some(false),
+                        Some(false) => None,
+                    };
+                    self.reload(ctx);
+                }
+                Key::Char('J') => {
+                    let not_empty = true;
+                    let placeholder = "type in a date to jump to, like 2024-03-01 or last march...";
+                    let on_submit = |ctx: &ModeContext, date: String| {
+                        ctx.event_sender.send_response(ModeResponse::Log(Response::JumpToDate(date)));
+                    };
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::MessageInput,
+                        ModeChangeInfo::message_input(ModeKind::Log, not_empty, placeholder, on_submit),
+                    );
+                }
+                Key::Char('Z') => {
+                    if let Some(current_entry_index) = current_entry_index {
+                        let entry = &self.entries[current_entry_index];
+                        let archive = ArchiveExport::new(entry.hash.clone(), &entry.short_hash);
+                        let on_submit = |ctx: &ModeContext, path: String| {
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::ArchivePath(path)));
+                        };
+                        ctx.event_sender.send_mode_change(ModeKind::MessageInput, archive.ask_path(ModeKind::Log, on_submit));
+                        self.archive = Some(archive);
+                    }
                 }
                 Key::Char('g') => {
                     self.state = State::Waiting(WaitOperation::Push);
-                    request(ctx, Backend::push_gerrit); // push to gerrit
+                    request(ctx, self.log_filter.clone(), Backend::push_gerrit);
+                    // push to gerrit
                 }
                 _ => (),
             }
@@ -234,7 +945,7 @@ impl ModeTrait for Mode {
         ModeStatus { pending_input: false }
     }
 
-    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
         let response = as_variant!(response, ModeResponse::Log).unwrap();
         match response {
             Response::Refresh(result) => {
@@ -248,6 +959,10 @@ impl ModeTrait for Mode {
                         Ok((start_index, entries)) => {
                             self.entries.truncate(start_index);
                             self.entries.extend(entries);
+                            // pinned to the newest commit, but the next pages can still be scrolled to
+                            if self.follow.is_some() && start_index == 0 && !self.filter.has_focus() {
+                                self.select = SelectMenu::default();
+                            }
                         }
                         Err(error) => {
                             self.entries.clear();
@@ -258,10 +973,296 @@ impl ModeTrait for Mode {
 
                 self.filter.filter(self.entries.iter());
                 self.select.saturate_cursor(self.filter.visible_indices().len());
+                if let Some(session) = self.restore.take() {
+                    self.restore_session(ctx, session);
+                }
+
+                request_ci_status(ctx, &self.entries);
+                self.describe_hovered(ctx);
+                self.preview_hovered(ctx);
+                self.prefetch_hovered(ctx);
+                if self.show_notes {
+                    request_notes(ctx);
+                }
+                if let Some(date) = self.jump_date.take() {
+                    self.jump_to_date(ctx, date);
+                }
+            }
+            Response::Preview(hash, message) => {
+                for entry in self.entries.iter_mut().filter(|e| e.hash == hash) {
+                    entry.full_message = Some(message.clone());
+                }
+            }
+            Response::Describe(hash, describe) => {
+                for entry in self.entries.iter_mut().filter(|e| e.hash == hash) {
+                    entry.describe = Some(describe.clone());
+                }
+            }
+            // dropped when hidden again meanwhile
+            Response::AllRefs(hash, Ok(refs)) => {
+                for entry in self.entries.iter_mut().filter(|e| e.hash == hash && e.all_refs.is_some()) {
+                    entry.all_refs = Some(refs.clone());
+                }
+            }
+            Response::AllRefs(_, Err(error)) => self.output.set(error),
+            Response::Notes(Ok(revisions)) => {
+                for entry in &mut self.entries {
+                    entry.note = revisions.iter().any(|r| r.starts_with(&entry.hash));
+                }
+            }
+            Response::Notes(Err(error)) => self.output.set(error),
+            Response::Copied(Ok(())) => (),
+            Response::Copied(Err(error)) => self.output.set(error),
+            Response::EditNote(revision, Ok(note)) => {
+                self.note_revision = Some(revision);
+                let not_empty = false;
+                let placeholder = "type in the note, empty to remove it...";
+                let on_submit = |ctx: &ModeContext, note: String| {
+                    ctx.event_sender.send_response(ModeResponse::Log(Response::Note(note)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::Log, not_empty, placeholder, on_submit)
+                        .with_initial_message(note),
+                );
+            }
+            Response::EditNote(_, Err(error)) => self.output.set(error),
+            Response::Note(note) => {
+                if let Some(revision) = self.note_revision.take() {
+                    ctx.prefetched.remove(&revision);
+                    self.state = State::Waiting(WaitOperation::Note);
+                    request(ctx, self.log_filter.clone(), move |b| match note.trim().is_empty() {
+                        true => b.remove_note(&revision),
+                        false => b.set_note(&revision, &note),
+                    });
+                }
+            }
+            Response::CiStatus(hash, status) => {
+                for entry in self.entries.iter_mut().filter(|e| e.hash == hash) {
+                    entry.ci_status = Some(status);
+                }
+            }
+            Response::Reset(revision, mode) => {
+                self.state = State::Waiting(WaitOperation::Reset);
+                request(ctx, self.log_filter.clone(), move |b| b.reset(&revision, mode));
+            }
+            Response::ResetPreview(result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok((commits, local_changes)) => {
+                        let mut message = format!("hard reset {} to its remote branch\n", self.current_branch());
+                        message.push_str("\ncommits to discard:\n");
+                        message.push_str(if commits.trim().is_empty() { "none\n" } else { &commits });
+                        if local_changes {
+                            message.push_str("\nthe uncommitted changes are stashed first\n");
+                        }
+                        let on_confirm: fn(&ModeContext, String) = match local_changes {
+                            true => |ctx, revision| {
+                                ctx.event_sender.send_response(ModeResponse::Log(Response::StashAndReset(revision)))
+                            },
+                            false => |ctx, revision| {
+                                ctx.event_sender.send_response(ModeResponse::Log(Response::Reset(revision, ResetMode::Hard)))
+                            },
+                        };
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::Confirm,
+                            ModeChangeInfo::confirm(ModeKind::Log, message, String::new(), on_confirm),
+                        );
+                    }
+                    Err(error) => self.output.set(error),
+                }
+            }
+            Response::StashAndReset(revision) => {
+                self.state = State::Waiting(WaitOperation::Reset);
+                let stash_message = format!("before the reset of {} to its remote branch", self.current_branch());
+                request(ctx, self.log_filter.clone(), move |b| {
+                    b.stash_all(&stash_message).and_then(|_| b.reset(&revision, ResetMode::Hard))
+                });
+            }
+            Response::Deepen(count) => match count.trim().parse() {
+                Ok(count) => {
+                    self.state = State::Waiting(WaitOperation::Fetch);
+                    request(ctx, self.log_filter.clone(), move |b| b.deepen(count));
+                }
+                Err(_) => self.output.set(format!("invalid commit count '{}'", count)),
+            },
+            Response::PushPreview(result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok(preview) => ctx.event_sender.send_mode_change(
+                        ModeKind::Confirm,
+                        ModeChangeInfo::confirm(ModeKind::Log, preview, String::new(), |ctx, _| {
+                            ctx.event_sender.send_response(ModeResponse::Log(Response::Push));
+                        }),
+                    ),
+                    Err(error) => self.output.set(error),
+                }
+            }
+            Response::RebaseOnto(upstream) => {
+                if let Some(onto) = self.rebase_onto.take() {
+                    self.rebase(ctx, onto, Some(upstream.trim().to_owned()));
+                }
+            }
+            Response::Push => {
+                self.state = State::Waiting(WaitOperation::Push);
+                request(ctx, self.log_filter.clone(), Backend::push);
+            }
+            Response::RemoveNote(revision) => self.remove_note(ctx, revision),
+            Response::ForcePush => {
+                self.state = State::Waiting(WaitOperation::Push);
+                request(ctx, self.log_filter.clone(), Backend::push_force);
+            }
+            // rewriting pushed commits is refused unless confirmed, the branch then has to be force pushed
+            Response::SquashPreview(result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok((message, 0)) => self.edit_squash_message(ctx, message),
+                    Ok((message, pushed_count)) => {
+                        let warning = format!(
+                            "{} of these commits are already pushed, squashing them needs a force push afterwards",
+                            pushed_count
+                        );
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::Confirm,
+                            ModeChangeInfo::confirm(ModeKind::Log, warning, message, |ctx, message| {
+                                ctx.event_sender.send_response(ModeResponse::Log(Response::EditSquashMessage(message)));
+                            }),
+                        );
+                    }
+                    Err(error) => self.output.set(error),
+                }
+            }
+            Response::EditSquashMessage(message) => self.edit_squash_message(ctx, message),
+            // the range is gone once squashed, the log continues from the squashed commit
+            Response::Squash(message) => {
+                if let Some(range) = self.log_filter.range.take() {
+                    self.state = State::Waiting(WaitOperation::Squash);
+                    request(ctx, self.log_filter.clone(), move |b| b.squash(&range, &message));
+                }
+            }
+            Response::OperationFailed(operation, error) => {
+                self.state = State::Idle;
+                let on_confirm = |ctx: &ModeContext, _| {
+                    ctx.event_sender.send_response(ModeResponse::Log(Response::Autostash));
+                };
+                match operation.confirm_autostash(ModeKind::Log, &error, on_confirm) {
+                    Some(info) => {
+                        self.autostash = Some(operation);
+                        ctx.event_sender.send_mode_change(ModeKind::Confirm, info);
+                    }
+                    None => self.output.set(error),
+                }
+            }
+            Response::Autostash => {
+                if let Some(operation) = self.autostash.take() {
+                    self.run_work_tree_operation(ctx, operation, true);
+                }
+            }
+            Response::JumpToDate(date) => match self.state {
+                State::Idle => self.jump_to_date(ctx, date.trim().to_owned()),
+                State::Waiting(_) => self.jump_date = Some(date.trim().to_owned()),
+            },
+            Response::Jump(result) => {
+                self.state = State::Idle;
+                let (hash, start_index, entries) = match result {
+                    Ok(jump) => jump,
+                    Err(error) => {
+                        self.output.set(error);
+                        return;
+                    }
+                };
+                self.output.set(String::new());
+                let loaded_count = entries.len();
+                self.entries.truncate(start_index);
+                self.entries.extend(entries);
+                self.filter.filter(self.entries.iter());
+
+                let index = self.entries.iter().position(|e| e.hash == hash);
+                match index.and_then(|index| self.filter.visible_indices().iter().position(|&i| i == index)) {
+                    Some(cursor) => {
+                        self.select.cursor = cursor;
+                        self.select.scroll = cursor;
+                    }
+                    None => self.output.set("the commit to jump to is hidden by the filter".to_owned()),
+                }
+
+                if loaded_count > 0 {
+                    request_ci_status(ctx, &self.entries);
+                }
+                self.describe_hovered(ctx);
+                self.preview_hovered(ctx);
+                self.prefetch_hovered(ctx);
+            }
+            // a commit that is gone leaves the log at its top, with the filter of the session
+            Response::Restore(mut session, start_index, result) => {
+                self.state = State::Idle;
+                match result {
+                    Ok(Some(entries)) => {
+                        self.entries.truncate(start_index);
+                        self.entries.extend(entries);
+                    }
+                    Ok(None) => session.hovered.clear(),
+                    Err(error) => {
+                        session.hovered.clear();
+                        self.output.set(error);
+                    }
+                }
+                session.restore(&mut self.select, &mut self.filter, &self.entries, |e| &e.hash);
+
+                request_ci_status(ctx, &self.entries);
+                self.describe_hovered(ctx);
+                self.preview_hovered(ctx);
+                self.prefetch_hovered(ctx);
+            }
+            Response::ArchivePath(path) => {
+                if let Some(archive) = &mut self.archive {
+                    let on_submit = |ctx: &ModeContext, prefix: String| {
+                        ctx.event_sender.send_response(ModeResponse::Log(Response::Archive(prefix)));
+                    };
+                    let info = archive.ask_prefix(path.trim().to_owned(), ModeKind::Log, on_submit);
+                    ctx.event_sender.send_mode_change(ModeKind::MessageInput, info);
+                }
+            }
+            Response::Archive(prefix) => {
+                if let Some(archive) = self.archive.take() {
+                    self.state = State::Waiting(WaitOperation::Archive);
+                    let prefix = prefix.trim().to_owned();
+                    let ctx = ctx.clone();
+                    thread::spawn(move || {
+                        let result = ctx.backend.archive(&archive.revision, &archive.path, &prefix);
+                        ctx.event_sender.send_response(ModeResponse::Log(Response::Archived(result)));
+                    });
+                }
+            }
+            Response::Archived(result) => {
+                self.state = State::Idle;
+                if let Err(error) = result {
+                    self.output.set(error);
+                }
+            }
+            // dropped once another commit is hovered
+            Response::Prefetch(revision) => {
+                let hovered = self.filter.get_visible_index(self.select.cursor).map(|i| &self.entries[i].hash);
+                if self.prefetch.as_ref() == Some(&revision) && hovered == Some(&revision) {
+                    self.prefetch = None;
+                    if !ctx.prefetched.contains(&revision) {
+                        revision_details::prefetch(ctx, revision);
+                    }
+                }
+            }
+            // skipped while busy or filtering, the next one catches up
+            Response::Follow => {
+                if self.follow.is_some() && matches!(self.state, State::Idle) && !self.filter.has_focus() {
+                    self.refresh_following(ctx);
+                }
             }
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
@@ -271,6 +1272,15 @@ impl ModeTrait for Mode {
 
     fn header(&self) -> (&str, &str, &str) {
         let name = match self.state {
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.follow.is_some() => "log (following)",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.range.is_some() => "commit range",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.path.is_some() => "file history",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.author.is_some() => "author history",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.merges == Some(true) => "merges",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.first_parent => "first-parent log",
+            State::Idle | State::Waiting(WaitOperation::Refresh) if self.log_filter.merges == Some(false) => {
+                "log without merges"
+            }
             State::Idle | State::Waiting(WaitOperation::Refresh) => "log",
             State::Waiting(WaitOperation::Reset) => "reset",
             State::Waiting(WaitOperation::Checkout) => "checkout",
@@ -278,29 +1288,86 @@ impl ModeTrait for Mode {
             State::Waiting(WaitOperation::Fetch) => "fetch",
             State::Waiting(WaitOperation::Pull) => "pull",
             State::Waiting(WaitOperation::Push) => "push",
+            State::Waiting(WaitOperation::PushPreview) => "push preview",
+            State::Waiting(WaitOperation::ResetPreview) => "reset preview",
+            State::Waiting(WaitOperation::Fixup) => "fixup",
+            State::Waiting(WaitOperation::Autosquash) => "autosquash",
+            State::Waiting(WaitOperation::Rebase) => "rebase",
+            State::Waiting(WaitOperation::Note) => "note",
+            State::Waiting(WaitOperation::SquashPreview) | State::Waiting(WaitOperation::Squash) => "squash",
+            State::Waiting(WaitOperation::Jump) => "jump to date",
+            State::Waiting(WaitOperation::Archive) => "archive",
+            State::Waiting(WaitOperation::Restore) => "restore session",
         };
 
-        let left_help = "[c]checkout [enter]details [f]fetch [p]pull [P]push [g]gerrit [r]reset [R]reset to remote";
-        let right_help = "[tab]full message [Left]back [arrows]move [ctrl+f]filter";
+        let left_help =
+            "[c]checkout [enter]details [f]fetch [p]pull [P]push [F]force push [g]gerrit [r]reset [R]reset to remote [H]deepen [U]unshallow [x]fixup [X]autosquash [B]rebase onto [O]rebase range onto [J]jump to date";
+        let right_help = match self.log_filter.range {
+            Some(_) => "[d]combined diff [Q]squash [C]patch series [v]range [1]first parent [e]merges [o]preview [#]full hashes [E]all refs [W]follow [n]notes [N]edit note [D]remove note [y]copy hash [Y]copy message [T]tree [A]authors [V]verify signatures [Z]archive [i]open issue [tab]full message [Left]back [arrows]move [ctrl+f]filter",
+            None => "[v]range [1]first parent [e]merges [o]preview [#]full hashes [E]all refs [W]follow [n]notes [N]edit note [D]remove note [y]copy hash [Y]copy message [T]tree [A]authors [V]verify signatures [Z]archive [i]open issue [tab]full message [Left]back [arrows]move [ctrl+f]filter",
+        };
         (name, left_help, right_help)
     }
 
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        let mut placeholders = vec![("branch", self.current_branch().to_owned())];
+        if let Some(current_entry_index) = self.filter.get_visible_index(self.select.cursor) {
+            placeholders.push(("hash", self.entries[current_entry_index].hash.clone()));
+        }
+        placeholders
+    }
+
+    // file and author histories and ranges are left out, they are opened from elsewhere
+    fn session(&self) -> Option<ListSession> {
+        match self.log_filter.path.is_none() && self.log_filter.author.is_none() && self.log_filter.range.is_none() {
+            true => Some(ListSession::save(&self.select, &self.filter, &self.entries, |e| &e.hash)),
+            false => None,
+        }
+    }
+
     fn draw(&self, drawer: &mut Drawer) {
+        let mut range_line_count = 0;
+        if let Some(range_end) = &self.range_end {
+            let range_end = match self.entries.iter().find(|e| &e.hash == range_end) {
+                Some(entry) if !self.full_hash => &entry.short_hash,
+                _ => range_end,
+            };
+            drawer.fmt(format_args!("{}range from {}, [v] on its other end", Color::DarkYellow, range_end));
+            drawer.next_line();
+            range_line_count = 1;
+        }
         let filter_line_count = drawer.filter(&self.filter);
         if self.output.text().is_empty() {
+            // the list is drawn as if the terminal ended where the preview starts
+            let viewport_height = drawer.viewport_size.1;
+            let preview_height = self.preview_height(viewport_height);
+            drawer.viewport_size.1 -= preview_height as u16;
+            let lines: Vec<_> = self
+                .filter
+                .visible_indices()
+                .iter()
+                .map(|&i| LogLine { entry: &self.entries[i], full_hash: self.full_hash })
+                .collect();
             drawer.select_menu(
                 &self.select,
-                filter_line_count,
+                range_line_count + filter_line_count,
                 self.show_full_hovered_message,
-                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+                lines.iter(),
             );
+            drawer.viewport_size.1 = viewport_height;
+
+            if preview_height > 0 {
+                drawer.clear_to_bottom();
+                let top_line = (viewport_height as usize).saturating_sub(preview_height + 1);
+                self.draw_preview(drawer, top_line, preview_height);
+            }
         } else {
             drawer.output(&self.output);
         }
     }
 }
 
-fn request<F>(ctx: &ModeContext, f: F)
+fn request<F>(ctx: &ModeContext, log_filter: LogFilter, f: F)
 where
     F: 'static + Send + Sync + FnOnce(&dyn Backend) -> BackendResult<()>,
 {
@@ -309,8 +1376,77 @@ where
         use std::ops::Deref;
 
         let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
-        let result = f(ctx.backend.deref()).and_then(|_| ctx.backend.log(0, available_height));
+        let result = f(ctx.backend.deref()).and_then(|_| log_page(&ctx, 0, available_height, &log_filter));
         //println!("result: {:?}", result);
         ctx.event_sender.send_response(ModeResponse::Log(Response::Refresh(result)));
     });
 }
+
+fn log_page(ctx: &ModeContext, start: usize, len: usize, log_filter: &LogFilter) -> BackendResult<(usize, Vec<LogEntry>)> {
+    let decorate_exclude = ctx.config.get_list("log.decorate_exclude");
+    ctx.backend.log(start, len, log_filter, &date_format(&ctx.config), &decorate_exclude)
+}
+
+// the pages following the entries loaded, until the one with the commit, none when the log ends without it
+fn load_until(
+    ctx: &ModeContext,
+    mut skip: usize,
+    hash: &str,
+    log_filter: &LogFilter,
+) -> BackendResult<Option<Vec<LogEntry>>> {
+    let mut entries = Vec::new();
+    loop {
+        let (_, page) = log_page(ctx, skip, JUMP_PAGE_LEN, log_filter)?;
+        let found = page.iter().any(|e| e.hash == hash);
+        let end = page.is_empty();
+        skip += page.iter().filter(|e| !e.hash.is_empty()).count();
+        entries.extend(page);
+        if found {
+            return Ok(Some(entries));
+        }
+        if end {
+            return Ok(None);
+        }
+    }
+}
+
+fn request_notes(ctx: &ModeContext) {
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        let revisions = ctx.backend.noted_revisions();
+        ctx.event_sender.send_response(ModeResponse::Log(Response::Notes(revisions)));
+    });
+}
+
+// statuses are fetched one commit at a time so markers show up as soon as each one is known
+// the url and the token come from the global config, a cloned repository could send the token anywhere
+fn request_ci_status(ctx: &ModeContext, entries: &[LogEntry]) {
+    let url_template = match ctx.config.get_global("ci.status_url") {
+        Some(url_template) => url_template.to_owned(),
+        None => return,
+    };
+    let token = ctx.config.get_global("ci.token").map(str::to_owned);
+
+    let hashes: Vec<_> =
+        entries.iter().filter(|e| !e.ci_status.map(|s| s.is_final()).unwrap_or(false)).map(|e| e.hash.clone()).collect();
+    if hashes.is_empty() {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        for hash in hashes {
+            if let Ok(status) = ctx.backend.ci_status(&url_template, token.as_deref(), &hash) {
+                ctx.event_sender.send_response(ModeResponse::Log(Response::CiStatus(hash, status)));
+            }
+        }
+    });
+}
+
+// cut to the given number of characters, so that a line does not wrap
+fn truncate(text: &str, char_count: usize) -> &str {
+    match text.char_indices().nth(char_count) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
@@ -0,0 +1,149 @@
+use std::{thread, time::Duration};
+
+use crate::{
+    backend::{BackendResult, MaintenanceTask, RepositorySize},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Size(BackendResult<RepositorySize>),
+    Line(String, bool),
+    Done(BackendResult<()>),
+}
+
+// the size summary and an empty line above the output
+const SUMMARY_LINES_COUNT: usize = 2;
+
+fn format_size(kib: usize) -> String {
+    match kib {
+        0..=1023 => format!("{} KiB", kib),
+        1024..=1048575 => format!("{:.1} MiB", kib as f64 / 1024.0),
+        _ => format!("{:.1} GiB", kib as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    running: Option<MaintenanceTask>,
+    size: Option<RepositorySize>,
+    lines: Vec<String>,
+    progress: bool, // the last line is a progress line, the next one replaces it
+    output: Output,
+}
+impl Mode {
+    fn set_output(&mut self, ctx: &ModeContext) {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + SUMMARY_LINES_COUNT);
+        self.output.set(self.lines.join("\n"));
+        self.output.on_key(available_height, Key::End);
+    }
+}
+
+fn request_size(ctx: &ModeContext) {
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        let size = ctx.backend.repository_size();
+        ctx.event_sender.send_response(ModeResponse::Maintenance(Response::Size(size)));
+    });
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        if self.running.is_none() {
+            request_size(ctx);
+        }
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + SUMMARY_LINES_COUNT);
+        self.output.on_key(available_height, key);
+
+        let task = match key {
+            Key::Char('g') => Some(MaintenanceTask::Gc),
+            Key::Char('p') => Some(MaintenanceTask::Prune),
+            Key::Char('f') => Some(MaintenanceTask::Fsck),
+            Key::Char('c') => Some(MaintenanceTask::CountObjects),
+            Key::Char('r') => {
+                let seconds = ctx.config.get("remote.check_timeout").and_then(|s| s.parse().ok()).unwrap_or(10);
+                Some(MaintenanceTask::CheckRemotes(Duration::from_secs(seconds)))
+            }
+            _ => None,
+        };
+        if let Some(task) = task.filter(|_| self.running.is_none()) {
+            self.running = Some(task);
+            self.lines = vec![format!("git {}", task.as_str())];
+            self.progress = false;
+            self.set_output(ctx);
+
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                let result = ctx.backend.maintenance(task, &mut |line, progress| {
+                    let response = Response::Line(line.to_owned(), progress);
+                    ctx.event_sender.send_response(ModeResponse::Maintenance(response));
+                });
+                ctx.event_sender.send_response(ModeResponse::Maintenance(Response::Done(result)));
+                request_size(&ctx);
+            });
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Maintenance).unwrap();
+        match response {
+            Response::Size(Ok(size)) => self.size = Some(size),
+            Response::Size(Err(error)) => {
+                self.lines.push(error);
+                self.set_output(ctx);
+            }
+            Response::Line(line, progress) => {
+                if self.progress {
+                    self.lines.pop();
+                }
+                self.lines.push(line);
+                self.progress = progress;
+                self.set_output(ctx);
+            }
+            Response::Done(result) => {
+                self.running = None;
+                self.progress = false;
+                match result {
+                    Ok(()) => self.lines.push("done!".to_owned()),
+                    Err(error) => self.lines.push(error),
+                }
+                self.set_output(ctx);
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.running.is_some()
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("maintenance", "[g]gc [p]prune [f]fsck [c]count objects [r]check remotes", "[Left]back [arrows]scroll")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        match &self.size {
+            Some(size) => drawer.fmt(format_args!(
+                "{}size: {}{} ({} loose objects: {}, {} packed objects in {} packs: {}, garbage: {})",
+                Color::DarkYellow,
+                format_size(size.loose_size + size.pack_size + size.garbage_size),
+                Color::White,
+                size.loose_count,
+                format_size(size.loose_size),
+                size.packed_count,
+                size.pack_count,
+                format_size(size.pack_size),
+                format_size(size.garbage_size),
+            )),
+            None => drawer.str("size: ..."),
+        }
+        drawer.next_line();
+        drawer.next_line();
+        drawer.output(&self.output);
+    }
+}
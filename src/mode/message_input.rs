@@ -1,7 +1,9 @@
 use crate::{mode::*, platform::Key, ui::Drawer};
 use std::fmt;
 
-pub enum Response {}
+pub enum Response {
+    AddTrailers(Vec<String>),
+}
 
 #[derive(Clone)]
 pub struct OnSubmit(fn(ctx: &ModeContext, message: String));
@@ -16,15 +18,43 @@ impl fmt::Debug for OnSubmit {
         write!(f, "on_submit: fn")
     }
 }
+#[derive(Clone)]
+pub struct OnCancel(pub fn(ctx: &ModeContext));
+impl Default for OnCancel {
+    fn default() -> Self {
+        Self(|_ctx: &ModeContext| {})
+    }
+}
+
+impl fmt::Debug for OnCancel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "on_cancel: fn")
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ModeInfo {
     pub not_empty: bool, // the submit string must be not empty
     pub placeholder: String,
+    pub initial_message: String,
+    pub co_authors: bool,
+    pub prompt: String, // shown above the input, it stays while typing
+    pub secret: bool,   // the input is drawn masked, like a password
     pub on_submit: OnSubmit,
+    pub on_cancel: OnCancel,
 }
 impl ModeInfo {
     pub fn new(not_empty: bool, placeholder: String, on_submit: fn(ctx: &ModeContext, message: String)) -> Self {
-        Self { not_empty, placeholder, on_submit: OnSubmit(on_submit) }
+        Self {
+            not_empty,
+            placeholder,
+            initial_message: String::new(),
+            co_authors: false,
+            prompt: String::new(),
+            secret: false,
+            on_submit: OnSubmit(on_submit),
+            on_cancel: OnCancel::default(),
+        }
     }
 }
 
@@ -34,7 +64,39 @@ pub struct Mode {
     from: ModeKind,
     placeholder: String,
     on_submit: OnSubmit,
+    on_cancel: OnCancel,
     not_empty: bool,
+    co_authors: bool,
+    prompt: String,
+    secret: bool,
+}
+
+impl Mode {
+    // trailers go in the last paragraph, which is started if the message does not end with one yet
+    fn add_trailers(&mut self, trailers: Vec<String>) {
+        let mut message = self.readline.input().trim_end().to_owned();
+        for trailer in trailers {
+            if message.lines().any(|l| l == trailer) {
+                continue;
+            }
+            if ends_with_trailers(&message) {
+                message.push('\n');
+            } else if !message.is_empty() {
+                message.push_str("\n\n");
+            }
+            message.push_str(&trailer);
+        }
+        self.readline.set(message);
+    }
+}
+
+fn ends_with_trailers(message: &str) -> bool {
+    match message.rsplit_once("\n\n") {
+        Some((_, paragraph)) => paragraph
+            .lines()
+            .all(|l| matches!(l.split_once(": "), Some((token, _)) if !token.is_empty() && !token.contains(' '))),
+        None => false,
+    }
 }
 
 impl ModeTrait for Mode {
@@ -43,15 +105,28 @@ impl ModeTrait for Mode {
         self.from = info.from;
         let mode_info = as_variant!(info.info.unwrap(), super::ModeInfo::MessageInput).unwrap();
         self.placeholder = mode_info.placeholder;
+        self.readline.set(mode_info.initial_message);
         self.on_submit = mode_info.on_submit;
+        self.on_cancel = mode_info.on_cancel;
+        self.prompt = mode_info.prompt;
+        self.secret = mode_info.secret;
         self.not_empty = mode_info.not_empty;
+        self.co_authors = mode_info.co_authors;
     }
 
     fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
-        self.readline.on_key(key);
+        match key {
+            Key::Ctrl('j') => self.readline.new_line(), // ctrl+enter sends a line feed on most terminals
+            Key::Ctrl('o') if self.co_authors => {
+                ctx.event_sender.send_mode_change(ModeKind::CoAuthors, ModeChangeInfo::new(ModeKind::MessageInput));
+                return ModeStatus { pending_input: true };
+            }
+            _ => self.readline.on_key(key),
+        }
 
         if key.is_cancel() {
             ctx.event_sender.send_mode_revert();
+            self.on_cancel.0(ctx);
         } else if key.is_submit() {
             let message = self.readline.input().to_string();
             // when submit should not be empty, just do nothing if no message input
@@ -64,17 +139,37 @@ impl ModeTrait for Mode {
         ModeStatus { pending_input: true }
     }
 
-    fn on_response(&mut self, _ctx: &ModeContext, _response: ModeResponse) {}
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::MessageInput).unwrap();
+        match response {
+            Response::AddTrailers(trailers) => self.add_trailers(trailers),
+        }
+    }
 
     fn is_waiting_response(&self) -> bool {
         false
     }
 
     fn header(&self) -> (&str, &str, &str) {
-        ("message input", "[enter]submit [Esc]cancel", "[Left]back")
+        let left_help = if self.co_authors {
+            "[enter]submit [ctrl+j]new line [ctrl+o]co-authors [Esc]cancel"
+        } else {
+            "[enter]submit [ctrl+j]new line [Esc]cancel"
+        };
+        ("message input", left_help, "[Left]back")
     }
 
     fn draw(&self, drawer: &mut Drawer) {
-        drawer.readline(&self.readline, &self.placeholder);
+        if !self.prompt.is_empty() {
+            drawer.str(&self.prompt);
+            drawer.next_line();
+        }
+        if self.secret {
+            let mut masked = ReadLine::default();
+            masked.set("*".repeat(self.readline.input().chars().count()));
+            drawer.readline(&masked, &self.placeholder);
+        } else {
+            drawer.readline(&self.readline, &self.placeholder);
+        }
     }
 }
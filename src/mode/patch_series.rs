@@ -0,0 +1,254 @@
+use std::{fs, path::PathBuf, thread};
+
+use crate::{
+    backend::BackendResult,
+    mode::*,
+    platform::Key,
+    tool::shell_quote,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<String>>),
+    CoverLetter(String), // the subject on the first line, the blurb below
+}
+
+// what git puts in the cover letter for the author to replace
+const SUBJECT_PLACEHOLDER: &str = "*** SUBJECT HERE ***";
+const BLURB_PLACEHOLDER: &str = "*** BLURB HERE ***";
+const PATCH_DIRECTORY_NAME: &str = "gituse-patches";
+const SEND_COMMAND_MISSING: &str =
+    "set 'patch.send_command' in the global config to send the patches, like 'git send-email --to=<list>'";
+
+#[derive(Clone, Debug)]
+struct PatchEntry {
+    path: String,
+    subject: Option<String>, // only the cover letter has one to write
+}
+impl SelectEntryDraw for PatchEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        let name = self.path.rsplit(['/', '\\']).next().unwrap_or(&self.path);
+        drawer.str(name);
+        match self.subject.as_deref() {
+            Some("") => drawer.fmt(format_args!(" {}no subject yet, [e] to write it", Color::DarkRed)),
+            Some(subject) => drawer.fmt(format_args!(" {}{}", Color::DarkGreen, subject)),
+            None => (),
+        }
+        1
+    }
+}
+
+// the patches of a range formatted as a numbered series with a cover letter, ready to be sent by mail
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    range: String,
+    directory: PathBuf,
+    entries: Vec<PatchEntry>,
+    cover_template: String, // the cover letter as git wrote it, with the placeholders
+    cover_blurb: String,
+    viewing: bool, // the output is the hovered patch, going back shows the list again
+    output: Output,
+    select: SelectMenu,
+}
+impl Mode {
+    fn refresh(&mut self, ctx: &ModeContext) {
+        self.waiting = true;
+        let range = self.range.clone();
+        let directory = self.directory.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.format_patches(&range, &directory);
+            ctx.event_sender.send_response(ModeResponse::PatchSeries(Response::Refresh(result)));
+        });
+    }
+
+    fn cover_letter(&self) -> Option<&PatchEntry> {
+        self.entries.first().filter(|e| e.subject.is_some())
+    }
+
+    fn description(&self) -> String {
+        let cover_letter = self.cover_letter().is_some();
+        let patch_count = self.entries.len() - cover_letter as usize;
+        let cover_letter = if cover_letter { " and a cover letter" } else { "" };
+        format!("{}: {} patches{}", self.range, patch_count, cover_letter)
+    }
+
+    // the placeholders left are the parts not written yet
+    fn write_cover_letter(&self, subject: &str) -> Result<(), String> {
+        let path = match self.cover_letter() {
+            Some(entry) => &entry.path,
+            None => return Err("no cover letter".to_owned()),
+        };
+        let subject = if subject.is_empty() { SUBJECT_PLACEHOLDER } else { subject };
+        let blurb = if self.cover_blurb.is_empty() { BLURB_PLACEHOLDER } else { &self.cover_blurb };
+        let text = self.cover_template.replacen(SUBJECT_PLACEHOLDER, subject, 1).replacen(BLURB_PLACEHOLDER, blurb, 1);
+        fs::write(path, text).map_err(|error| format!("could not write '{}': {}", path, error))
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let Some(ModeInfo::PatchSeries(range)) = info.info {
+            self.range = range;
+        }
+        self.directory = match ctx.config.get_global("patch.directory") {
+            Some(directory) => PathBuf::from(directory),
+            None => ctx.backend.git_dir().join(PATCH_DIRECTORY_NAME),
+        };
+        self.viewing = false;
+        self.output.set(String::new());
+        self.refresh(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        // the range and the directory sit above the list
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        if self.output.text().is_empty() {
+            self.select.on_key(self.entries.len(), available_height, key);
+        } else {
+            self.output.on_key(available_height, key);
+        }
+
+        if self.waiting {
+            return ModeStatus { pending_input: false };
+        }
+        if self.viewing {
+            if key.is_back() {
+                self.viewing = false;
+                self.output.set(String::new());
+                return ModeStatus { pending_input: true };
+            }
+            return ModeStatus { pending_input: false };
+        }
+        match key {
+            // the mail headers before the diff are not something the diff mode can parse
+            Key::Enter => {
+                if let Some(entry) = self.entries.get(self.select.cursor) {
+                    match fs::read_to_string(&entry.path) {
+                        Ok(text) => {
+                            self.viewing = true;
+                            self.output.set(text);
+                        }
+                        Err(error) => self.output.set(format!("could not read '{}': {}", entry.path, error)),
+                    }
+                }
+            }
+            Key::Char('e') => {
+                if let Some(entry) = self.cover_letter() {
+                    let message = format!("{}\n\n{}", entry.subject.as_deref().unwrap_or(""), self.cover_blurb);
+                    let not_empty = true;
+                    let placeholder = "type in the subject of the series, then what it is about below an empty line...";
+                    let on_submit = |ctx: &ModeContext, message: String| {
+                        ctx.event_sender.send_response(ModeResponse::PatchSeries(Response::CoverLetter(message)));
+                    };
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::MessageInput,
+                        ModeChangeInfo::message_input(ModeKind::PatchSeries, not_empty, placeholder, on_submit)
+                            .with_initial_message(message.trim_end()),
+                    );
+                }
+            }
+            // like "git send-email --to=list@example.org --confirm=never", the patches are appended
+            Key::Char('s') if !self.entries.is_empty() => {
+                match ctx.config.get_global("patch.send_command") {
+                    Some(command) => {
+                        let mut command = command.to_owned();
+                        for entry in &self.entries {
+                            command.push(' ');
+                            command.push_str(&shell_quote(&entry.path));
+                        }
+                        let message = format!("send {}?\n\n{}", self.description(), command);
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::Confirm,
+                            ModeChangeInfo::confirm(ModeKind::PatchSeries, message, command, |ctx, command| {
+                                ctx.event_sender.send_mode_change(
+                                    ModeKind::Command,
+                                    ModeChangeInfo::command(ModeKind::PatchSeries, Ok(command)),
+                                );
+                            }),
+                        );
+                    }
+                    None => self.output.set(SEND_COMMAND_MISSING.to_owned()),
+                }
+                // not the global [s] to the status
+                return ModeStatus { pending_input: true };
+            }
+            Key::Char('r') => {
+                self.output.set(String::new());
+                self.refresh(ctx);
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::PatchSeries).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                self.viewing = false;
+                self.select = SelectMenu::default();
+                self.cover_blurb.clear();
+                let paths = match result {
+                    Ok(paths) => paths,
+                    Err(error) => {
+                        self.entries.clear();
+                        self.output.set(error);
+                        return;
+                    }
+                };
+                // git writes the cover letter first
+                self.cover_template = match paths.first().filter(|p| p.ends_with("0000-cover-letter.patch")) {
+                    Some(path) => fs::read_to_string(path).unwrap_or_default(),
+                    None => String::new(),
+                };
+                self.entries = paths
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, path)| {
+                        let subject = (i == 0 && !self.cover_template.is_empty()).then(String::new);
+                        PatchEntry { path, subject }
+                    })
+                    .collect();
+            }
+            Response::CoverLetter(message) => {
+                let (subject, blurb) = message.split_once('\n').unwrap_or((&message, ""));
+                let subject = subject.trim().to_owned();
+                self.cover_blurb = blurb.trim().to_owned();
+                match self.write_cover_letter(&subject) {
+                    Ok(()) => {
+                        if let Some(entry) = self.entries.first_mut() {
+                            entry.subject = Some(subject);
+                        }
+                    }
+                    Err(error) => self.output.set(error),
+                }
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        let name = if self.waiting { "format patches" } else { "patch series" };
+        if self.viewing {
+            return (name, "", "[Left]back to the patches [arrows]scroll");
+        }
+        (name, "[e]edit cover letter [s]send [r]format again [enter]view", "[Left]back [arrows]move")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        drawer.fmt(format_args!("{}{} in {}", Color::DarkYellow, self.description(), self.directory.display()));
+        drawer.next_line();
+        if self.output.text().is_empty() {
+            drawer.select_menu(&self.select, 1, false, self.entries.iter());
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
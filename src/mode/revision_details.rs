@@ -1,7 +1,8 @@
-use std::thread;
+use bounded_vec_deque::BoundedVecDeque;
+use std::{sync::Mutex, thread};
 
 use crate::{
-    backend::{RevisionEntry, RevisionInfo},
+    backend::{BackendResult, RevisionEntry, RevisionInfo},
     mode::*,
     platform::Key,
     ui::{Drawer, RESERVED_LINES_COUNT},
@@ -9,6 +10,7 @@ use crate::{
 
 pub enum Response {
     Info(RevisionInfo),
+    Copied(BackendResult<()>),
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +24,51 @@ impl Default for State {
     }
 }
 
+const PREFETCHED_LEN: usize = 16;
+
+// the details of the commits last hovered in log for a moment, so opening one of them does not wait,
+// the most recently used last
+pub struct Prefetched(Mutex<BoundedVecDeque<(String, RevisionInfo)>>);
+impl Default for Prefetched {
+    fn default() -> Self {
+        Self(Mutex::new(BoundedVecDeque::new(PREFETCHED_LEN)))
+    }
+}
+impl Prefetched {
+    pub fn contains(&self, revision: &str) -> bool {
+        self.0.lock().unwrap().iter().any(|(r, _)| r == revision)
+    }
+
+    pub fn insert(&self, revision: String, info: RevisionInfo) {
+        let mut revisions = self.0.lock().unwrap();
+        revisions.retain(|(r, _)| *r != revision);
+        revisions.push_back((revision, info));
+    }
+
+    pub fn remove(&self, revision: &str) {
+        self.0.lock().unwrap().retain(|(r, _)| r != revision);
+    }
+
+    fn get(&self, revision: &str) -> Option<RevisionInfo> {
+        let mut revisions = self.0.lock().unwrap();
+        let index = revisions.iter().position(|(r, _)| r == revision)?;
+        let entry = revisions.remove(index)?;
+        let info = entry.1.clone();
+        revisions.push_back(entry);
+        Some(info)
+    }
+}
+
+// fetched in the background, an error is not kept so that opening the commit shows it
+pub fn prefetch(ctx: &ModeContext, revision: String) {
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        if let Ok(info) = ctx.backend.revision_details(&revision, &date_format(&ctx.config)) {
+            ctx.prefetched.insert(revision, info);
+        }
+    });
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Mode {
     state: State,
@@ -30,13 +77,41 @@ pub struct Mode {
     select: SelectMenu,
     filter: Filter,
     show_full_message: bool,
+    search: Search,
     revision: String,
+    message: String, // without the notes shown below it
     from: ModeKind,
 }
 impl Mode {
     fn get_selected_entries(&self) -> Vec<RevisionEntry> {
         self.entries.iter().filter(|&e| e.selected).cloned().collect()
     }
+
+    // the lines of the message are searched, then the names of the files shown
+    fn find(&mut self) {
+        let message_lines = self.output.text().lines();
+        let entries = &self.entries;
+        let names = self.filter.visible_indices().iter().map(|&i| entries[i].name.as_str());
+        self.search.find(message_lines.chain(names), 0);
+    }
+
+    // a match in the message expands it, a match in a file name hovers the file
+    fn show_search_match(&mut self, ctx: &ModeContext, line: Option<usize>) {
+        let line = match line {
+            Some(line) => line,
+            None => return,
+        };
+        match line.checked_sub(self.output.line_count()) {
+            None => self.show_full_message |= line > 0,
+            Some(cursor) => {
+                let line_count = if self.show_full_message { self.output.line_count() } else { 1 };
+                let available_height =
+                    (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + line_count + 2).max(1);
+                self.select.cursor = cursor;
+                self.select.scroll = self.select.scroll.min(cursor).max((cursor + 1).saturating_sub(available_height));
+            }
+        }
+    }
 }
 
 impl ModeTrait for Mode {
@@ -50,18 +125,21 @@ impl ModeTrait for Mode {
         self.filter.clear();
         self.select.cursor = 0;
         self.show_full_message = false;
+        self.search = Search::default();
         self.from = info.from;
         self.revision = as_variant!(info.info.unwrap(), ModeInfo::RevisionDetails).unwrap();
 
+        if let Some(info) = ctx.prefetched.get(&self.revision) {
+            self.on_response(ctx, ModeResponse::RevisionDetails(Response::Info(info)));
+            return;
+        }
         let ctx = ctx.clone();
         let revision = self.revision.clone();
         thread::spawn(move || {
-            let mut info = match ctx.backend.revision_details(&revision) {
+            let info = match ctx.backend.revision_details(&revision, &date_format(&ctx.config)) {
                 Ok(info) => info,
-                Err(error) => RevisionInfo { message: error, entries: Vec::new() },
+                Err(error) => RevisionInfo { date: String::new(), message: error, notes: String::new(), entries: Vec::new() },
             };
-            info.entries.sort_unstable_by(|a, b| a.status.cmp(&b.status));
-
             ctx.event_sender.send_response(ModeResponse::RevisionDetails(Response::Info(info)));
         });
     }
@@ -74,35 +152,66 @@ impl ModeTrait for Mode {
 
             return ModeStatus { pending_input: true };
         }
+        if self.search.has_focus() {
+            self.search.on_key(key);
+            self.find();
+            self.show_search_match(ctx, self.search.current_line());
+
+            return ModeStatus { pending_input: true };
+        }
 
         if let State::Idle = self.state {
             let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
             let line_count = if self.show_full_message { self.output.line_count() } else { 1 };
+            let line_count = line_count + self.search.is_searching() as usize;
 
-            match self.select.on_key(
-                self.filter.visible_indices().len(),
-                available_height.saturating_sub(line_count + 1),
-                key,
-            ) {
-                SelectMenuAction::None => (),
-                SelectMenuAction::Toggle(i) => {
-                    if let Some(i) = self.filter.get_visible_index(i) {
-                        self.entries[i].selected = !self.entries[i].selected
-                    }
-                }
-                SelectMenuAction::ToggleAll => {
-                    let all_selected = self.filter.visible_indices().iter().all(|&i| self.entries[i].selected);
-                    for &i in self.filter.visible_indices() {
-                        self.entries[i].selected = !all_selected;
-                    }
-                }
-            }
+            let action =
+                self.select.on_key(self.filter.visible_indices().len(), available_height.saturating_sub(line_count + 1), key);
+            action.apply(&self.filter, &mut self.entries);
 
             match key {
                 Key::Ctrl('f') => self.filter.enter(),
+                Key::Char('/') => self.search.enter(),
+                Key::Char('n') => {
+                    let line = self.search.next_match();
+                    self.show_search_match(ctx, line);
+                }
+                Key::Char('N') => {
+                    let line = self.search.previous_match();
+                    self.show_search_match(ctx, line);
+                }
                 Key::Tab => {
                     self.show_full_message = !self.show_full_message;
                 }
+                Key::Char('v') => {
+                    if let Some(current_entry_index) = self.filter.get_visible_index(self.select.cursor) {
+                        let (revision, path) = (self.revision.clone(), self.entries[current_entry_index].name.clone());
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::FileView,
+                            ModeChangeInfo::file_view(ModeKind::RevisionDetails, revision, path),
+                        );
+                    }
+                }
+                Key::Char('T') => {
+                    let revision = self.revision.clone();
+                    ctx.event_sender
+                        .send_mode_change(ModeKind::Tree, ModeChangeInfo::tree(ModeKind::RevisionDetails, revision));
+                }
+                Key::Char('i') => {
+                    if let Err(error) = open_issue_references(&ctx.config, self.output.text()) {
+                        self.output.set(error);
+                    }
+                }
+                Key::Char('y') => {
+                    copy_to_clipboard(ctx, self.revision.clone(), |result| {
+                        ModeResponse::RevisionDetails(Response::Copied(result))
+                    });
+                }
+                Key::Char('Y') => {
+                    copy_to_clipboard(ctx, self.message.clone(), |result| {
+                        ModeResponse::RevisionDetails(Response::Copied(result))
+                    });
+                }
                 Key::Enter => {
                     if !self.entries.is_empty() {
                         let entries = self.get_selected_entries();
@@ -130,20 +239,43 @@ impl ModeTrait for Mode {
     fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
         let response = as_variant!(response, ModeResponse::RevisionDetails).unwrap();
         match response {
+            Response::Copied(Ok(())) => (),
+            Response::Copied(Err(error)) => self.output.set(error),
             Response::Info(info) => {
                 if let State::Waiting = self.state {
                     self.state = State::Idle;
                 }
 
-                self.output.set(info.message);
+                self.message = info.message.clone();
+                // the date goes before the subject, so it shows with the message collapsed too
+                let message = match info.date.is_empty() {
+                    true => info.message,
+                    false => format!("{} {}", info.date, info.message),
+                };
+                // like git log, the notes go below the message
+                match info.notes.is_empty() {
+                    true => self.output.set(message),
+                    false => {
+                        let notes: Vec<_> = info.notes.lines().map(|l| format!("    {}", l)).collect();
+                        self.output.set(format!("{}\n\nNotes:\n{}", message, notes.join("\n")));
+                    }
+                }
                 self.entries = info.entries;
+                self.entries.sort_unstable_by(|a, b| a.status.cmp(&b.status));
 
                 self.filter.filter(self.entries.iter());
                 self.select.saturate_cursor(self.filter.visible_indices().len());
+                if self.search.is_searching() {
+                    self.find();
+                }
             }
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus() || self.search.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
@@ -154,25 +286,31 @@ impl ModeTrait for Mode {
     fn header(&self) -> (&str, &str, &str) {
         (
             "revision details",
-            "[enter]diff",
-            "[tab]full message [Left]back [arrows]move [space]toggle [a]toggle all [ctrl+f]filter",
+            "[enter]diff [v]view file [T]tree [i]open issue [y]copy hash [Y]copy message",
+            "[tab]full message [/]search [n]next match [N]previous match [Left]back [arrows]move [space]toggle [a]toggle all [ctrl+f]filter",
         )
     }
 
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        let mut placeholders = vec![("hash", self.revision.clone())];
+        if let Some(current_entry_index) = self.filter.get_visible_index(self.select.cursor) {
+            placeholders.push(("file", self.entries[current_entry_index].name.clone()));
+        }
+        placeholders
+    }
+
     fn draw(&self, drawer: &mut Drawer) {
-        let filter_line_count = drawer.filter(&self.filter);
+        let filter_line_count = drawer.filter(&self.filter) + drawer.search(&self.search);
 
         let line_count = if self.show_full_message {
-            drawer.output(&self.output)
+            drawer.message(&self.output, self.search.as_str())
         } else {
             let output = self.output.text().lines().next().unwrap_or("");
             let output = match output.char_indices().nth(drawer.viewport_size.0.saturating_sub(1) as _) {
                 Some((i, c)) => &output[..i + c.len_utf8()],
                 None => output,
             };
-            drawer.str(output);
-            drawer.next_line();
-            1
+            drawer.message(&Output::new(output.to_owned()), self.search.as_str())
         };
 
         let line_count = filter_line_count + line_count;
@@ -0,0 +1,189 @@
+use std::thread;
+
+use crate::{
+    backend::{BackendResult, SetupInfo},
+    mode::*,
+    platform::Key,
+    ui::{glyph, Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<SetupInfo>),
+    Set(String), // the value of the config key being set
+}
+
+// sparse-checkout is the most recent command used
+const MIN_GIT_VERSION: (u32, u32) = (2, 25);
+
+// major and minor, a build suffix like ".windows.1" or " (Apple Git-145)" is ignored
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.split(['.', ' ']).map(str::parse::<u32>);
+    Some((numbers.next()?.ok()?, numbers.next()?.ok()?))
+}
+
+fn is_old(version: &str) -> bool {
+    parse_version(version).map(|v| v < MIN_GIT_VERSION).unwrap_or(false)
+}
+
+// whether the setup mode is worth showing before the first mode
+pub fn needs_setup(info: &SetupInfo) -> bool {
+    is_old(&info.git_version) || info.user_name.is_none() || info.user_email.is_none()
+}
+
+#[derive(Clone, Debug)]
+struct CheckEntry {
+    description: String,
+    problem: Option<String>,
+    key: Option<&'static str>, // the config key that fixes it once set
+    value: String,             // of the key, offered to be changed
+}
+impl SelectEntryDraw for CheckEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        match &self.problem {
+            None => drawer.fmt(format_args!(
+                "{}{} {}{}",
+                Color::DarkGreen,
+                glyph("\u{2713}", "+"),
+                Color::White,
+                self.description
+            )),
+            Some(problem) => drawer.fmt(format_args!(
+                "{}{} {}{} {}{}",
+                Color::DarkRed,
+                glyph("\u{2717}", "x"),
+                Color::White,
+                self.description,
+                Color::DarkRed,
+                problem
+            )),
+        }
+        1
+    }
+}
+
+fn check_entries(info: SetupInfo) -> Vec<CheckEntry> {
+    let version_problem = is_old(&info.git_version).then(|| {
+        format!("is older than {}.{}, sparse checkout and some other actions fail", MIN_GIT_VERSION.0, MIN_GIT_VERSION.1)
+    });
+    let identity = |key: &'static str, value: Option<String>| {
+        let problem = value.is_none().then(|| "is not set, git refuses to commit or guesses it from the machine".to_owned());
+        let value = value.unwrap_or_default();
+        let description = format!("{} {}", key, value).trim_end().to_owned();
+        CheckEntry { description, problem, key: Some(key), value }
+    };
+    vec![
+        CheckEntry {
+            description: format!("git {}", info.git_version),
+            problem: version_problem,
+            key: None,
+            value: String::new(),
+        },
+        identity("user.name", info.user_name),
+        identity("user.email", info.user_email),
+    ]
+}
+
+// shown when starting with something missing that would make later operations fail with cryptic errors,
+// the identity is set from here
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    entries: Vec<CheckEntry>,
+    output: Output,
+    select: SelectMenu,
+    editing: Option<(&'static str, bool)>, // the config key being set and whether it is set globally
+}
+impl Mode {
+    fn edit(&mut self, ctx: &ModeContext, global: bool) {
+        let entry = match self.entries.get(self.select.cursor) {
+            Some(entry) => entry,
+            None => return,
+        };
+        let key = match entry.key {
+            Some(key) => key,
+            None => return,
+        };
+        self.editing = Some((key, global));
+
+        let scope = if global { "for every repository" } else { "for this repository" };
+        let placeholder = format!("type in the {} {}...", key, scope);
+        let on_submit = |ctx: &ModeContext, value: String| {
+            ctx.event_sender.send_response(ModeResponse::Setup(Response::Set(value)));
+        };
+        ctx.event_sender.send_mode_change(
+            ModeKind::MessageInput,
+            ModeChangeInfo::message_input(ModeKind::Setup, true, placeholder, on_submit).with_initial_message(&entry.value),
+        );
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        self.waiting = true;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.setup_info();
+            ctx.event_sender.send_response(ModeResponse::Setup(Response::Refresh(result)));
+        });
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.entries.len(), available_height, key);
+
+        if !self.waiting {
+            match key {
+                Key::Enter => self.edit(ctx, true),
+                Key::Char('r') => self.edit(ctx, false),
+                _ => (),
+            }
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Setup).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(info) => {
+                        let done = !needs_setup(&info);
+                        self.entries = check_entries(info);
+                        self.select.saturate_cursor(self.entries.len());
+                        match done {
+                            true => self.output.set("all set, [Left] goes on".to_owned()),
+                            false => self.output.set(String::new()),
+                        }
+                    }
+                    Err(error) => self.output.set(error),
+                }
+            }
+            Response::Set(value) => {
+                if let Some((key, global)) = self.editing.take() {
+                    self.waiting = true;
+                    let ctx = ctx.clone();
+                    thread::spawn(move || {
+                        let result = ctx.backend.set_config(key, value.trim(), global).and_then(|_| ctx.backend.setup_info());
+                        ctx.event_sender.send_response(ModeResponse::Setup(Response::Refresh(result)));
+                    });
+                }
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("setup", "[enter]set for every repository [r]set for this repository", "[Left]continue [arrows]move")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        drawer.select_menu(&self.select, 0, false, self.entries.iter());
+        drawer.next_line();
+        drawer.output(&self.output);
+    }
+}
@@ -0,0 +1,182 @@
+use std::thread;
+
+use crate::{
+    backend::{BackendResult, SignatureEntry, SignatureStatus},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<SignatureEntry>>),
+    Range(String),
+}
+
+impl SelectEntryDraw for SignatureEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        let color = match self.status {
+            SignatureStatus::Good => Color::DarkGreen,
+            SignatureStatus::Bad => Color::DarkRed,
+            _ => Color::DarkYellow,
+        };
+        drawer.fmt(format_args!(
+            "{}{} {}{:<width$}{} ",
+            Color::DarkYellow,
+            self.hash,
+            color,
+            self.status.as_str(),
+            Color::White,
+            width = SignatureStatus::max_len()
+        ));
+        match &self.tag {
+            Some(tag) => drawer.fmt(format_args!("tag {}", tag)),
+            None => drawer.str(&self.subject),
+        }
+        if !self.key.is_empty() {
+            drawer.fmt(format_args!(" {}[{} {}", Color::DarkBlue, self.key, self.signer));
+            if !self.trust.is_empty() {
+                drawer.fmt(format_args!(", trust {}", self.trust));
+            }
+            drawer.str("]");
+        }
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    range: String,
+    entries: Vec<SignatureEntry>,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+impl Mode {
+    fn refresh(&mut self, ctx: &ModeContext) {
+        self.waiting = true;
+        let range = self.range.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.signatures(&range);
+            ctx.event_sender.send_response(ModeResponse::Signatures(Response::Refresh(result)));
+        });
+    }
+
+    fn input_range(&self, ctx: &ModeContext) {
+        let not_empty = false;
+        let placeholder = "type in the range to verify like 'v1.0..release', empty for HEAD...";
+        let on_submit = |ctx: &ModeContext, range: String| {
+            ctx.event_sender.send_response(ModeResponse::Signatures(Response::Range(range)));
+        };
+        ctx.event_sender.send_mode_change(
+            ModeKind::MessageInput,
+            ModeChangeInfo::message_input(ModeKind::Signatures, not_empty, placeholder, on_submit),
+        );
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        self.input_range(ctx);
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.filter.filter(self.entries.iter());
+            self.select.saturate_cursor(self.filter.visible_indices().len());
+
+            return ModeStatus { pending_input: true };
+        }
+
+        // the verified range sits above the filter and the list
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+
+        let current_entry_index = self.filter.get_visible_index(self.select.cursor);
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Enter => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let revision = self.entries[current_entry_index].hash.clone();
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::RevisionDetails,
+                        ModeChangeInfo::revision(ModeKind::Signatures, revision),
+                    );
+                }
+            }
+            Key::Char('r') if !self.waiting => self.input_range(ctx),
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Signatures).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(entries) if entries.is_empty() => {
+                        self.entries = entries;
+                        self.output.set("no commits!".to_owned());
+                    }
+                    Ok(entries) => {
+                        self.entries = entries;
+                        self.output.set(String::new());
+                    }
+                    Err(error) => {
+                        self.entries = Vec::new();
+                        self.output.set(error);
+                    }
+                }
+                self.filter.filter(self.entries.iter());
+                self.select = SelectMenu::default();
+            }
+            Response::Range(range) => {
+                self.range = match range.trim() {
+                    "" => "HEAD".to_owned(),
+                    range => range.to_owned(),
+                };
+                self.refresh(ctx);
+            }
+        }
+    }
+
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("signatures", "[r]range [enter]details", "[Left]back [arrows]move [ctrl+f]filter")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        let unsigned = self.entries.iter().filter(|e| e.status == SignatureStatus::Unsigned).count();
+        drawer.fmt(format_args!(
+            "{}{}: {} entries, {} unsigned",
+            Color::DarkYellow,
+            self.range,
+            self.entries.len(),
+            unsigned
+        ));
+        drawer.next_line();
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text().is_empty() {
+            drawer.select_menu(
+                &self.select,
+                1 + filter_line_count,
+                false,
+                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+            );
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
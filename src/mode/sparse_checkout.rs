@@ -0,0 +1,196 @@
+use std::thread;
+
+use crate::{
+    backend::{Backend, BackendResult, SparseEntry},
+    mode::*,
+    platform::Key,
+    ui::{Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<SparseEntry>>),
+    Add(String),
+}
+
+#[derive(Clone, Debug)]
+enum WaitOperation {
+    Refresh,
+    Add,
+    Remove,
+    Reapply,
+    Disable,
+}
+
+#[derive(Default, Clone, Debug)]
+enum State {
+    #[default]
+    Idle,
+    Waiting(WaitOperation),
+}
+
+impl SelectEntryDraw for SparseEntry {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.str(&self.directory);
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    state: State,
+    entries: Vec<SparseEntry>,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        if let State::Waiting(_) = self.state {
+            return;
+        }
+        self.state = State::Waiting(WaitOperation::Refresh);
+
+        self.output.set(String::new());
+        self.filter.filter(self.entries.iter());
+        self.select.saturate_cursor(self.filter.visible_indices().len());
+
+        request(ctx, |_| Ok(()));
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.filter.filter(self.entries.iter());
+            self.select.saturate_cursor(self.filter.visible_indices().len());
+
+            return ModeStatus { pending_input: true };
+        }
+
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+        if self.output.text().is_empty() {
+            self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+        } else {
+            self.output.on_key(available_height, key);
+        }
+
+        let current_entry_index = self.filter.get_visible_index(self.select.cursor);
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Char('n') => {
+                let not_empty = true;
+                let placeholder = "type in the directory to check out...";
+                let on_submit = |ctx: &ModeContext, directory: String| {
+                    ctx.event_sender.send_response(ModeResponse::SparseCheckout(Response::Add(directory)));
+                };
+                ctx.event_sender.send_mode_change(
+                    ModeKind::MessageInput,
+                    ModeChangeInfo::message_input(ModeKind::SparseCheckout, not_empty, placeholder, on_submit),
+                );
+            }
+            Key::Char('D') => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let entry = &self.entries[current_entry_index];
+                    self.state = State::Waiting(WaitOperation::Remove);
+
+                    let directory = entry.directory.clone();
+                    self.entries.remove(current_entry_index);
+                    self.filter.on_remove_entry(current_entry_index);
+                    self.select.on_remove_entry(self.select.cursor);
+                    request(ctx, move |b| b.sparse_checkout_remove(&directory));
+                }
+            }
+            Key::Char('r') => {
+                self.state = State::Waiting(WaitOperation::Reapply);
+                request(ctx, Backend::sparse_checkout_reapply);
+            }
+            Key::Char('X') => {
+                self.state = State::Waiting(WaitOperation::Disable);
+                request(ctx, Backend::sparse_checkout_disable);
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::SparseCheckout).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                self.entries = Vec::new();
+                self.output.set(String::new());
+
+                if let State::Waiting(_) = self.state {
+                    self.state = State::Idle;
+                }
+                if let State::Idle = self.state {
+                    match result {
+                        Ok(entries) if entries.is_empty() => {
+                            self.output.set("sparse checkout is disabled, [n] checks out only the given directory".into())
+                        }
+                        Ok(entries) => self.entries = entries,
+                        Err(error) => self.output.set(error),
+                    }
+                }
+
+                self.filter.filter(self.entries.iter());
+                self.select.saturate_cursor(self.filter.visible_indices().len());
+            }
+            Response::Add(directory) => {
+                self.state = State::Waiting(WaitOperation::Add);
+                request(ctx, move |b| b.sparse_checkout_add(&directory));
+            }
+        }
+    }
+
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        match self.state {
+            State::Idle => false,
+            State::Waiting(_) => true,
+        }
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        let name = match self.state {
+            State::Idle | State::Waiting(WaitOperation::Refresh) => "sparse checkout",
+            State::Waiting(WaitOperation::Add) => "add directory",
+            State::Waiting(WaitOperation::Remove) => "remove directory",
+            State::Waiting(WaitOperation::Reapply) => "reapply sparse checkout",
+            State::Waiting(WaitOperation::Disable) => "disable sparse checkout",
+        };
+        let (left_help, right_help) =
+            ("[n]add directory [D]remove directory [r]reapply [X]disable", "[Left]back [arrows]move [ctrl+f]filter");
+        (name, left_help, right_help)
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text.is_empty() {
+            drawer.select_menu(
+                &self.select,
+                filter_line_count,
+                false,
+                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
+            );
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
+
+fn request<F>(ctx: &ModeContext, f: F)
+where
+    F: 'static + Send + Sync + FnOnce(&dyn Backend) -> BackendResult<()>,
+{
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        use std::ops::Deref;
+
+        let result = f(ctx.backend.deref()).and_then(|_| ctx.backend.sparse_checkout_list());
+        ctx.event_sender.send_response(ModeResponse::SparseCheckout(Response::Refresh(result)));
+    });
+}
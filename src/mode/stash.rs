@@ -152,6 +152,10 @@ impl ModeTrait for Mode {
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
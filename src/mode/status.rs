@@ -1,7 +1,7 @@
-use std::thread;
+use std::{collections::HashSet, thread};
 
 use crate::{
-    backend::{Backend, BackendResult, FileStatus, RevisionEntry, StatusInfo},
+    backend::{Backend, BackendResult, FileStatus, LfsState, Operation, RevisionEntry, StatusInfo},
     mode::*,
     platform::Key,
     ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
@@ -12,6 +12,7 @@ pub enum Response {
     Refresh(StatusInfo),
     Commit(String),
     Stash(String),
+    Discard,
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +23,10 @@ enum WaitOperation {
     Stash,
     ResolveTakingOurs,
     ResolveTakingTheirs,
+    LfsPull,
+    Continue,
+    Skip,
+    Abort,
 }
 
 #[derive(Clone, Debug)]
@@ -36,28 +41,84 @@ impl Default for State {
 }
 
 impl SelectEntryDraw for RevisionEntry {
-    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
-        const NAME_TOO_LONG_PREFIX: &str = "...";
+    fn draw(&self, drawer: &mut Drawer, hovered: bool, _: bool) -> usize {
+        draw_entry(drawer, self, &self.name, 0, hovered)
+    }
+}
 
-        let name_available_width = (drawer.viewport_size.0 as usize)
-            .saturating_sub(2 + 1 + FileStatus::max_len() + 1 + 1 + NAME_TOO_LONG_PREFIX.len() + 1);
+fn draw_entry(drawer: &mut Drawer, entry: &RevisionEntry, name: &str, indent: usize, hovered: bool) -> usize {
+    const NAME_TOO_LONG_PREFIX: &str = "...";
+
+    let lfs_marker = match entry.lfs {
+        Some(LfsState::Downloaded) => " [lfs]",
+        Some(LfsState::Pointer) => " [lfs pointer]",
+        None => "",
+    };
+
+    let name_available_width = (drawer.viewport_size.0 as usize)
+        .saturating_sub(2 + indent + 1 + FileStatus::max_len() + 1 + 1 + NAME_TOO_LONG_PREFIX.len() + lfs_marker.len() + 1);
+
+    let (name_prefix, trimmed_name) = match name.char_indices().nth_back(name_available_width) {
+        Some((i, _)) => (NAME_TOO_LONG_PREFIX, &name[i..]),
+        None => ("", name),
+    };
+
+    drawer.selected_marker(entry.selected);
+    drawer.fmt(format_args!(
+        "{:indent$}[{:>width$}] {}{}{}{}{}",
+        "",
+        entry.status.as_str(),
+        name_prefix,
+        trimmed_name,
+        if hovered { Color::White } else { Color::DarkMagenta },
+        lfs_marker,
+        Color::White,
+        indent = indent,
+        width = FileStatus::max_len(),
+    ));
+
+    1
+}
 
-        let (name_prefix, trimmed_name) = match self.name.char_indices().nth_back(name_available_width) {
-            Some((i, _)) => (NAME_TOO_LONG_PREFIX, &self.name[i..]),
-            None => ("", &self.name[..]),
-        };
+// when grouped by directory, the files are listed under their directories, which collapse
+#[derive(Clone, Debug)]
+enum Row {
+    Directory { path: String, depth: usize, entries: Vec<usize> }, // the indices of all the files under it
+    File { index: usize, depth: usize },
+}
 
-        let selected_text = if self.selected { '+' } else { ' ' };
-        drawer.fmt(format_args!(
-            "{} [{:>width$}] {}{}",
-            selected_text,
-            self.status.as_str(),
-            name_prefix,
-            trimmed_name,
-            width = FileStatus::max_len(),
-        ));
-
-        1
+struct RowDraw<'a> {
+    row: &'a Row,
+    entries: &'a [RevisionEntry],
+    collapsed: bool,
+}
+impl SelectEntryDraw for RowDraw<'_> {
+    fn draw(&self, drawer: &mut Drawer, hovered: bool, _: bool) -> usize {
+        match self.row {
+            Row::Directory { path, depth, entries } => {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                drawer.selected_marker(entries.iter().all(|&i| self.entries[i].selected));
+                drawer.fmt(format_args!(
+                    "{:indent$}{}{}{}/{} ({})",
+                    "",
+                    if self.collapsed { "+ " } else { "- " },
+                    Color::DarkYellow,
+                    name,
+                    Color::White,
+                    entries.len(),
+                    indent = depth * 2,
+                ));
+                1
+            }
+            Row::File { index, depth } => {
+                let entry = &self.entries[*index];
+                let name = match *depth {
+                    0 => &entry.name[..],
+                    _ => entry.name.rsplit('/').next().unwrap_or(&entry.name),
+                };
+                draw_entry(drawer, entry, name, depth * 2, hovered)
+            }
+        }
     }
 }
 
@@ -69,13 +130,91 @@ pub struct Mode {
     select: SelectMenu,
     filter: Filter,
     from: ModeKind,
+    operation: Option<Operation>, // a merge, rebase, cherry-pick or revert stopped halfway
+    restore: Option<ListSession>, // applied once the entries are loaded
+    group: bool,
+    collapsed: HashSet<String>, // paths of the collapsed directories
+    rows: Vec<Row>,
 }
 impl Mode {
+    // the filtered entries, under their directories when grouped
+    fn update_rows(&mut self) {
+        self.filter.filter(self.entries.iter());
+        self.rows.clear();
+        if !self.group {
+            self.rows.extend(self.filter.visible_indices().iter().map(|&index| Row::File { index, depth: 0 }));
+            self.select.saturate_cursor(self.rows.len());
+            return;
+        }
+
+        let entries = &self.entries;
+        let mut indices = self.filter.visible_indices().to_vec();
+        indices.sort_by(|&a, &b| entries[a].name.cmp(&entries[b].name));
+
+        let mut rows = Vec::new();
+        let mut open = Vec::new(); // rows of the directories the previous file is in
+        for index in indices {
+            let name = &entries[index].name;
+            let directories: Vec<_> = name.match_indices('/').map(|(slash, _)| &name[..slash]).collect();
+            let shared = open
+                .iter()
+                .zip(&directories)
+                .take_while(|&(&row, &directory)| matches!(&rows[row], Row::Directory { path, .. } if path == directory))
+                .count();
+            open.truncate(shared);
+            for (depth, directory) in directories.iter().enumerate().skip(shared) {
+                open.push(rows.len());
+                rows.push(Row::Directory { path: directory.to_string(), depth, entries: Vec::new() });
+            }
+            for &row in &open {
+                if let Row::Directory { entries, .. } = &mut rows[row] {
+                    entries.push(index);
+                }
+            }
+            rows.push(Row::File { index, depth: directories.len() });
+        }
+
+        // a row shows up when none of the directories above it is collapsed
+        let collapsed = &self.collapsed;
+        rows.retain(|row| {
+            let path = match row {
+                Row::Directory { path, .. } => path,
+                Row::File { index, .. } => &entries[*index].name,
+            };
+            !path.match_indices('/').any(|(slash, _)| collapsed.contains(&path[..slash]))
+        });
+        self.rows = rows;
+        self.select.saturate_cursor(self.rows.len());
+    }
+
+    fn hovered_entry_index(&self) -> Option<usize> {
+        match self.rows.get(self.select.cursor) {
+            Some(Row::File { index, .. }) => Some(*index),
+            _ => None,
+        }
+    }
+
+    fn hovered_directory(&self) -> Option<(&str, &[usize])> {
+        match self.rows.get(self.select.cursor) {
+            Some(Row::Directory { path, entries, .. }) => Some((path, entries)),
+            _ => None,
+        }
+    }
+
     fn get_selected_entries(&self) -> Vec<RevisionEntry> {
         let entries: Vec<_> = self.entries.iter().filter(|&e| e.selected).cloned().collect();
         entries
     }
 
+    fn discard(&mut self, ctx: &ModeContext) {
+        self.state = State::Waiting(WaitOperation::Discard);
+        let entries = self.get_selected_entries();
+        self.remove_selected_entries();
+
+        let config = ctx.config.clone();
+        request(ctx, move |b| b.discard(&entries, &excluded(&config)));
+    }
+
     fn remove_selected_entries(&mut self) {
         let previous_len = self.entries.len();
 
@@ -96,6 +235,7 @@ impl Mode {
             self.select.cursor = 0;
             self.filter.clear();
         }
+        self.update_rows();
     }
 
     fn commit<S: Into<String>>(&mut self, ctx: &ModeContext, message: S, amend: bool) {
@@ -108,15 +248,17 @@ impl Mode {
         //log(format!("amend: {}, commit message: \n {:?}, entries: {:?}\n", amend, message, entries));
 
         let ctx = ctx.clone();
-        thread::spawn(move || match ctx.backend.commit(&message, &entries, amend) {
+        thread::spawn(move || match ctx.backend.commit(&message, &entries, amend, &excluded(&ctx.config)) {
             Ok(()) => {
                 log(format!("commit ok\n"));
                 ctx.event_sender.send_response(ModeResponse::Status(Response::Idle));
                 ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Status));
             }
-            Err(error) => ctx
-                .event_sender
-                .send_response(ModeResponse::Status(Response::Refresh(StatusInfo { header: error, entries: Vec::new() }))),
+            Err(error) => ctx.event_sender.send_response(ModeResponse::Status(Response::Refresh(StatusInfo {
+                header: error,
+                entries: Vec::new(),
+                operation: ctx.backend.operation(),
+            }))),
         });
     }
 }
@@ -129,9 +271,12 @@ impl ModeTrait for Mode {
         self.state = State::Waiting(WaitOperation::Refresh);
 
         self.output.set(String::new());
-        self.filter.filter(self.entries.iter());
-        self.select.saturate_cursor(self.filter.visible_indices().len());
+        self.group = ctx.config.get_bool("status.group_by_directory", false);
+        self.update_rows();
         self.from = info.from;
+        if let Some(ModeInfo::Session(session)) = info.info {
+            self.restore = Some(session);
+        }
 
         request(ctx, |_| Ok(()));
     }
@@ -139,8 +284,7 @@ impl ModeTrait for Mode {
     fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
         if self.filter.has_focus() {
             self.filter.on_key(key);
-            self.filter.filter(self.entries.iter());
-            self.select.saturate_cursor(self.filter.visible_indices().len());
+            self.update_rows();
 
             return ModeStatus { pending_input: true };
         }
@@ -149,35 +293,61 @@ impl ModeTrait for Mode {
         if self.output.line_count() > 1 {
             self.output.on_key(available_height, key);
         } else {
-            match self.select.on_key(self.filter.visible_indices().len(), available_height.saturating_sub(2), key) {
-                SelectMenuAction::None => (),
-                SelectMenuAction::Toggle(i) => {
-                    if let Some(i) = self.filter.get_visible_index(i) {
-                        self.entries[i].selected = !self.entries[i].selected
-                    }
-                }
-                SelectMenuAction::ToggleAll => {
-                    let all_selected = self.filter.visible_indices().iter().all(|&i| self.entries[i].selected);
-                    for &i in self.filter.visible_indices() {
+            let action = self.select.on_key(self.rows.len(), available_height.saturating_sub(2), key);
+            match (action, self.rows.get(self.select.cursor)) {
+                // toggling a directory marks all of its files, or unmarks them when they all are
+                (SelectMenuAction::Toggle(_), Some(Row::Directory { entries, .. })) => {
+                    let all_selected = entries.iter().all(|&i| self.entries[i].selected);
+                    for &i in entries {
                         self.entries[i].selected = !all_selected;
                     }
                 }
+                (SelectMenuAction::Toggle(_), Some(&Row::File { index, .. })) => {
+                    self.entries[index].selected = !self.entries[index].selected;
+                }
+                (action, _) => action.apply(&self.filter, &mut self.entries),
             }
         }
 
         match key {
             Key::Ctrl('f') => self.filter.enter(),
+            Key::Char('g') => {
+                self.group = !self.group;
+                self.update_rows();
+            }
+
+            Key::Char('c') | Key::Char('C')
+                if !self.entries.is_empty()
+                    && (key == Key::Char('C') || ctx.config.get_bool("commit.conventional", false)) =>
+            {
+                ctx.event_sender.send_mode_change(ModeKind::ConventionalCommit, ModeChangeInfo::new(ModeKind::Status));
+            }
             Key::Char('c') => {
                 if !self.entries.is_empty() {
-                    let not_empty = true;
-                    let placeholder = "type in the commit message...";
-                    let on_submit = |ctx: &ModeContext, message: String| {
-                        ctx.event_sender.send_response(ModeResponse::Status(Response::Commit(message)));
-                    };
-                    ctx.event_sender.send_mode_change(
-                        ModeKind::MessageInput,
-                        ModeChangeInfo::message_input(ModeKind::Status, not_empty, placeholder, on_submit),
-                    );
+                    let commit_all = !self.entries.iter().any(|e| e.selected);
+                    let ctx = ctx.clone();
+                    thread::spawn(move || {
+                        // pre-populate from the commit template, the prepare-commit-msg hook runs on the commit like a cli one
+                        let message = ctx.backend.commit_template().unwrap_or_default();
+                        // the lines staged from the diff view are committed instead of everything
+                        let prompt = match commit_all && ctx.backend.has_staged_changes().unwrap_or(false) {
+                            true => "committing the staged changes only",
+                            false => "",
+                        };
+
+                        let not_empty = true;
+                        let placeholder = "type in the commit message...";
+                        let on_submit = |ctx: &ModeContext, message: String| {
+                            ctx.event_sender.send_response(ModeResponse::Status(Response::Commit(message)));
+                        };
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::MessageInput,
+                            ModeChangeInfo::message_input(ModeKind::Status, not_empty, placeholder, on_submit)
+                                .with_prompt(prompt, false)
+                                .with_initial_message(message)
+                                .with_co_authors(),
+                        );
+                    });
                 }
             }
             Key::Char('A') => {
@@ -187,11 +357,43 @@ impl ModeTrait for Mode {
             }
             Key::Char('D') => {
                 if matches!(self.state, State::Idle) && !self.entries.is_empty() {
-                    self.state = State::Waiting(WaitOperation::Discard);
+                    // on a directory, exactly its files are discarded
+                    if let Some((_, entries)) = self.hovered_directory() {
+                        let entries = entries.to_vec();
+                        for entry in &mut self.entries {
+                            entry.selected = false;
+                        }
+                        for i in entries {
+                            self.entries[i].selected = true;
+                        }
+                    }
+
                     let entries = self.get_selected_entries();
-                    self.remove_selected_entries();
+                    let (confirm, message) = match entries.is_empty() {
+                        true => {
+                            let mut message = "discard all changes (reset --hard HEAD) and remove untracked files".to_owned();
+                            let excluded = excluded(&ctx.config);
+                            if !excluded.is_empty() {
+                                message.push_str(&format!(", but in {}", excluded.join(", ")));
+                            }
+                            (ctx.config.get_bool("confirm.clean", true), message)
+                        }
+                        false => (
+                            ctx.config.get_bool("confirm.discard", true),
+                            entries.iter().fold("discard changes to:\n".to_owned(), |message, e| message + "\n" + &e.name),
+                        ),
+                    };
 
-                    request(ctx, move |b| b.discard(&entries));
+                    if confirm {
+                        ctx.event_sender.send_mode_change(
+                            ModeKind::Confirm,
+                            ModeChangeInfo::confirm(ModeKind::Status, message, String::new(), |ctx, _| {
+                                ctx.event_sender.send_response(ModeResponse::Status(Response::Discard));
+                            }),
+                        );
+                    } else {
+                        self.discard(ctx);
+                    }
                 }
             }
             Key::Char('O') => {
@@ -210,6 +412,27 @@ impl ModeTrait for Mode {
                     request(ctx, move |b| b.resolve_taking_theirs(&entries));
                 }
             }
+            Key::Char('p') => {
+                ctx.event_sender.send_mode_change(ModeKind::SparseCheckout, ModeChangeInfo::new(ModeKind::Status));
+            }
+            Key::Char('N') if matches!(self.state, State::Idle) && self.operation.is_some() => {
+                self.state = State::Waiting(WaitOperation::Continue);
+                request(ctx, Backend::operation_continue);
+            }
+            Key::Char('K') if matches!(self.state, State::Idle) && self.operation.is_some() => {
+                self.state = State::Waiting(WaitOperation::Skip);
+                request(ctx, Backend::operation_skip);
+            }
+            Key::Char('Q') if matches!(self.state, State::Idle) && self.operation.is_some() => {
+                self.state = State::Waiting(WaitOperation::Abort);
+                request(ctx, Backend::operation_abort);
+            }
+            Key::Char('L') => {
+                if matches!(self.state, State::Idle) {
+                    self.state = State::Waiting(WaitOperation::LfsPull);
+                    request(ctx, Backend::lfs_pull);
+                }
+            }
             Key::Ctrl('s') => {
                 if !self.entries.is_empty() {
                     let not_empty = false;
@@ -224,7 +447,13 @@ impl ModeTrait for Mode {
                 }
             }
             Key::Enter => {
-                if !self.entries.is_empty() {
+                if let Some((path, _)) = self.hovered_directory() {
+                    let path = path.to_owned();
+                    if !self.collapsed.remove(&path) {
+                        self.collapsed.insert(path);
+                    }
+                    self.update_rows();
+                } else if !self.entries.is_empty() {
                     let entries = self.get_selected_entries();
 
                     let ctx = ctx.clone();
@@ -257,11 +486,25 @@ impl ModeTrait for Mode {
                 }
 
                 self.entries = info.entries;
-
-                self.filter.filter(self.entries.iter());
-                self.select.saturate_cursor(self.filter.visible_indices().len());
+                self.operation = info.operation;
+
+                self.update_rows();
+                if let Some(session) = self.restore.take() {
+                    session.restore(&mut self.select, &mut self.filter, &self.entries, |e| &e.name);
+                    self.update_rows();
+                    let entries = &self.entries;
+                    let hovered = self
+                        .rows
+                        .iter()
+                        .position(|row| matches!(row, Row::File { index, .. } if entries[*index].name == session.hovered));
+                    if let Some(cursor) = hovered {
+                        self.select.cursor = cursor;
+                        self.select.scroll = session.scroll.min(cursor);
+                    }
+                }
             }
             Response::Commit(message) => self.commit(ctx, message, false),
+            Response::Discard => self.discard(ctx),
             Response::Stash(message) => {
                 self.state = State::Waiting(WaitOperation::Stash);
 
@@ -276,6 +519,10 @@ impl ModeTrait for Mode {
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
@@ -291,14 +538,31 @@ impl ModeTrait for Mode {
             State::Waiting(WaitOperation::Discard) => "discard",
             State::Waiting(WaitOperation::ResolveTakingOurs) => "resolve taking ours",
             State::Waiting(WaitOperation::ResolveTakingTheirs) => "resolve taking theirs",
+            State::Waiting(WaitOperation::LfsPull) => "lfs pull",
+            State::Waiting(WaitOperation::Continue) => "continue",
+            State::Waiting(WaitOperation::Skip) => "skip",
+            State::Waiting(WaitOperation::Abort) => "abort",
+        };
+        let left_help = match self.operation {
+            Some(_) => "[N]continue [K]skip [Q]abort [c]commit [D]discard [enter]diff [O]take ours [T]take theirs",
+            None => "[c]commit [C]conventional commit [A]amend [D]discard [ctrl+s]stash [enter]diff [O]take ours [T]take theirs [L]lfs pull [p]sparse checkout",
         };
-        let (left_help, right_help) = (
-            "[c]commit [A]amend [D]discard [ctrl+s]stash [enter]diff [O]take ours [T]take theirs",
-            "[arrows]move [space]toggle [a]toggle all [ctrl+f]filter",
-        );
+        let right_help = "[arrows]move [space]toggle [a]toggle all [g]group by directory [ctrl+f]filter";
         (name, left_help, right_help)
     }
 
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        match self.hovered_entry_index() {
+            Some(current_entry_index) => vec![("file", self.entries[current_entry_index].name.clone())],
+            None => Vec::new(),
+        }
+    }
+
+    fn session(&self) -> Option<ListSession> {
+        let hovered = self.hovered_entry_index().map(|i| self.entries[i].name.clone()).unwrap_or_default();
+        Some(ListSession { hovered, scroll: self.select.scroll, filter: self.filter.as_str().to_owned() })
+    }
+
     fn draw(&self, drawer: &mut Drawer) {
         //log(format!("start to draw status: \n {:?}:\n", self.output.text()));
         let filter_line_count = drawer.filter(&self.filter);
@@ -316,12 +580,15 @@ impl ModeTrait for Mode {
             drawer.str(output);
             drawer.next_line();
             drawer.next_line();
-            drawer.select_menu(
-                &self.select,
-                2 + filter_line_count,
-                false,
-                self.filter.visible_indices().iter().map(|&i| &self.entries[i]),
-            );
+            let rows: Vec<_> = self
+                .rows
+                .iter()
+                .map(|row| {
+                    let collapsed = matches!(row, Row::Directory { path, .. } if self.collapsed.contains(path));
+                    RowDraw { row, entries: &self.entries, collapsed }
+                })
+                .collect();
+            drawer.select_menu(&self.select, 2 + filter_line_count, false, rows.iter());
 
             if self.entries.is_empty() {
                 let empty_message = match self.state {
@@ -334,6 +601,11 @@ impl ModeTrait for Mode {
     }
 }
 
+// only the global config sets them, so that a cloned repository cannot hide files from the commits and discards
+fn excluded(config: &Config) -> Vec<&str> {
+    config.get_global_list("status.exclude")
+}
+
 fn request<F>(ctx: &ModeContext, f: F)
 where
     F: 'static + Send + Sync + FnOnce(&dyn Backend) -> BackendResult<()>,
@@ -342,9 +614,9 @@ where
     thread::spawn(move || {
         use std::ops::Deref;
 
-        let mut info = match f(ctx.backend.deref()).and_then(|_| ctx.backend.status()) {
+        let mut info = match f(ctx.backend.deref()).and_then(|_| ctx.backend.status(&excluded(&ctx.config))) {
             Ok(info) => info,
-            Err(error) => StatusInfo { header: error, entries: Vec::new() },
+            Err(error) => StatusInfo { header: error, entries: Vec::new(), operation: ctx.backend.operation() },
         };
         info.entries.sort_unstable_by(|a, b| a.status.cmp(&b.status));
 
@@ -11,6 +11,12 @@ pub enum Response {
     Refresh(BackendResult<Vec<TagEntry>>),
     Checkout,
     New(String),
+    Delete(String),       // tag names one per line
+    DeleteRemote(String), // tag names one per line
+    OperationFailed(WorkTreeOperation, String),
+    Autostash,
+    ArchivePath(String),
+    Archive(String), // the prefix, the path was asked for first
 }
 
 #[derive(Clone, Debug)]
@@ -18,6 +24,9 @@ enum WaitOperation {
     Refresh,
     New,
     Delete,
+    Push,
+    DeleteRemote,
+    Archive,
 }
 
 #[derive(Clone, Debug)]
@@ -33,6 +42,7 @@ impl Default for State {
 
 impl SelectEntryDraw for TagEntry {
     fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        drawer.selected_marker(self.selected);
         drawer.str(&self.name);
         1
     }
@@ -45,12 +55,39 @@ pub struct Mode {
     output: Output,
     select: SelectMenu,
     filter: Filter,
+    restore: Option<ListSession>,         // applied once the entries are loaded
+    autostash: Option<WorkTreeOperation>, // run again around a stash once confirmed
+    archive: Option<ArchiveExport>,
 }
+impl Mode {
+    // a checkout refused because of local changes is offered again around a stash
+    fn checkout(&self, ctx: &ModeContext, operation: WorkTreeOperation, autostash: bool) {
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            use std::ops::Deref;
+
+            match operation.run(ctx.backend.deref(), autostash) {
+                Ok(()) => {
+                    ctx.event_sender.send_response(ModeResponse::Tags(Response::Checkout));
+                    ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Tags));
+                }
+                Err(error) if !autostash => {
+                    ctx.event_sender.send_response(ModeResponse::Tags(Response::OperationFailed(operation, error)));
+                }
+                Err(error) => ctx.event_sender.send_response(ModeResponse::Tags(Response::Refresh(Err(error)))),
+            }
+        });
+    }
+}
+
 impl ModeTrait for Mode {
-    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
         if let State::Waiting(_) = self.state {
             return;
         }
+        if let Some(ModeInfo::Session(session)) = info.info {
+            self.restore = Some(session);
+        }
         self.state = State::Waiting(WaitOperation::Refresh);
 
         self.output.set(String::new());
@@ -71,7 +108,8 @@ impl ModeTrait for Mode {
 
         let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
         if self.output.text().is_empty() {
-            self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+            let action = self.select.on_key(self.filter.visible_indices().len(), available_height, key);
+            action.apply(&self.filter, &mut self.entries);
         } else {
             self.output.on_key(available_height, key);
         }
@@ -81,16 +119,8 @@ impl ModeTrait for Mode {
             Key::Ctrl('f') => self.filter.enter(),
             Key::Enter => {
                 if let Some(current_entry_index) = current_entry_index {
-                    let entry = &self.entries[current_entry_index];
-                    let name = entry.name.clone();
-                    let ctx = ctx.clone();
-                    thread::spawn(move || match ctx.backend.checkout(&name) {
-                        Ok(()) => {
-                            ctx.event_sender.send_response(ModeResponse::Tags(Response::Checkout));
-                            ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::new(ModeKind::Tags));
-                        }
-                        Err(error) => ctx.event_sender.send_response(ModeResponse::Tags(Response::Refresh(Err(error)))),
-                    });
+                    let operation = WorkTreeOperation::Checkout(self.entries[current_entry_index].name.clone());
+                    self.checkout(ctx, operation, false);
                 }
             }
             Key::Char('n') => {
@@ -104,6 +134,16 @@ impl ModeTrait for Mode {
                     ModeChangeInfo::message_input(ModeKind::Branches, not_empty, placeholder, on_submit),
                 );
             }
+            Key::Char('D') if self.entries.iter().any(|e| e.selected) => {
+                let names: Vec<_> = self.entries.iter().filter(|e| e.selected).map(|e| e.name.as_str()).collect();
+                let message = format!("delete the selected tags?\n\n{}", names.join("\n"));
+                ctx.event_sender.send_mode_change(
+                    ModeKind::Confirm,
+                    ModeChangeInfo::confirm(ModeKind::Tags, message, names.join("\n"), |ctx, names| {
+                        ctx.event_sender.send_response(ModeResponse::Tags(Response::Delete(names)));
+                    }),
+                );
+            }
             Key::Char('D') => {
                 if let Some(current_entry_index) = current_entry_index {
                     let entry = &self.entries[current_entry_index];
@@ -116,6 +156,44 @@ impl ModeTrait for Mode {
                     request(ctx, move |b| b.delete_tag(&name));
                 }
             }
+            Key::Char('p') if current_entry_index.is_some() => {
+                self.state = State::Waiting(WaitOperation::Push);
+                let names: Vec<_> =
+                    selected_or_hovered(&self.entries, current_entry_index).iter().map(|e| e.name.clone()).collect();
+                request(ctx, move |b| b.push_tags(&names));
+            }
+            Key::Char('P') => {
+                self.state = State::Waiting(WaitOperation::Push);
+                request(ctx, Backend::push_all_tags);
+            }
+            Key::Char('X') if current_entry_index.is_some() => {
+                let names: Vec<_> =
+                    selected_or_hovered(&self.entries, current_entry_index).iter().map(|e| e.name.as_str()).collect();
+                if ctx.config.get_bool("confirm.delete_remote", true) {
+                    let message = format!("delete from the remote?\n\n{}", names.join("\n"));
+                    ctx.event_sender.send_mode_change(
+                        ModeKind::Confirm,
+                        ModeChangeInfo::confirm(ModeKind::Tags, message, names.join("\n"), |ctx, names| {
+                            ctx.event_sender.send_response(ModeResponse::Tags(Response::DeleteRemote(names)));
+                        }),
+                    );
+                } else {
+                    self.state = State::Waiting(WaitOperation::DeleteRemote);
+                    let names = names.iter().map(|&n| n.to_owned()).collect::<Vec<_>>();
+                    request(ctx, move |b| b.delete_remote_tags(&names));
+                }
+            }
+            Key::Char('Z') => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let name = &self.entries[current_entry_index].name;
+                    let archive = ArchiveExport::new(name.clone(), name);
+                    let on_submit = |ctx: &ModeContext, path: String| {
+                        ctx.event_sender.send_response(ModeResponse::Tags(Response::ArchivePath(path)));
+                    };
+                    ctx.event_sender.send_mode_change(ModeKind::MessageInput, archive.ask_path(ModeKind::Tags, on_submit));
+                    self.archive = Some(archive);
+                }
+            }
             _ => (),
         }
 
@@ -141,15 +219,64 @@ impl ModeTrait for Mode {
 
                 self.filter.filter(self.entries.iter());
                 self.select.saturate_cursor(self.filter.visible_indices().len());
+                if let Some(session) = self.restore.take() {
+                    session.restore(&mut self.select, &mut self.filter, &self.entries, |e| &e.name);
+                }
             }
             Response::Checkout => self.state = State::Idle,
+            Response::OperationFailed(operation, error) => {
+                let on_confirm = |ctx: &ModeContext, _| {
+                    ctx.event_sender.send_response(ModeResponse::Tags(Response::Autostash));
+                };
+                match operation.confirm_autostash(ModeKind::Tags, &error, on_confirm) {
+                    Some(info) => {
+                        self.autostash = Some(operation);
+                        ctx.event_sender.send_mode_change(ModeKind::Confirm, info);
+                    }
+                    None => self.output.set(error),
+                }
+            }
+            Response::Autostash => {
+                if let Some(operation) = self.autostash.take() {
+                    self.checkout(ctx, operation, true);
+                }
+            }
             Response::New(name) => {
                 self.state = State::Waiting(WaitOperation::New);
                 request(ctx, move |b| b.new_tag(&name));
             }
+            Response::Delete(names) => {
+                self.state = State::Waiting(WaitOperation::Delete);
+                request(ctx, move |b| names.lines().try_for_each(|name| b.delete_tag(name)));
+            }
+            Response::ArchivePath(path) => {
+                if let Some(archive) = &mut self.archive {
+                    let on_submit = |ctx: &ModeContext, prefix: String| {
+                        ctx.event_sender.send_response(ModeResponse::Tags(Response::Archive(prefix)));
+                    };
+                    let info = archive.ask_prefix(path.trim().to_owned(), ModeKind::Tags, on_submit);
+                    ctx.event_sender.send_mode_change(ModeKind::MessageInput, info);
+                }
+            }
+            Response::Archive(prefix) => {
+                if let Some(archive) = self.archive.take() {
+                    self.state = State::Waiting(WaitOperation::Archive);
+                    let prefix = prefix.trim().to_owned();
+                    request(ctx, move |b| b.archive(&archive.revision, &archive.path, &prefix));
+                }
+            }
+            Response::DeleteRemote(names) => {
+                self.state = State::Waiting(WaitOperation::DeleteRemote);
+                let names: Vec<_> = names.lines().map(String::from).collect();
+                request(ctx, move |b| b.delete_remote_tags(&names));
+            }
         }
     }
 
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
     fn is_waiting_response(&self) -> bool {
         match self.state {
             State::Idle => false,
@@ -162,11 +289,28 @@ impl ModeTrait for Mode {
             State::Idle | State::Waiting(WaitOperation::Refresh) => "tags",
             State::Waiting(WaitOperation::New) => "new tag",
             State::Waiting(WaitOperation::Delete) => "delete tag",
+            State::Waiting(WaitOperation::Push) => "push tags",
+            State::Waiting(WaitOperation::DeleteRemote) => "delete remote tag",
+            State::Waiting(WaitOperation::Archive) => "archive",
         };
-        let (left_help, right_help) = ("[enter]checkout [n]new [D]delete", "[arrows]move [ctrl+f]filter");
+        let (left_help, right_help) = (
+            "[enter]checkout [n]new [D]delete [p]push [P]push all [X]delete from remote [Z]archive",
+            "[arrows]move [ctrl+f]filter",
+        );
         (name, left_help, right_help)
     }
 
+    fn placeholders(&self) -> Vec<(&'static str, String)> {
+        match self.filter.get_visible_index(self.select.cursor) {
+            Some(current_entry_index) => vec![("tag", self.entries[current_entry_index].name.clone())],
+            None => Vec::new(),
+        }
+    }
+
+    fn session(&self) -> Option<ListSession> {
+        Some(ListSession::save(&self.select, &self.filter, &self.entries, |e| &e.name))
+    }
+
     fn draw(&self, drawer: &mut Drawer) {
         let filter_line_count = drawer.filter(&self.filter);
         if self.output.text.is_empty() {
@@ -0,0 +1,196 @@
+use std::{collections::HashSet, thread};
+
+use crate::{
+    backend::{BackendResult, TreeEntry},
+    mode::*,
+    platform::Key,
+    ui::{Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(BackendResult<Vec<TreeEntry>>),
+}
+
+#[derive(Default, Clone, Debug)]
+enum State {
+    #[default]
+    Idle,
+    Waiting,
+}
+
+// entries are drawn indented under their directory, or with their full path while filtering
+struct TreeEntryDraw<'a> {
+    entry: &'a TreeEntry,
+    expanded: bool,
+    flat: bool,
+}
+impl SelectEntryDraw for TreeEntryDraw<'_> {
+    fn draw(&self, drawer: &mut Drawer, _: bool, _: bool) -> usize {
+        let marker = match (self.entry.directory, self.expanded) {
+            (true, true) => "- ",
+            (true, false) => "+ ",
+            (false, _) => "  ",
+        };
+        if self.flat {
+            drawer.fmt(format_args!("{}{}", marker, self.entry.path));
+        } else {
+            drawer.fmt(format_args!("{:indent$}{}{}", "", marker, self.entry.name(), indent = self.entry.depth() * 2));
+        }
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    state: State,
+    revision: String,
+    entries: Vec<TreeEntry>,
+    expanded: HashSet<String>, // paths of the expanded directories
+    visible_indices: Vec<usize>,
+    output: Output,
+    select: SelectMenu,
+    filter: Filter,
+}
+impl Mode {
+    fn update_visible_indices(&mut self) {
+        self.filter.filter(self.entries.iter());
+        if self.filter.as_str().is_empty() {
+            // an entry shows up when all the directories above it are expanded
+            let expanded = &self.expanded;
+            self.visible_indices = (0..self.entries.len())
+                .filter(|&i| {
+                    let path = &self.entries[i].path;
+                    path.match_indices('/').all(|(slash, _)| expanded.contains(&path[..slash]))
+                })
+                .collect();
+        } else {
+            self.visible_indices = self.filter.visible_indices().to_vec();
+        }
+        self.select.saturate_cursor(self.visible_indices.len());
+    }
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, info: ModeChangeInfo) {
+        if let State::Waiting = self.state {
+            return;
+        }
+        self.state = State::Waiting;
+
+        self.revision = as_variant!(info.info.unwrap(), ModeInfo::Tree).unwrap();
+        self.output.set(String::new());
+        self.filter.clear();
+        self.select.cursor = 0;
+
+        let ctx = ctx.clone();
+        let revision = self.revision.clone();
+        thread::spawn(move || {
+            let result = ctx.backend.tree(&revision);
+            ctx.event_sender.send_response(ModeResponse::Tree(Response::Refresh(result)));
+        });
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        if self.filter.has_focus() {
+            self.filter.on_key(key);
+            self.update_visible_indices();
+
+            return ModeStatus { pending_input: true };
+        }
+
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+        if self.output.text().is_empty() {
+            self.select.on_key(self.visible_indices.len(), available_height, key);
+        } else {
+            self.output.on_key(available_height, key);
+        }
+
+        let current_entry_index = self.visible_indices.get(self.select.cursor).copied();
+        match key {
+            Key::Ctrl('f') => self.filter.enter(),
+            Key::Enter => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let entry = &self.entries[current_entry_index];
+                    if entry.directory {
+                        if !self.expanded.remove(&entry.path) {
+                            self.expanded.insert(entry.path.clone());
+                        }
+                        self.update_visible_indices();
+                    } else {
+                        let (revision, path) = (self.revision.clone(), entry.path.clone());
+                        ctx.event_sender
+                            .send_mode_change(ModeKind::FileView, ModeChangeInfo::file_view(ModeKind::Tree, revision, path));
+                    }
+                }
+            }
+            Key::Char('B') => {
+                if let Some(entry) = current_entry_index.map(|i| &self.entries[i]).filter(|e| !e.directory) {
+                    let (revision, path) = (self.revision.clone(), entry.path.clone());
+                    ctx.event_sender.send_mode_change(ModeKind::Blame, ModeChangeInfo::blame(ModeKind::Tree, revision, path));
+                }
+            }
+            Key::Char('h') => {
+                if let Some(current_entry_index) = current_entry_index {
+                    let path = self.entries[current_entry_index].path.clone();
+                    ctx.event_sender.send_mode_change(ModeKind::Log, ModeChangeInfo::file_history(ModeKind::Tree, path));
+                }
+            }
+            _ => (),
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Tree).unwrap();
+        match response {
+            Response::Refresh(result) => {
+                if let State::Waiting = self.state {
+                    self.state = State::Idle;
+                }
+                match result {
+                    Ok(entries) => self.entries = entries,
+                    Err(error) => self.output.set(error),
+                }
+                self.update_visible_indices();
+            }
+        }
+    }
+
+    fn has_text_focus(&self) -> bool {
+        self.filter.has_focus()
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        match self.state {
+            State::Idle => false,
+            State::Waiting => true,
+        }
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        (
+            "tree",
+            "[enter]expand/collapse directory or view file [h]history [B]blame",
+            "[Left]back [arrows]move [ctrl+f]filter",
+        )
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        let filter_line_count = drawer.filter(&self.filter);
+        if self.output.text().is_empty() {
+            let flat = !self.filter.as_str().is_empty();
+            let entries: Vec<_> = self
+                .visible_indices
+                .iter()
+                .map(|&i| {
+                    let entry = &self.entries[i];
+                    TreeEntryDraw { entry, expanded: self.expanded.contains(&entry.path), flat }
+                })
+                .collect();
+            drawer.select_menu(&self.select, filter_line_count, false, entries.iter());
+        } else {
+            drawer.output(&self.output);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::thread;
+
+use crate::{
+    backend::{BackendResult, UndoEntry},
+    mode::*,
+    platform::Key,
+    ui::{Color, Drawer, SelectEntryDraw, RESERVED_LINES_COUNT},
+};
+
+pub enum Response {
+    Refresh(Vec<UndoEntry>),
+    Undo(BackendResult<()>),
+}
+
+impl SelectEntryDraw for UndoEntry {
+    fn draw(&self, drawer: &mut Drawer, hovered: bool, _: bool) -> usize {
+        const SHORT_HASH_LEN: usize = 8;
+
+        let color = if hovered { Color::White } else { Color::DarkYellow };
+        let branch = self.branch.as_deref().unwrap_or("detached");
+        let head = self.head.get(..SHORT_HASH_LEN).unwrap_or(&self.head);
+        drawer.fmt(format_args!("{}{}{} (was {} at {})", color, self.operation, Color::White, branch, head));
+        1
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct Mode {
+    waiting: bool,
+    entries: Vec<UndoEntry>, // the most recent operation first
+    output: Output,
+    select: SelectMenu,
+}
+
+impl ModeTrait for Mode {
+    fn on_enter(&mut self, ctx: &ModeContext, _info: ModeChangeInfo) {
+        let mut entries = ctx.backend.undo_list();
+        entries.reverse();
+        self.entries = entries;
+    }
+
+    fn on_key(&mut self, ctx: &ModeContext, key: Key) -> ModeStatus {
+        let available_height = (ctx.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + 1);
+        self.select.on_key(self.entries.len(), available_height, key);
+
+        if let Key::Enter = key {
+            if !self.waiting && !self.entries.is_empty() {
+                self.waiting = true;
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    let result = ctx.backend.undo();
+                    let entries = ctx.backend.undo_list();
+                    ctx.event_sender.send_response(ModeResponse::Undo(Response::Undo(result)));
+                    ctx.event_sender.send_response(ModeResponse::Undo(Response::Refresh(entries)));
+                });
+            }
+        }
+
+        ModeStatus { pending_input: false }
+    }
+
+    fn on_response(&mut self, _ctx: &ModeContext, response: ModeResponse) {
+        let response = as_variant!(response, ModeResponse::Undo).unwrap();
+        match response {
+            Response::Refresh(mut entries) => {
+                entries.reverse();
+                self.entries = entries;
+                self.select.saturate_cursor(self.entries.len());
+            }
+            Response::Undo(result) => {
+                self.waiting = false;
+                match result {
+                    Ok(()) => self.output.set("undone!".to_owned()),
+                    Err(error) => self.output.set(error),
+                }
+            }
+        }
+    }
+
+    fn is_waiting_response(&self) -> bool {
+        self.waiting
+    }
+
+    fn header(&self) -> (&str, &str, &str) {
+        ("undo", "[enter]undo the most recent operation", "[Left]back [arrows]move")
+    }
+
+    fn draw(&self, drawer: &mut Drawer) {
+        if self.entries.is_empty() {
+            drawer.fmt(format_args!("{}nothing to undo!", Color::DarkYellow));
+            drawer.next_line();
+        } else {
+            drawer.select_menu(&self.select, 1, false, self.entries.iter());
+        }
+        drawer.next_line();
+        drawer.output(&self.output);
+    }
+}
@@ -1,3 +1,12 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
 #[cfg(unix)]
 use std::os::unix::io::RawFd;
 
@@ -12,7 +21,8 @@ use winapi::{
         fileapi::GetFileType,
         handleapi::INVALID_HANDLE_VALUE,
         processenv::GetStdHandle,
-        winbase::{FILE_TYPE_CHAR, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
+        synchapi::WaitForSingleObject,
+        winbase::{FILE_TYPE_CHAR, INFINITE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
         wincon::{
             GetConsoleScreenBufferInfo, ENABLE_PROCESSED_OUTPUT, ENABLE_VIRTUAL_TERMINAL_PROCESSING, ENABLE_WINDOW_INPUT,
         },
@@ -63,11 +73,123 @@ impl Key {
     }
 }
 
+// not through "cmd /C start", which would run what follows a '&' in the url
+pub fn open_url(url: &str) -> Result<(), String> {
+    let (command_name, args): (&str, &[&str]) = if cfg!(windows) {
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else {
+        ("xdg-open", &[])
+    };
+
+    let mut command = Command::new(command_name);
+    command.args(args).arg(url).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    match command.spawn() {
+        // some openers only return once the browser is closed
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+            Ok(())
+        }
+        Err(error) => Err(format!("could not open '{}' with '{}': {}", url, command_name, error)),
+    }
+}
+
+// hands the text to the first clipboard utility that takes it
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let commands: &[(&str, &[&str])] = if cfg!(windows) {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+    };
+
+    for (command_name, args) in commands {
+        let mut command = Command::new(command_name);
+        command.args(*args).stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null());
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        if child.wait().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    Err("no clipboard utility could copy, set 'clipboard.osc52 = true' to copy through the terminal".into())
+}
+
+// the text of the first clipboard utility that gives it
+pub fn paste_from_clipboard() -> Result<String, String> {
+    let commands: &[(&str, &[&str])] = if cfg!(windows) {
+        &[("powershell", &["-NoProfile", "-Command", "Get-Clipboard"])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbpaste", &[])]
+    } else {
+        &[
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+        ]
+    };
+
+    for (command_name, args) in commands {
+        let mut command = Command::new(command_name);
+        command.args(*args).stdin(Stdio::null()).stderr(Stdio::null());
+        match command.output() {
+            Ok(output) if output.status.success() => return Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            _ => continue,
+        }
+    }
+    Err("no clipboard utility could paste".into())
+}
+
+// runs $EDITOR on the file and waits for it, the terminal must be handed over with Platform::suspend() first
+pub fn open_editor(path: &str, line: usize) -> Result<(), String> {
+    let default_editor = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor = env::var("EDITOR").ok().filter(|editor| !editor.trim().is_empty());
+    let editor = editor.as_deref().unwrap_or(default_editor);
+
+    // the variable may hold arguments too, like "code --wait"
+    let mut words = editor.split_whitespace();
+    let command_name = words.next().unwrap();
+    let mut command = Command::new(command_name);
+    command.args(words);
+    if line > 0 && command_name != "notepad" {
+        command.arg(format!("+{}", line));
+    }
+    command.arg(path);
+
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("editor '{}' exited with {}", editor, status)),
+        Err(error) => Err(format!("could not open '{}' with '{}': {}", path, editor, error)),
+    }
+}
+
+// set while another program owns the terminal, the event reader then leaves its input alone
+static INPUT_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+// returns whether the input was suspended, the pending input then belonged to the other program
+fn wait_while_input_suspended() -> bool {
+    if !INPUT_SUSPENDED.load(Ordering::Acquire) {
+        return false;
+    }
+    while INPUT_SUSPENDED.load(Ordering::Acquire) {
+        thread::sleep(Duration::from_millis(20));
+    }
+    true
+}
+
 // ========================================================= UNIX
 
 #[cfg(unix)]
 pub struct Platform {
     original: libc::termios,
+    raw: libc::termios,
 }
 
 #[cfg(unix)]
@@ -78,7 +200,7 @@ impl Platform {
             return None;
         }
 
-        let original = unsafe {
+        let (original, raw) = unsafe {
             let mut original = std::mem::zeroed();
             libc::tcgetattr(libc::STDIN_FILENO, &mut original);
             let mut new = original.clone();
@@ -98,11 +220,22 @@ impl Platform {
             new.c_cc[libc::VMIN] = 0;
             new.c_cc[libc::VTIME] = 0;
             libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &new);
-            original
+            (original, new)
         };
         let backspace_code = original.c_cc[libc::VERASE];
 
-        Some((Self { original }, PlatformEventReader::new(backspace_code)))
+        Some((Self { original, raw }, PlatformEventReader::new(backspace_code)))
+    }
+
+    // hands the terminal over to another program until resume()
+    pub fn suspend(&self) {
+        INPUT_SUSPENDED.store(true, Ordering::Release);
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+
+    pub fn resume(&self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.raw) };
+        INPUT_SUSPENDED.store(false, Ordering::Release);
     }
 
     pub fn terminal_size() -> (u16, u16) {
@@ -136,7 +269,7 @@ const MAX_TRIGGERED_EVENT_COUNT: usize = 32;
 
 #[cfg(unix)]
 impl PlatformEventReader {
-    pub fn read(fd: RawFd, buf: &mut [u8]) -> Result<usize, ()> {
+    fn read(fd: RawFd, buf: &mut [u8]) -> Result<usize, ()> {
         let len = unsafe { libc::read(fd, buf.as_mut_ptr() as _, buf.len() as _) };
         if len >= 0 {
             Ok(len as _)
@@ -291,6 +424,7 @@ impl PlatformEventReader {
 
         for event_index in epoll_wait(self.queue_fd, &mut epoll_events) {
             match event_index {
+                0 if wait_while_input_suspended() => (),
                 0 => match Self::read(libc::STDIN_FILENO, &mut self.buf) {
                     Ok(0) | Err(()) => panic!("could not read from stdin"),
                     Ok(len) => Self::parse_terminal_keys(&self.buf[..len], self.backspace_code, keys),
@@ -390,6 +524,7 @@ impl PlatformEventReader {
 
         for event in kqueue_wait(self.queue_fd, &mut kqueue_events) {
             match event {
+                Ok(TriggeredEvent { index: 0, .. }) if wait_while_input_suspended() => (),
                 Ok(TriggeredEvent { index: 0, data }) => {
                     self.buf.resize(data as _, 0);
                     match Self::read(libc::STDIN_FILENO, &mut self.buf) {
@@ -413,6 +548,11 @@ pub struct Platform {
     output_handle_original_mode: DWORD,
 }
 
+#[cfg(windows)]
+const INPUT_MODE: DWORD = ENABLE_WINDOW_INPUT;
+#[cfg(windows)]
+const OUTPUT_MODE: DWORD = ENABLE_PROCESSED_OUTPUT | ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+
 #[cfg(windows)]
 impl Platform {
     pub fn new() -> Option<(Self, PlatformEventReader)> {
@@ -424,9 +564,8 @@ impl Platform {
             return None;
         }
 
-        let input_handle_original_mode = Self::swap_console_mode(input_handle, ENABLE_WINDOW_INPUT);
-        let output_handle_original_mode =
-            Self::swap_console_mode(output_handle, ENABLE_PROCESSED_OUTPUT | ENABLE_VIRTUAL_TERMINAL_PROCESSING);
+        let input_handle_original_mode = Self::swap_console_mode(input_handle, INPUT_MODE);
+        let output_handle_original_mode = Self::swap_console_mode(output_handle, OUTPUT_MODE);
 
         Some((Self { input_handle_original_mode, output_handle_original_mode }, PlatformEventReader))
     }
@@ -445,6 +584,26 @@ impl Platform {
         (console_info.dwSize.X as _, console_info.dwSize.Y as _)
     }
 
+    // hands the console over to another program until resume()
+    pub fn suspend(&self) {
+        INPUT_SUSPENDED.store(true, Ordering::Release);
+        self.set_modes(self.input_handle_original_mode, self.output_handle_original_mode);
+    }
+
+    pub fn resume(&self) {
+        self.set_modes(INPUT_MODE, OUTPUT_MODE);
+        INPUT_SUSPENDED.store(false, Ordering::Release);
+    }
+
+    fn set_modes(&self, input_mode: DWORD, output_mode: DWORD) {
+        if let Some(handle) = Self::get_std_handle(STD_INPUT_HANDLE) {
+            Self::set_console_mode(handle, input_mode);
+        }
+        if let Some(handle) = Self::get_std_handle(STD_OUTPUT_HANDLE) {
+            Self::set_console_mode(handle, output_mode);
+        }
+    }
+
     fn get_std_handle(which: DWORD) -> Option<HANDLE> {
         let handle = unsafe { GetStdHandle(which) };
         if handle != NULL && handle != INVALID_HANDLE_VALUE {
@@ -475,12 +634,7 @@ impl Platform {
 #[cfg(windows)]
 impl Drop for Platform {
     fn drop(&mut self) {
-        if let Some(handle) = Platform::get_std_handle(STD_INPUT_HANDLE) {
-            Platform::set_console_mode(handle, self.input_handle_original_mode);
-        }
-        if let Some(handle) = Platform::get_std_handle(STD_OUTPUT_HANDLE) {
-            Platform::set_console_mode(handle, self.output_handle_original_mode);
-        }
+        self.set_modes(self.input_handle_original_mode, self.output_handle_original_mode);
     }
 }
 
@@ -497,6 +651,11 @@ impl PlatformEventReader {
             None => return,
         };
 
+        unsafe { WaitForSingleObject(input_handle, INFINITE) };
+        if wait_while_input_suspended() {
+            return;
+        }
+
         let mut events = [unsafe { std::mem::zeroed() }; 32];
         let mut event_count = 0;
         let result = unsafe { ReadConsoleInputW(input_handle, events.as_mut_ptr(), events.len() as _, &mut event_count) };
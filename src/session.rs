@@ -0,0 +1,46 @@
+use std::{fs, path::Path};
+
+use crate::mode::{ListSession, ModeKind};
+
+// kept in the git directory so that each repository, and each worktree, has its own
+const SESSION_FILE_NAME: &str = "gituse-session";
+
+// only the modes that open without any other information can be restored
+const RESTORED_MODES: &[ModeKind] = &[ModeKind::Status, ModeKind::Log, ModeKind::Branches, ModeKind::Tags];
+
+// "key = value" lines, like the config
+pub fn load(git_dir: &Path) -> Option<(ModeKind, ListSession)> {
+    let text = fs::read_to_string(git_dir.join(SESSION_FILE_NAME)).ok()?;
+    let mut mode = None;
+    let mut session = ListSession::default();
+    for (key, value) in text.lines().filter_map(|l| l.split_once(" = ")) {
+        match key {
+            "mode" => mode = RESTORED_MODES.iter().find(|m| m.name() == value).cloned(),
+            "hovered" => session.hovered = value.to_owned(),
+            "scroll" => session.scroll = value.parse().unwrap_or(0),
+            "filter" => session.filter = value.to_owned(),
+            _ => (),
+        }
+    }
+    mode.map(|mode| (mode, session))
+}
+
+// a mode that cannot be restored removes the previous session, so the next launch starts as usual
+pub fn save(git_dir: &Path, session: Option<(ModeKind, ListSession)>) -> Result<(), String> {
+    let path = git_dir.join(SESSION_FILE_NAME);
+    let (mode, session) = match session.filter(|(mode, _)| RESTORED_MODES.contains(mode)) {
+        Some(session) => session,
+        None => {
+            let _ = fs::remove_file(&path);
+            return Ok(());
+        }
+    };
+    let text = format!(
+        "mode = {}\nhovered = {}\nscroll = {}\nfilter = {}\n",
+        mode.name(),
+        session.hovered,
+        session.scroll,
+        session.filter
+    );
+    fs::write(&path, text).map_err(|error| format!("could not write {:?}: {}", path, error))
+}
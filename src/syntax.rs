@@ -0,0 +1,294 @@
+use std::path::Path;
+
+pub struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    keywords: &'static [&'static str],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    Text,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "break",
+    "case",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "include",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "true",
+    "typedef",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for",
+        "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+        "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const C: Syntax = Syntax { line_comments: &["//"], block_comment: Some(("/*", "*/")), keywords: C_KEYWORDS };
+
+const GO: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "false",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+};
+
+const JAVA_LIKE: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    keywords: &[
+        "abstract",
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+    ],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except",
+        "False", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "None", "nonlocal", "not", "or",
+        "pass", "raise", "return", "True", "try", "while", "with", "yield",
+    ],
+};
+
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in", "local", "return",
+        "then", "until", "while",
+    ],
+};
+
+// config files only get their comments, strings and numbers highlighted
+const CONFIG: Syntax = Syntax { line_comments: &["#"], block_comment: None, keywords: &["false", "true"] };
+
+pub fn syntax_for_path(path: &str) -> Option<&'static Syntax> {
+    let extension = Path::new(path).extension()?.to_str()?;
+    match extension {
+        "rs" => Some(&RUST),
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" => Some(&C),
+        "go" => Some(&GO),
+        "java" | "kt" | "cs" | "js" | "jsx" | "ts" | "tsx" | "swift" | "scala" => Some(&JAVA_LIKE),
+        "py" => Some(&PYTHON),
+        "sh" | "bash" | "zsh" => Some(&SHELL),
+        "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" => Some(&CONFIG),
+        _ => None,
+    }
+}
+
+impl Syntax {
+    // splits a line into highlighted tokens, in_block_comment carries a block comment over to the next line
+    pub fn tokenize<'a>(&self, line: &'a str, in_block_comment: &mut bool) -> Vec<(TokenKind, &'a str)> {
+        let mut tokens: Vec<(TokenKind, &'a str)> = Vec::new();
+        let mut push = |kind: TokenKind, text: &'a str| match tokens.last_mut() {
+            Some((last_kind, last_text)) if *last_kind == kind && kind == TokenKind::Text => {
+                let start = last_text.as_ptr() as usize - line.as_ptr() as usize;
+                *last_text = &line[start..start + last_text.len() + text.len()];
+            }
+            _ => tokens.push((kind, text)),
+        };
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+
+            if *in_block_comment {
+                let (_, end) = self.block_comment.unwrap();
+                let len = match rest.find(end) {
+                    Some(position) => {
+                        *in_block_comment = false;
+                        position + end.len()
+                    }
+                    None => rest.len(),
+                };
+                push(TokenKind::Comment, &rest[..len]);
+                i += len;
+                continue;
+            }
+
+            if self.line_comments.iter().any(|c| rest.starts_with(c)) {
+                push(TokenKind::Comment, rest);
+                break;
+            }
+            if let Some((begin, _)) = self.block_comment.filter(|(begin, _)| rest.starts_with(begin)) {
+                *in_block_comment = true;
+                push(TokenKind::Comment, &rest[..begin.len()]);
+                i += begin.len();
+                continue;
+            }
+
+            let c = rest.chars().next().unwrap();
+            let len = if let Some(len) = string_len(rest, c) {
+                push(TokenKind::String, &rest[..len]);
+                len
+            } else if c.is_alphanumeric() || c == '_' {
+                let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+                let word = &rest[..len];
+                if c.is_ascii_digit() {
+                    push(TokenKind::Number, word);
+                } else if self.keywords.contains(&word) {
+                    push(TokenKind::Keyword, word);
+                } else {
+                    push(TokenKind::Text, word);
+                }
+                len
+            } else {
+                push(TokenKind::Text, &rest[..c.len_utf8()]);
+                c.len_utf8()
+            };
+            i += len;
+        }
+
+        tokens
+    }
+}
+
+// a single quote only starts a string when it closes soon after, so rust lifetimes stay plain text
+fn string_len(text: &str, quote: char) -> Option<usize> {
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        if quote == '\'' && i > 4 {
+            return None;
+        }
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return Some(i + 1),
+            _ => (),
+        }
+    }
+
+    match quote {
+        '"' => Some(text.len()), // unterminated strings run to the end of the line
+        _ => None,
+    }
+}
@@ -18,3 +18,197 @@ pub fn log<S: Into<String>>(info: S) {
         file.write_all(info.into().as_bytes()).unwrap();
     }
 }
+
+#[derive(Clone, Debug)]
+enum PatternAtom {
+    Char(char),
+    Any,
+    Digit,
+    Word,
+    Space,
+    Class(bool, Vec<(char, char)>), // negated, the ranges
+}
+impl PatternAtom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Char(expected) => c == *expected,
+            Self::Any => true,
+            Self::Digit => c.is_ascii_digit(),
+            Self::Word => c.is_alphanumeric() || c == '_',
+            Self::Space => c.is_whitespace(),
+            Self::Class(negated, ranges) => ranges.iter().any(|&(low, high)| low <= c && c <= high) != *negated,
+        }
+    }
+}
+
+// the small part of the regular expressions issue references need: characters, '.', '\d', '\w', '\s',
+// classes like "[A-Z]" or "[^ ]" and the '*', '+' and '?' repetitions, no groups nor alternatives
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    items: Vec<(PatternAtom, usize, usize)>, // the atom with its minimum and maximum count
+}
+impl Pattern {
+    // anything that is not part of the syntax above matches itself, like an unclosed '['
+    pub fn new(pattern: &str) -> Self {
+        let mut items = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '.' => PatternAtom::Any,
+                '\\' => match chars.next() {
+                    Some('d') => PatternAtom::Digit,
+                    Some('w') => PatternAtom::Word,
+                    Some('s') => PatternAtom::Space,
+                    Some(c) => PatternAtom::Char(c),
+                    None => PatternAtom::Char('\\'),
+                },
+                '[' => match Self::class(&chars.clone().collect::<String>()) {
+                    Some((atom, len)) => {
+                        chars.nth(len - 1);
+                        atom
+                    }
+                    None => PatternAtom::Char('['),
+                },
+                '*' | '+' | '?' if !items.is_empty() => {
+                    let (_, min, max) = items.last_mut().unwrap();
+                    match c {
+                        '*' => (*min, *max) = (0, usize::MAX),
+                        '+' => *max = usize::MAX,
+                        _ => *min = 0,
+                    }
+                    continue;
+                }
+                c => PatternAtom::Char(c),
+            };
+            items.push((atom, 1, 1));
+        }
+        Self { items }
+    }
+
+    // the class after its '[', with the count of characters up to its ']'
+    fn class(text: &str) -> Option<(PatternAtom, usize)> {
+        let (negated, body) = match text.strip_prefix('^') {
+            Some(body) => (true, body),
+            None => (false, text),
+        };
+        // a ']' right at the start is part of the class
+        let close = body.char_indices().skip(1).find(|&(_, c)| c == ']').map(|(i, _)| i)?;
+        let chars: Vec<char> = body[..close].chars().collect();
+        let mut ranges = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+        let len = negated as usize + body[..=close].chars().count();
+        Some((PatternAtom::Class(negated, ranges), len))
+    }
+
+    // the end of the longest match starting right at the start, the repetitions backing off when the rest fails
+    pub fn match_at(&self, text: &str, start: usize) -> Option<usize> {
+        Self::match_items(&self.items, text, start)
+    }
+
+    fn match_items(items: &[(PatternAtom, usize, usize)], text: &str, start: usize) -> Option<usize> {
+        let ((atom, min, max), rest) = match items.split_first() {
+            Some(first) => first,
+            None => return Some(start),
+        };
+        let mut ends = vec![start];
+        for c in text[start..].chars().take(*max) {
+            if !atom.matches(c) {
+                break;
+            }
+            ends.push(ends.last().unwrap() + c.len_utf8());
+        }
+        ends.iter().skip(*min).rev().find_map(|&end| Self::match_items(rest, text, end))
+    }
+}
+
+// an issue reference is a match of one of the patterns (e.g. "#12" for "#\d+" or "JIRA-34" for "JIRA-\d+")
+// that does not continue a word, returns the byte ranges of the references found in the text
+pub fn issue_references(text: &str, patterns: &[Pattern]) -> Vec<(usize, usize)> {
+    let mut references = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let is_word_start = !text[..start].ends_with(|c: char| c.is_alphanumeric());
+        let found = if is_word_start {
+            patterns.iter().filter_map(|pattern| pattern.match_at(text, start)).find(|&end| end > start)
+        } else {
+            None
+        };
+
+        match found {
+            Some(end) => {
+                references.push((start, end));
+                start = end;
+            }
+            None => start += text[start..].chars().next().map(char::len_utf8).unwrap_or(1),
+        }
+    }
+    references
+}
+
+// the byte ranges where the pattern is found in the text, ignoring the ascii case
+pub fn search_matches(text: &str, pattern: &str) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    if pattern.is_empty() {
+        return matches;
+    }
+    let mut start = 0;
+    while start < text.len() {
+        let end = start + pattern.len();
+        match text.get(start..end) {
+            Some(found) if found.eq_ignore_ascii_case(pattern) => {
+                matches.push((start, end));
+                start = end;
+            }
+            _ => start += text[start..].chars().next().map(char::len_utf8).unwrap_or(1),
+        }
+    }
+    matches
+}
+
+// a single argument for the shell the custom actions run in
+pub fn shell_quote(text: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+// standard alphabet with padding
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+// "yyyy-mm-dd" in utc, days are converted to a civil date as in howard hinnant's days_from_civil inverse
+pub fn date_from_unix_time(seconds: i64) -> String {
+    let days = seconds.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
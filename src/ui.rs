@@ -1,7 +1,24 @@
-use std::fmt;
-
-use crate::mode::diff::{DIFF_FORMAT_FILE_HEADER_CONTENT, DIFF_FORMAT_FILE_HEADER_LINE, DIFF_FORMAT_LINE_HEADER};
-use crate::mode::{Filter, Output, ReadLine, SelectMenu};
+use std::{
+    fmt,
+    iter::Peekable,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::mode::diff::{
+    whitespace_errors, DIFF_FORMAT_FILE_HEADER_CONTENT, DIFF_FORMAT_FILE_HEADER_LINE, DIFF_FORMAT_LINE_HEADER,
+};
+use crate::mode::{Filter, Output, ReadLine, Search, SelectMenu};
+use crate::{
+    config::Config,
+    messages::GLOBAL_HELP,
+    syntax::{Syntax, TokenKind},
+    tool,
+};
+
+pub mod screen;
 
 pub const HEADER_LINE_COUNT: usize = 2;
 pub const RESERVED_LINES_COUNT: usize = HEADER_LINE_COUNT + 1;
@@ -14,6 +31,65 @@ pub static RESET_STYLE_CODE: &[u8] = b"\x1b[0;49m";
 pub static BEGIN_TITLE_CODE: &[u8] = b"\x1b]0;";
 pub static END_TITLE_CODE: &[u8] = b"\x07";
 
+// no colors and only ascii, for monochrome terminals and screen readers
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+// the ascii replacement when plain
+pub fn glyph(glyph: &'static str, ascii: &'static str) -> &'static str {
+    if is_plain() {
+        ascii
+    } else {
+        glyph
+    }
+}
+
+// at most len chars, translated help lines are not ascii
+fn truncate_chars(text: &str, len: usize) -> &str {
+    match text.char_indices().nth(len) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+// asks the terminal to put the text in the clipboard, which also works over ssh
+pub fn clipboard_code(text: &str) -> Vec<u8> {
+    let code = format!("\x1b]52;c;{}\x07", tool::base64_encode(text.as_bytes()));
+    // tmux only passes the sequence through to the outer terminal when wrapped, with its escapes doubled
+    match std::env::var_os("TMUX") {
+        Some(_) => format!("\x1bPtmux;{}\x1b\\", code.replace('\x1b', "\x1b\x1b")).into_bytes(),
+        None => code.into_bytes(),
+    }
+}
+
+// whitespace errors get a red background, or inverse video without colors
+fn highlight_whitespace_errors<I: Iterator<Item = (usize, usize)>>(
+    buf: &mut Vec<u8>,
+    errors: &mut Peekable<I>,
+    i: usize,
+    background: Color,
+) {
+    if errors.next_if(|&(_, end)| end == i).is_some() {
+        match is_plain() {
+            true => set_inverse(buf, false),
+            false => set_background_color(buf, background),
+        }
+    }
+    if errors.peek().is_some_and(|&(begin, _)| begin == i) {
+        match is_plain() {
+            true => set_inverse(buf, true),
+            false => set_background_color(buf, Color::DarkRed),
+        }
+    }
+}
+
 pub fn clear_until_new_line(buf: &mut Vec<u8>) {
     buf.extend_from_slice(b"\x1b[0K");
 }
@@ -31,13 +107,37 @@ pub fn move_cursor_to_next_line(buf: &mut Vec<u8>) {
 }
 
 pub fn set_background_color(buf: &mut Vec<u8>, color: Color) {
+    if is_plain() {
+        return;
+    }
     buf.extend_from_slice(b"\x1b[48;5;");
     buf.extend_from_slice(color.code().as_bytes());
     buf.push(b'm');
 }
 
+pub fn set_underline(buf: &mut Vec<u8>, underline: bool) {
+    buf.extend_from_slice(if underline { b"\x1b[4m" } else { b"\x1b[24m" });
+}
+
+pub fn set_inverse(buf: &mut Vec<u8>, inverse: bool) {
+    buf.extend_from_slice(if inverse { b"\x1b[7m" } else { b"\x1b[27m" });
+}
+
+// search matches are in inverse video, called with the byte index of each character and the line length at its end
+fn highlight_search_matches<I: Iterator<Item = (usize, usize)>>(buf: &mut Vec<u8>, matches: &mut Peekable<I>, i: usize) {
+    if matches.next_if(|&(_, end)| end == i).is_some() {
+        set_inverse(buf, false);
+    }
+    if matches.peek().is_some_and(|&(begin, _)| begin == i) {
+        set_inverse(buf, true);
+    }
+}
+
 static BEGIN_FOREGROUND_COLOR_CODE: &str = "\x1b[38;5;";
 pub fn set_foreground_color(buf: &mut Vec<u8>, color: Color) {
+    if is_plain() {
+        return;
+    }
     buf.extend_from_slice(BEGIN_FOREGROUND_COLOR_CODE.as_bytes());
     buf.extend_from_slice(color.code().as_bytes());
     buf.push(b'm');
@@ -70,6 +170,9 @@ impl Color {
 }
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if is_plain() {
+            return Ok(());
+        }
         f.write_str(BEGIN_FOREGROUND_COLOR_CODE)?;
         f.write_str(self.code())?;
         f.write_str("m")?;
@@ -84,12 +187,13 @@ pub trait SelectEntryDraw {
 pub struct Drawer {
     buf: Vec<u8>,
     pub viewport_size: (u16, u16),
+    pub config: Arc<Config>,
 }
 
 impl Drawer {
-    pub fn new(mut buf: Vec<u8>, viewport_size: (u16, u16)) -> Self {
+    pub fn new(mut buf: Vec<u8>, viewport_size: (u16, u16), config: Arc<Config>) -> Self {
         buf.clear();
-        Self { buf, viewport_size }
+        Self { buf, viewport_size, config }
     }
 
     pub fn take_buf(self) -> Vec<u8> {
@@ -101,18 +205,37 @@ impl Drawer {
         clear_to_end(&mut self.buf);
     }
 
-    pub fn header(&mut self, current_mode_name: &str, left_help: &str, right_help: &str, spinner: u8) {
+    pub fn header<G, F>(
+        &mut self,
+        current_mode_name: &str,
+        left_help: &str,
+        right_help: &str,
+        spinner: u8,
+        global_disabled: G,
+        disabled: F,
+    ) where
+        G: Fn(&str) -> bool,
+        F: Fn(&str) -> bool,
+    {
         let background_color = Color::Black;
         let foreground_color = Color::DarkYellow;
 
+        let inverse = is_plain() && self.config.get_bool("ui.inverse_video", false);
+
         let set_color = |buf: &mut Vec<u8>| {
             set_background_color(buf, background_color);
             set_foreground_color(buf, foreground_color);
+            if inverse {
+                set_inverse(buf, false);
+            }
         };
 
         let toggle_color = |buf: &mut Vec<u8>| {
             set_background_color(buf, foreground_color);
             set_foreground_color(buf, background_color);
+            if inverse {
+                set_inverse(buf, true);
+            }
         };
 
         move_cursor_to_zero(&mut self.buf);
@@ -127,58 +250,123 @@ impl Drawer {
         self.buf.extend_from_slice(current_mode_name.as_bytes());
         self.buf.push(b' ');
 
+        let config = self.config.clone();
+        let messages = config.messages();
         let version = env!("CARGO_PKG_VERSION");
-        let header_help = format!("[s]status [l]log [b]branches [t]tags [S]stash Ver:{}", version);
-        let mut header_help = header_help.as_bytes();
-        let current_mode_len = 3 + 1 + current_mode_name.len() + 1;
+        let header_help = format!("{} Ver:{}", messages.help(GLOBAL_HELP), version);
+        let current_mode_len = 3 + 1 + current_mode_name.chars().count() + 1;
         let available_width = self.viewport_size.0.saturating_sub(1) as usize;
+        let header_help = truncate_chars(&header_help, available_width.saturating_sub(current_mode_len));
 
-        if current_mode_len + header_help.len() > available_width {
-            let overflow_len = current_mode_len + header_help.len() - available_width;
-            header_help = &header_help[..header_help.len() - overflow_len];
-        }
-
-        let spacer_len = available_width - current_mode_len - header_help.len();
+        let spacer_len = available_width.saturating_sub(current_mode_len + header_help.chars().count());
 
         set_color(&mut self.buf);
         self.buf.extend(std::iter::repeat(b' ').take(spacer_len));
-        self.buf.extend_from_slice(header_help);
+        self.help(header_help, foreground_color, &global_disabled);
 
         self.next_line();
 
         toggle_color(&mut self.buf);
 
-        let mut left_help = left_help.as_bytes();
-        let mut right_help = right_help.as_bytes();
-
-        if left_help.len() > available_width {
-            left_help = &left_help[..available_width];
-            right_help = &[];
-        } else if left_help.len() + right_help.len() > available_width {
-            let overflow_len = left_help.len() + right_help.len() - available_width;
-            right_help = &right_help[..right_help.len() - overflow_len];
-        }
+        let left_help = messages.help(left_help);
+        let right_help = messages.help(right_help);
+        let left_width = left_help.chars().count();
+        let (left_help, right_help) = match left_width > available_width {
+            true => (truncate_chars(&left_help, available_width), ""),
+            false => (left_help.as_str(), truncate_chars(&right_help, available_width - left_width)),
+        };
 
-        let spacer_len = 1 + available_width - left_help.len() - right_help.len();
-        self.buf.extend_from_slice(left_help);
+        let spacer_len = 1 + available_width - left_help.chars().count() - right_help.chars().count();
+        self.help(left_help, background_color, &disabled);
         self.buf.extend(std::iter::repeat(b' ').take(spacer_len));
-        self.buf.extend_from_slice(right_help);
+        self.help(right_help, background_color, &disabled);
 
+        if inverse {
+            set_inverse(&mut self.buf, false);
+        }
         move_cursor_to_next_line(&mut self.buf);
 
         set_background_color(&mut self.buf, Color::Black);
         set_foreground_color(&mut self.buf, Color::White);
     }
 
+    // draws each "[key]action" entry, graying out the disabled ones
+    fn help<F>(&mut self, help: &str, foreground_color: Color, disabled: &F)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut entries = help.split_inclusive(' ').peekable();
+        while let Some(entry) = entries.next() {
+            let label = entry.strip_prefix('[').and_then(|e| e.split_once(']')).map(|(label, _)| label);
+            if label.map(disabled).unwrap_or(false) {
+                set_foreground_color(&mut self.buf, Color::DarkGray);
+                // without colors the brackets tell the disabled keys apart, as "(s)status"
+                match is_plain() {
+                    true => self.buf.extend_from_slice(entry.replacen('[', "(", 1).replacen(']', ")", 1).as_bytes()),
+                    false => self.buf.extend_from_slice(entry.as_bytes()),
+                }
+                // actions can span several words, e.g. "[R]reset to remote"
+                while let Some(word) = entries.next_if(|w| !w.starts_with('[')) {
+                    self.buf.extend_from_slice(word.as_bytes());
+                }
+                set_foreground_color(&mut self.buf, foreground_color);
+            } else {
+                self.buf.extend_from_slice(entry.as_bytes());
+            }
+        }
+    }
+
     pub fn str(&mut self, line: &str) {
         self.buf.extend_from_slice(line.as_bytes());
     }
 
+    // same as str() but with the issue references underlined
+    pub fn message_line(&mut self, line: &str) {
+        let issue_references = tool::issue_references(line, &self.config.issue_patterns());
+        let mut last = 0;
+        for (begin, end) in issue_references {
+            self.buf.extend_from_slice(&line.as_bytes()[last..begin]);
+            set_underline(&mut self.buf, true);
+            self.buf.extend_from_slice(&line.as_bytes()[begin..end]);
+            set_underline(&mut self.buf, false);
+            last = end;
+        }
+        self.buf.extend_from_slice(&line.as_bytes()[last..]);
+    }
+
+    // the marker column of lists whose entries can be selected
+    pub fn selected_marker(&mut self, selected: bool) {
+        self.buf.extend_from_slice(if selected { b"+ " } else { b"  " });
+    }
+
+    // a horizontal bar scaled so that max fills the width left after the label and the value
+    pub fn bar(&mut self, label: &str, value: usize, max: usize) {
+        let text = format!("{} {:>6} ", label, value);
+        let available_width = (self.viewport_size.0 as usize).saturating_sub(text.chars().count() + 1);
+        // rounded up so that a single commit still shows
+        let len = match max {
+            0 => 0,
+            _ => (value * available_width).div_ceil(max),
+        };
+
+        self.buf.extend_from_slice(text.as_bytes());
+        set_foreground_color(&mut self.buf, Color::DarkYellow);
+        for _ in 0..len {
+            self.buf.extend_from_slice(glyph("█", "#").as_bytes());
+        }
+        set_foreground_color(&mut self.buf, Color::White);
+    }
+
     pub fn fmt(&mut self, args: fmt::Arguments) {
         use std::io::Write;
         self.buf.write_fmt(args).unwrap();
     }
 
+    // lines are counted from the top of the terminal, the header included
+    pub fn move_to_line(&mut self, line: usize) {
+        self.fmt(format_args!("\x1b[{};1H", line + 1));
+    }
+
     pub fn next_line(&mut self) {
         clear_until_new_line(&mut self.buf);
         move_cursor_to_next_line(&mut self.buf);
@@ -285,17 +473,111 @@ impl Drawer {
         line_count
     }
 
-    pub fn diff_format(&mut self, output: &Output) -> usize {
+    // file content with line numbers, long lines are cut at the viewport width
+    pub fn source(&mut self, output: &Output, syntax: Option<&Syntax>) -> usize {
+        let line_number_width = output.line_count().to_string().len();
+        let available_width = (self.viewport_size.0 as usize).saturating_sub(line_number_width + 1);
+        let max_line_count = (self.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT);
+
+        set_background_color(&mut self.buf, Color::Black);
+
+        let mut in_block_comment = false;
+        let mut line_count = 0;
+        for (i, line) in output.text().lines().enumerate() {
+            // lines above the scroll are still tokenized to know where block comments are
+            let tokens = match syntax {
+                Some(syntax) => syntax.tokenize(line, &mut in_block_comment),
+                None => vec![(TokenKind::Text, line)],
+            };
+            if i < output.scroll() {
+                continue;
+            }
+
+            self.fmt(format_args!("{}{:>width$} ", Color::DarkGray, i + 1, width = line_number_width));
+            let mut x = 0;
+            'tokens: for (kind, text) in tokens {
+                let color = match kind {
+                    TokenKind::Text => Color::White,
+                    TokenKind::Keyword => Color::DarkYellow,
+                    TokenKind::String => Color::DarkGreen,
+                    TokenKind::Number => Color::DarkMagenta,
+                    TokenKind::Comment => Color::DarkBlue,
+                };
+                set_foreground_color(&mut self.buf, color);
+                for c in text.chars() {
+                    let c_width = if c == '\t' { 4 } else { 1 };
+                    if x + c_width > available_width {
+                        break 'tokens;
+                    }
+                    match c {
+                        '\t' => self.buf.extend_from_slice(b"    "),
+                        _ => self.buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                    }
+                    x += c_width;
+                }
+            }
+            self.next_line();
+
+            line_count += 1;
+            if line_count >= max_line_count {
+                break;
+            }
+        }
+
+        set_foreground_color(&mut self.buf, Color::White);
+
+        line_count
+    }
+
+    // the cursor line and the selected lines get a background
+    pub fn diff_format<F>(
+        &mut self,
+        output: &Output,
+        header_height: usize,
+        cursor: Option<usize>,
+        selected: F,
+        searched: &str,
+    ) -> usize
+    where
+        F: Fn(usize) -> bool,
+    {
         let tab_bytes = [b' '; 4];
         let mut utf8_buf = [0; 4];
 
         set_background_color(&mut self.buf, Color::Black);
 
+        // without colors the cursor and the selected lines are marked in a gutter
+        let gutter = is_plain() && cursor.is_some();
+
+        let show_whitespace_errors = self.config.get_bool("diff.whitespace_errors", true);
+        let mut previous_line = output.scroll().checked_sub(1).and_then(|i| output.text().lines().nth(i));
+
         let mut line_count = 0;
-        for line in output.lines_from_scroll() {
+        for (i, line) in output.lines_from_scroll().enumerate() {
             let mut x = 0;
             let mut num_headers = 0;
 
+            let line_index = output.scroll() + i;
+            let background = if cursor == Some(line_index) {
+                Color::DarkMagenta
+            } else if selected(line_index) {
+                Color::DarkGray
+            } else {
+                Color::Black
+            };
+            set_background_color(&mut self.buf, background);
+            if gutter {
+                let marker: &[u8] = if cursor == Some(line_index) {
+                    b"> "
+                } else if selected(line_index) {
+                    b"* "
+                } else {
+                    b"  "
+                };
+                self.buf.extend_from_slice(marker);
+                x += marker.len();
+            }
+
             if line.starts_with(DIFF_FORMAT_FILE_HEADER_LINE) {
                 set_foreground_color(&mut self.buf, Color::DarkYellow);
                 let bytes = '-'.encode_utf8(&mut utf8_buf).as_bytes();
@@ -317,7 +599,16 @@ impl Drawer {
                 }
             }
 
-            for c in line.chars().skip(num_headers) {
+            let text = &line[num_headers..];
+            let mut search_matches = tool::search_matches(text, searched).into_iter().peekable();
+            let whitespace_errors = match show_whitespace_errors && num_headers == 0 {
+                true => whitespace_errors(text, previous_line),
+                false => Vec::new(),
+            };
+            let mut whitespace_errors = whitespace_errors.into_iter().peekable();
+            for (i, c) in text.char_indices() {
+                highlight_search_matches(&mut self.buf, &mut search_matches, i);
+                highlight_whitespace_errors(&mut self.buf, &mut whitespace_errors, i, background);
                 match c {
                     '\t' => {
                         self.buf.extend_from_slice(&tab_bytes);
@@ -335,11 +626,15 @@ impl Drawer {
                     line_count += 1;
                 }
             }
+            highlight_search_matches(&mut self.buf, &mut search_matches, text.len());
+            highlight_whitespace_errors(&mut self.buf, &mut whitespace_errors, text.len(), background);
+            previous_line = Some(line);
 
             self.next_line();
+            set_background_color(&mut self.buf, Color::Black);
 
             line_count += 1;
-            if line_count + 1 >= self.viewport_size.1 as _ {
+            if line_count + 1 + header_height >= self.viewport_size.1 as _ {
                 break;
             }
         }
@@ -350,13 +645,35 @@ impl Drawer {
     }
 
     pub fn output(&mut self, output: &Output) -> usize {
+        self.output_highlighted(output, false, "")
+    }
+
+    // commit messages get their issue references underlined
+    pub fn message(&mut self, output: &Output, searched: &str) -> usize {
+        self.output_highlighted(output, true, searched)
+    }
+
+    fn output_highlighted(&mut self, output: &Output, highlight_issues: bool, searched: &str) -> usize {
         let tab_bytes = [b' '; 4];
         let mut utf8_buf = [0; 4];
+        let issue_patterns = if highlight_issues { self.config.issue_patterns() } else { Vec::new() };
 
         let mut line_count = 0;
         for line in output.lines_from_scroll() {
             let mut x = 0;
-            for c in line.chars() {
+            let mut issue_references = tool::issue_references(line, &issue_patterns).into_iter().peekable();
+            let mut search_matches = tool::search_matches(line, searched).into_iter().peekable();
+            for (i, c) in line.char_indices() {
+                highlight_search_matches(&mut self.buf, &mut search_matches, i);
+                match issue_references.peek() {
+                    Some(&(begin, _)) if begin == i => set_underline(&mut self.buf, true),
+                    Some(&(_, end)) if end == i => {
+                        set_underline(&mut self.buf, false);
+                        issue_references.next();
+                    }
+                    _ => (),
+                }
+
                 match c {
                     '\t' => {
                         self.buf.extend_from_slice(&tab_bytes);
@@ -374,6 +691,10 @@ impl Drawer {
                     line_count += 1;
                 }
             }
+            if issue_references.peek().is_some() {
+                set_underline(&mut self.buf, false);
+            }
+            highlight_search_matches(&mut self.buf, &mut search_matches, line.len());
 
             self.next_line();
 
@@ -391,10 +712,17 @@ impl Drawer {
 
         set_background_color(&mut self.buf, Color::Black);
         set_foreground_color(&mut self.buf, Color::White);
-        self.buf.extend_from_slice(input.as_bytes());
+        let mut lines = input.split('\n');
+        if let Some(line) = lines.next() {
+            self.buf.extend_from_slice(line.as_bytes());
+        }
+        for line in lines {
+            self.next_line();
+            self.buf.extend_from_slice(line.as_bytes());
+        }
 
         set_background_color(&mut self.buf, Color::DarkRed);
-        self.buf.push(b' ');
+        self.buf.push(if is_plain() { b'_' } else { b' ' });
         set_background_color(&mut self.buf, Color::Black);
 
         if input.is_empty() {
@@ -423,7 +751,7 @@ impl Drawer {
 
         if filter.has_focus() {
             set_background_color(&mut self.buf, Color::White);
-            self.buf.push(b' ');
+            self.buf.push(if is_plain() { b'_' } else { b' ' });
             if !trimmed {
                 set_background_color(&mut self.buf, Color::DarkRed);
             }
@@ -436,6 +764,34 @@ impl Drawer {
         1
     }
 
+    // the searched text followed by the current match and the match count
+    pub fn search(&mut self, search: &Search) -> usize {
+        if !search.is_searching() {
+            return 0;
+        }
+
+        set_background_color(&mut self.buf, Color::DarkBlue);
+        set_foreground_color(&mut self.buf, Color::White);
+        self.buf.extend_from_slice(b"search:");
+        self.buf.extend_from_slice(search.as_str().as_bytes());
+        if search.has_focus() {
+            set_background_color(&mut self.buf, Color::White);
+            self.buf.push(if is_plain() { b'_' } else { b' ' });
+            set_background_color(&mut self.buf, Color::DarkBlue);
+        }
+        match search.position() {
+            _ if search.as_str().is_empty() => (),
+            (_, 0) => self.buf.extend_from_slice(b" no matches"),
+            (current, count) => self.fmt(format_args!(" {}/{}", current, count)),
+        }
+
+        self.next_line();
+        set_background_color(&mut self.buf, Color::Black);
+        set_foreground_color(&mut self.buf, Color::White);
+
+        1
+    }
+
     pub fn select_menu<'entries, I, E>(
         &mut self,
         select: &SelectMenu,
@@ -451,6 +807,15 @@ impl Drawer {
         set_background_color(&mut self.buf, Color::Black);
         set_foreground_color(&mut self.buf, Color::White);
 
+        // without colors the hovered entry is marked, the entries are drawn narrower to leave room for it
+        const HOVERED_MARKER_LEN: u16 = 2;
+        let plain = is_plain();
+        let inverse = plain && self.config.get_bool("ui.inverse_video", false);
+        let viewport_width = self.viewport_size.0;
+        if plain {
+            self.viewport_size.0 = viewport_width.saturating_sub(HOVERED_MARKER_LEN);
+        }
+
         let mut line_count = 0;
         let max_line_count = (self.viewport_size.1 as usize).saturating_sub(RESERVED_LINES_COUNT + header_height);
 
@@ -459,10 +824,19 @@ impl Drawer {
             if hovered {
                 set_background_color(&mut self.buf, Color::DarkMagenta);
             }
+            if plain {
+                self.buf.extend_from_slice(if hovered { b"> " } else { b"  " });
+            }
+            if hovered && inverse {
+                set_inverse(&mut self.buf, true);
+            }
 
             line_count += entry.draw(self, hovered, hovered && show_full_hovered_entry);
 
             clear_until_new_line(&mut self.buf);
+            if hovered && inverse {
+                set_inverse(&mut self.buf, false);
+            }
             move_cursor_to_next_line(&mut self.buf);
 
             if hovered {
@@ -473,5 +847,7 @@ impl Drawer {
                 break;
             }
         }
+
+        self.viewport_size.0 = viewport_width;
     }
 }
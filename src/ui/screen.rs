@@ -0,0 +1,343 @@
+use std::io::Write;
+
+// longer gaps between changed cells on a line are jumped over with a cursor move instead of being written again
+const MAX_REWRITTEN_GAP: usize = 6;
+// a changed line ending with at least this many blank cells is cleared to its end instead
+const MIN_CLEARED_LEN: usize = 4;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+struct Style {
+    foreground: Option<u8>,
+    background: Option<u8>,
+    underline: bool,
+    inverse: bool,
+}
+impl Style {
+    // what erasing leaves behind: only the background is kept
+    fn erased(&self) -> Self {
+        Self { background: self.background, ..Self::default() }
+    }
+
+    fn write_code(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"\x1b[0");
+        if let Some(color) = self.foreground {
+            write!(out, ";38;5;{}", color).unwrap();
+        }
+        if let Some(color) = self.background {
+            write!(out, ";48;5;{}", color).unwrap();
+        }
+        if self.underline {
+            out.extend_from_slice(b";4");
+        }
+        if self.inverse {
+            out.extend_from_slice(b";7");
+        }
+        out.push(b'm');
+    }
+}
+
+// the right half of a wide character is a cell with no text
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Cell {
+    text: String,
+    style: Style,
+}
+impl Cell {
+    fn blank(style: Style) -> Self {
+        Self { text: " ".to_owned(), style }
+    }
+
+    fn is_blank(&self) -> bool {
+        self.text == " " && self.style == self.style.erased()
+    }
+}
+
+// east asian wide characters and emojis take two columns, combining marks none
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+// the cells of the terminal, updated by playing the bytes the drawer writes
+// only the cells that changed since the previous frame are then sent to the terminal
+#[derive(Default)]
+pub struct Screen {
+    size: (usize, usize), // columns, lines
+    cells: Vec<Cell>,
+    shown: Option<Vec<Cell>>,   // what the terminal shows, none when it has to be redrawn entirely
+    shown_style: Option<Style>, // the style the terminal writes with, none when not known
+    cursor: (usize, usize),
+    pending_wrap: bool, // the last column was written, the next character goes on the next line
+    style: Style,
+}
+
+impl Screen {
+    // the whole screen is sent again on the next update, like after the editor used the terminal
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+        self.shown_style = None;
+    }
+
+    // plays a frame drawn by the drawer, then writes to out what changed on the terminal since the previous one
+    pub fn update(&mut self, frame: &[u8], viewport_size: (u16, u16), out: &mut Vec<u8>) {
+        out.clear();
+        let size = (viewport_size.0.max(1) as usize, viewport_size.1.max(1) as usize);
+        if size != self.size {
+            self.size = size;
+            self.cells = vec![Cell::blank(Style::default()); size.0 * size.1];
+            self.shown = None;
+        }
+
+        self.play(frame);
+        self.write_changes(out);
+    }
+
+    // each line with its trailing spaces trimmed, without the empty lines at the bottom
+    pub fn text(&self) -> String {
+        let lines: Vec<_> = self
+            .cells
+            .chunks(self.size.0.max(1))
+            .map(|line| line.iter().map(|c| c.text.as_str()).collect::<String>().trim_end().to_owned())
+            .collect();
+        lines.join("\n").trim_end().to_owned()
+    }
+
+    fn play(&mut self, frame: &[u8]) {
+        let text = String::from_utf8_lossy(frame);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut parameters = String::new();
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                self.control(&parameters, c);
+                                break;
+                            }
+                            parameters.push(c);
+                        }
+                    }
+                    // operating system commands like the title or the clipboard end with a bell or an escaped backslash
+                    Some(']') | Some('P') => {
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                                break;
+                            }
+                        }
+                    }
+                    _ => (),
+                },
+                '\r' => self.move_to(self.cursor.1, 0),
+                '\n' => self.line_feed(),
+                '\t' => self.move_to(self.cursor.1, ((self.cursor.0 / 8 + 1) * 8).min(self.size.0 - 1)),
+                '\x08' => self.move_to(self.cursor.1, self.cursor.0.saturating_sub(1)),
+                c if c.is_control() => (),
+                c => self.print(c),
+            }
+        }
+    }
+
+    fn control(&mut self, parameters: &str, command: char) {
+        let numbers: Vec<usize> = parameters.split(';').map(|n| n.parse().unwrap_or(0)).collect();
+        let number = |i: usize, default: usize| numbers.get(i).copied().filter(|&n| n > 0).unwrap_or(default);
+        match command {
+            'H' => self.move_to(number(0, 1) - 1, number(1, 1) - 1),
+            'E' => self.move_to(self.cursor.1 + number(0, 1), 0),
+            // a full line keeps its last character, as most terminals do when waiting to wrap
+            'K' => {
+                let begin = self.index(self.cursor.1, self.cursor.0) + self.pending_wrap as usize;
+                let end = self.index(self.cursor.1, 0) + self.size.0;
+                self.erase(begin, end);
+            }
+            'J' => {
+                let begin = self.index(self.cursor.1, self.cursor.0) + self.pending_wrap as usize;
+                self.erase(begin, self.cells.len());
+            }
+            'm' => self.select_graphic_rendition(&numbers),
+            _ => (),
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, numbers: &[usize]) {
+        let mut numbers = numbers.iter().copied();
+        while let Some(number) = numbers.next() {
+            match number {
+                0 => self.style = Style::default(),
+                4 => self.style.underline = true,
+                24 => self.style.underline = false,
+                7 => self.style.inverse = true,
+                27 => self.style.inverse = false,
+                39 => self.style.foreground = None,
+                49 => self.style.background = None,
+                38 | 48 => {
+                    let color = match (numbers.next(), numbers.next()) {
+                        (Some(5), Some(color)) => Some(color as u8),
+                        _ => None,
+                    };
+                    match number {
+                        38 => self.style.foreground = color,
+                        _ => self.style.background = color,
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn index(&self, line: usize, column: usize) -> usize {
+        line * self.size.0 + column.min(self.size.0 - 1)
+    }
+
+    fn move_to(&mut self, line: usize, column: usize) {
+        self.cursor = (column.min(self.size.0 - 1), line.min(self.size.1 - 1));
+        self.pending_wrap = false;
+    }
+
+    fn erase(&mut self, begin: usize, end: usize) {
+        let blank = Cell::blank(self.style.erased());
+        for cell in &mut self.cells[begin..end] {
+            *cell = blank.clone();
+        }
+        self.pending_wrap = false;
+    }
+
+    // the terminal scrolls up when going below the last line
+    fn line_feed(&mut self) {
+        if self.cursor.1 + 1 < self.size.1 {
+            self.cursor.1 += 1;
+        } else {
+            self.cells.drain(..self.size.0);
+            self.cells.resize(self.size.0 * self.size.1, Cell::blank(self.style.erased()));
+        }
+        self.pending_wrap = false;
+    }
+
+    fn print(&mut self, c: char) {
+        let width = char_width(c);
+        if width == 0 {
+            // combining marks join the character before them
+            let index = self.index(self.cursor.1, self.cursor.0.saturating_sub(!self.pending_wrap as usize));
+            self.cells[index].text.push(c);
+            return;
+        }
+
+        if self.pending_wrap || self.cursor.0 + width > self.size.0 {
+            self.cursor.0 = 0;
+            self.line_feed();
+        }
+
+        let index = self.index(self.cursor.1, self.cursor.0);
+        // a wide character half overwritten loses its other half
+        if self.cells[index].text.is_empty() && self.cursor.0 > 0 {
+            self.cells[index - 1] = Cell::blank(self.cells[index - 1].style.erased());
+        }
+        let next = index + width;
+        if self.cursor.0 + width < self.size.0 && self.cells[next].text.is_empty() {
+            self.cells[next] = Cell::blank(self.cells[next].style.erased());
+        }
+
+        self.cells[index] = Cell { text: c.to_string(), style: self.style };
+        if width == 2 {
+            self.cells[index + 1] = Cell { text: String::new(), style: self.style };
+        }
+
+        if self.cursor.0 + width < self.size.0 {
+            self.cursor.0 += width;
+        } else {
+            self.cursor.0 = self.size.0 - 1;
+            self.pending_wrap = true;
+        }
+    }
+
+    fn write_changes(&mut self, out: &mut Vec<u8>) {
+        let width = self.size.0;
+        let shown = match self.shown.take() {
+            Some(shown) => shown,
+            None => {
+                // from a cleared screen, only what is not blank has to be written
+                out.extend_from_slice(b"\x1b[0m\x1b[H\x1b[2J");
+                self.shown_style = Some(Style::default());
+                vec![Cell::blank(Style::default()); self.cells.len()]
+            }
+        };
+
+        let mut style = self.shown_style;
+        let mut cursor = None; // where the terminal cursor is, none when not known
+        for (line_index, (line, shown_line)) in self.cells.chunks(width).zip(shown.chunks(width)).enumerate() {
+            let changed: Vec<_> = (0..width).filter(|&i| line[i] != shown_line[i]).collect();
+            let (first, last) = match (changed.first(), changed.last()) {
+                (Some(&first), Some(&last)) => (first, last),
+                _ => continue,
+            };
+
+            // a blank end of line with a single style is cleared at once
+            let blank_begin =
+                (0..width).rev().take_while(|&i| line[i].is_blank() && line[i].style == line[width - 1].style).last();
+            let clear_from = blank_begin.filter(|&b| b <= last && width - b >= MIN_CLEARED_LEN);
+            let write_end = clear_from.unwrap_or(last + 1);
+
+            let mut column = first;
+            while column < write_end {
+                if line[column] == shown_line[column] {
+                    // written again when it is cheaper than jumping over it
+                    let gap_end = (column..write_end).find(|&i| line[i] != shown_line[i]).unwrap_or(write_end);
+                    if cursor != Some((column, line_index)) || gap_end - column > MAX_REWRITTEN_GAP {
+                        column = gap_end;
+                        continue;
+                    }
+                }
+
+                let cell = &line[column];
+                if cell.text.is_empty() {
+                    // the right half of a wide character is written with its left half
+                    column += 1;
+                    cursor = None;
+                    continue;
+                }
+                if cursor != Some((column, line_index)) {
+                    write!(out, "\x1b[{};{}H", line_index + 1, column + 1).unwrap();
+                }
+                if style != Some(cell.style) {
+                    cell.style.write_code(out);
+                    style = Some(cell.style);
+                }
+                out.extend_from_slice(cell.text.as_bytes());
+
+                let cell_width = if column + 1 < width && line[column + 1].text.is_empty() { 2 } else { 1 };
+                column += cell_width;
+                // past the last column the cursor waits to wrap, a move is needed anyway
+                cursor = if column < width { Some((column, line_index)) } else { None };
+            }
+
+            if let Some(clear_from) = clear_from {
+                if cursor != Some((clear_from, line_index)) {
+                    write!(out, "\x1b[{};{}H", line_index + 1, clear_from + 1).unwrap();
+                }
+                let clear_style = line[clear_from].style;
+                if style != Some(clear_style) {
+                    clear_style.write_code(out);
+                    style = Some(clear_style);
+                }
+                out.extend_from_slice(b"\x1b[0K");
+                cursor = Some((clear_from, line_index));
+            }
+        }
+
+        self.shown = Some(self.cells.clone());
+        self.shown_style = style;
+    }
+}
@@ -0,0 +1,989 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gituse::{
+    application::headless::Headless,
+    backend::{
+        mock::MockBackend, BranchEntry, FileStatus, LogEntry, Operation, RevisionEntry, RevisionInfo, SetupInfo, StatusInfo,
+    },
+    config::Config,
+    mode::{ListSession, ModeChangeInfo, ModeKind},
+    platform::Key,
+};
+
+// a header line, the keys line and the five entries of a log page
+const VIEWPORT_SIZE: (u16, u16) = (80, 8);
+const LOG_PAGE_LEN: usize = 5;
+
+fn log_entries(range: std::ops::Range<usize>) -> Vec<LogEntry> {
+    range
+        .map(|i| LogEntry {
+            graph: "*".to_owned(),
+            hash: format!("{:07}", i),
+            short_hash: format!("{:07}", i),
+            date: "2024-01-01".to_owned(),
+            author: "author".to_owned(),
+            refs: String::new(),
+            message: format!("commit {}", i),
+            ci_status: None,
+            shallow: false,
+            note: false,
+            describe: None,
+            full_message: None,
+            all_refs: None,
+        })
+        .collect()
+}
+
+fn start(backend: &Arc<MockBackend>, mut config: Config, mode_kind: ModeKind, info: ModeChangeInfo) -> Headless {
+    // prefetching depends on how long the test takes, the tests checking it turn it back on
+    if config.get("log.prefetch").is_none() {
+        config.set("log.prefetch", "false");
+    }
+    Headless::new(backend.clone(), config, VIEWPORT_SIZE, (mode_kind, info))
+}
+
+fn start_log(backend: &Arc<MockBackend>, config: Config) -> Headless {
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    start(backend, config, ModeKind::Log, ModeChangeInfo::new(ModeKind::Log))
+}
+
+#[test]
+fn log_session_restores_a_commit_further_than_the_first_page() {
+    let backend = Arc::new(MockBackend::new(false));
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    backend.script("log", Ok((LOG_PAGE_LEN, log_entries(LOG_PAGE_LEN..10))));
+    let session = ListSession { hovered: "0000007".to_owned(), scroll: 6, filter: String::new() };
+    let info = ModeChangeInfo::session(ModeKind::Log, session);
+    let mut headless = start(&backend, Config::default(), ModeKind::Log, info);
+
+    let screen = headless.screen();
+    assert!(screen.contains("commit 7"));
+    assert!(!screen.contains("commit 0"));
+}
+
+#[test]
+fn log_requests_the_next_page_at_the_last_entry() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    assert!(headless.screen().contains("0000004 2024-01-01 author commit 4"));
+
+    backend.script("log", Ok((LOG_PAGE_LEN, log_entries(LOG_PAGE_LEN..7))));
+    headless.keys(&[Key::Down; LOG_PAGE_LEN - 1]);
+    let page_calls: Vec<_> = backend.calls().into_iter().filter(|c| c.starts_with("log ")).collect();
+    assert_eq!(page_calls, ["log 0 5 all commits", "log 5 5 all commits"]);
+
+    // the last entry asks for more again, an empty page being the end of the history
+    backend.script("log", Ok((7usize, Vec::<LogEntry>::new())));
+    headless.keys(&[Key::Down, Key::Down]);
+    let screen = headless.screen();
+    assert!(screen.contains("commit 6"));
+    assert!(!screen.contains("commit 1"));
+}
+
+#[test]
+fn log_follow_refreshes_until_toggled_off() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("log.follow_interval", "1");
+    let mut headless = start_log(&backend, config);
+    let log_calls = |backend: &MockBackend| backend.calls().iter().filter(|c| c.starts_with("log 0 ")).count();
+
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Char('W'));
+    assert!(headless.screen().contains("log (following)"));
+    assert_eq!(log_calls(&backend), 2);
+
+    // however late the timer thread gets to run
+    assert!(headless.wait_until(Duration::from_secs(10), || log_calls(&backend) >= 3));
+
+    headless.key(Key::Char('W'));
+    let calls = log_calls(&backend);
+    headless.wait(Duration::from_millis(1200));
+    assert_eq!(log_calls(&backend), calls);
+    assert!(!headless.screen().contains("following"));
+}
+
+#[test]
+fn log_range_lists_its_commits_and_their_combined_diff() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+
+    headless.keys(&[Key::Down, Key::Char('v'), Key::Down, Key::Down]);
+    assert!(headless.screen().contains("range from 0000001"));
+
+    backend.script("log", Ok((0usize, log_entries(1..4))));
+    headless.key(Key::Char('v'));
+    assert!(headless.screen().contains("commit range"));
+    assert!(backend.calls().contains(&"log 0 5 range: 0000003~..0000001".to_owned()));
+
+    backend.script("diff", Ok(String::new()));
+    headless.key(Key::Char('d'));
+    assert_eq!(headless.mode_kind(), ModeKind::Diff);
+    assert_eq!(backend.calls().last().unwrap(), "diff 0000003~..0000001");
+
+    // back to the range, then to the whole log
+    headless.keys(&[Key::Left, Key::Left]);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(!headless.screen().contains("commit range"));
+}
+
+#[test]
+fn log_asks_before_removing_a_note() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+
+    headless.key(Key::Char('D'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(headless.screen().contains("remove the note of 0000000"));
+    headless.key(Key::Char('n'));
+    assert!(!backend.calls().iter().any(|c| c.starts_with("remove_note")));
+
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.keys(&[Key::Char('D'), Key::Char('y')]);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(backend.calls().contains(&"remove_note 0000000".to_owned()));
+}
+
+#[test]
+fn log_range_squash_asks_before_rewriting_pushed_commits() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    backend.script("log", Ok((0usize, log_entries(1..3))));
+    headless.keys(&[Key::Down, Key::Char('v'), Key::Down, Key::Char('v')]);
+
+    backend.script("squash_preview", Ok(("commit 2\n\ncommit 1".to_owned(), 1usize)));
+    headless.key(Key::Char('Q'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(headless.screen().contains("1 of these commits are already pushed"));
+    headless.key(Key::Char('n'));
+    assert!(!backend.calls().iter().any(|c| c.starts_with("squash ")));
+
+    backend.script("squash_preview", Ok(("commit 2\n\ncommit 1".to_owned(), 1usize)));
+    headless.keys(&[Key::Char('Q'), Key::Char('y')]);
+    assert_eq!(headless.mode_kind(), ModeKind::MessageInput);
+
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(backend.calls().contains(&"squash 0000002~..0000001 commit 2\n\ncommit 1".to_owned()));
+    assert!(!headless.screen().contains("commit range"));
+}
+
+#[test]
+fn log_reset_to_remote_previews_the_discarded_commits_and_stashes_first() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+
+    // tall enough for the whole preview
+    headless.resize((80, 12));
+    backend.script("reset_preview", Ok(("0000000 commit 0\n".to_owned(), true)));
+    headless.key(Key::Char('R'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    let screen = headless.screen();
+    assert!(screen.contains("0000000 commit 0"));
+    assert!(screen.contains("the uncommitted changes are stashed first"));
+
+    backend.script("log", Ok((0usize, log_entries(1..LOG_PAGE_LEN))));
+    headless.key(Key::Char('y'));
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    let calls = backend.calls();
+    let stash_index = calls.iter().position(|c| c.starts_with("stash_all")).unwrap();
+    assert_eq!(calls[stash_index + 1], "reset --hard");
+}
+
+#[test]
+fn log_reset_asks_for_the_mode_and_defaults_to_the_configured_one() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    headless.resize((80, 12));
+
+    headless.key(Key::Char('r'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    let screen = headless.screen();
+    assert!(screen.contains("soft, mixed or hard? [s/m/h/n]"));
+    assert!(screen.contains("mixed: they stay in the working tree, unstaged"));
+
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(backend.calls().contains(&"reset --mixed 0000000".to_owned()));
+
+    let mut config = Config::default();
+    config.set("reset.mode", "soft");
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, config);
+    headless.key(Key::Char('r'));
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Enter);
+    assert!(backend.calls().contains(&"reset --soft 0000000".to_owned()));
+}
+
+#[test]
+fn log_jumps_to_the_first_commit_at_or_before_a_date() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+
+    backend.script("first_commit_until", Ok(None::<String>));
+    headless.key(Key::Char('J'));
+    assert_eq!(headless.mode_kind(), ModeKind::MessageInput);
+    headless.type_text("1999-01-01");
+    headless.key(Key::Enter);
+    assert!(headless.screen().contains("no commit at or before '1999-01-01'"));
+
+    // the commit is past the loaded entries, the log continues from them until it
+    backend.script("first_commit_until", Ok(Some("0000007".to_owned())));
+    backend.script("log", Ok((LOG_PAGE_LEN, log_entries(LOG_PAGE_LEN..10))));
+    headless.key(Key::Char('J'));
+    headless.type_text("last march");
+    headless.key(Key::Enter);
+    let calls = backend.calls();
+    assert!(calls.contains(&"first_commit_until last march all commits".to_owned()));
+    assert!(calls.contains(&"log 5 500 all commits".to_owned()));
+    let screen = headless.screen();
+    assert!(!screen.contains("no commit"));
+    // scrolled to the top of the list
+    assert!(screen.lines().nth(2).unwrap().contains("0000007 2024-01-01 author commit 7"));
+}
+
+#[test]
+fn log_exports_the_hovered_commit_to_an_archive() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    let project = std::env::current_dir().unwrap().file_name().unwrap().to_string_lossy().into_owned();
+
+    // the path is offered after the project and the commit, then the prefix is replaced
+    headless.key(Key::Down);
+    headless.key(Key::Char('Z'));
+    assert!(headless.screen().contains(&format!("{}-0000001.tar.gz", project)));
+    headless.key(Key::Enter);
+    assert!(headless.screen().contains(&format!("export 0000001 to {}-0000001.tar.gz under", project)));
+    headless.key(Key::Ctrl('u'));
+    headless.type_text("release");
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(backend.calls().contains(&format!("archive 0000001 {}-0000001.tar.gz release", project)));
+
+    // the directory follows the name of the archive typed in
+    backend.script("archive", Err::<(), _>("fatal: not a valid object name".to_owned()));
+    headless.keys(&[Key::Char('Z'), Key::Ctrl('u')]);
+    headless.type_text("snapshot.zip");
+    headless.keys(&[Key::Enter, Key::Enter]);
+    assert!(backend.calls().contains(&"archive 0000001 snapshot.zip snapshot/".to_owned()));
+    assert!(headless.screen().contains("fatal: not a valid object name"));
+}
+
+#[test]
+fn keys_recorded_are_replayed_once_the_previous_ones_are_done() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    for _ in 0..3 {
+        backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    }
+
+    headless.key(Key::Char('q'));
+    assert!(headless.screen().starts_with("    log | recording"));
+    headless.keys(&[Key::Down, Key::Char('N')]);
+    headless.type_text("reviewed");
+    headless.keys(&[Key::Enter, Key::Char('q')]);
+    assert!(!headless.screen().contains("recording"));
+
+    // each replay waits for the note to be set and the log to refresh before moving on
+    headless.keys(&[Key::Char('@'), Key::Char('@')]);
+    let notes: Vec<_> = backend.calls().into_iter().filter(|c| c.starts_with("set_note")).collect();
+    assert_eq!(notes, ["set_note 0000001 \"reviewed\"", "set_note 0000002 \"reviewed\"", "set_note 0000003 \"reviewed\""]);
+}
+
+#[test]
+fn log_toggles_first_parent_and_merges_only() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    let last_log_call = |backend: &MockBackend| backend.calls().into_iter().rfind(|c| c.starts_with("log "));
+
+    backend.script("log", Ok((0usize, log_entries(0..2))));
+    headless.key(Key::Char('1'));
+    assert_eq!(last_log_call(&backend).unwrap(), "log 0 5 first parent");
+    assert!(headless.screen().starts_with("    first-parent log"));
+
+    backend.script("log", Ok((0usize, log_entries(0..2))));
+    headless.key(Key::Char('e'));
+    assert_eq!(last_log_call(&backend).unwrap(), "log 0 5 first parent, merges only");
+    assert!(headless.screen().starts_with("    merges"));
+
+    backend.script("log", Ok((0usize, log_entries(0..2))));
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.keys(&[Key::Char('1'), Key::Char('e')]);
+    assert_eq!(last_log_call(&backend).unwrap(), "log 0 5 no merges");
+}
+
+#[test]
+fn log_leaves_out_the_excluded_refs_until_asked_for_them() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("log.decorate_exclude", "remotes/*, tags");
+    let mut headless = start_log(&backend, config);
+    assert_eq!(backend.calls()[0], "log 0 5 all commits excluding remotes/*,tags");
+
+    backend.script("refs", Ok("origin/main, tag: v1".to_owned()));
+    headless.key(Key::Char('E'));
+    assert!(backend.calls().contains(&"refs 0000000".to_owned()));
+    assert!(headless.screen().contains("0000000 2024-01-01 author (origin/main, tag: v1) commit 0"));
+
+    headless.key(Key::Char('E'));
+    assert!(headless.screen().contains("0000000 2024-01-01 author commit 0"));
+}
+
+#[test]
+fn log_preview_shows_the_full_message_of_the_hovered_commit() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    // the bottom third is tall enough for the separator, the metadata and two lines of message
+    headless.resize((80, 17));
+
+    let details = |message: &str| RevisionInfo {
+        date: String::new(),
+        message: message.to_owned(),
+        notes: String::new(),
+        entries: Vec::new(),
+    };
+    backend.script("revision_details", Ok(details("commit 0\nwith a body")));
+    headless.key(Key::Char('o'));
+    assert!(headless.screen().contains("with a body"));
+    assert_eq!(backend.calls().last().unwrap(), "revision_details 0000000");
+
+    backend.script("revision_details", Ok(details("commit 1\nanother body")));
+    headless.key(Key::Down);
+    let screen = headless.screen();
+    assert!(screen.contains("another body"));
+    assert!(!screen.contains("with a body"));
+
+    headless.key(Key::Char('o'));
+    assert!(!headless.screen().contains("another body"));
+}
+
+#[test]
+fn log_prefetches_the_details_of_the_commit_hovered_for_a_moment() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("log.prefetch", "true");
+    let mut headless = start_log(&backend, config);
+    let details_calls = |backend: &MockBackend| backend.calls().into_iter().filter(|c| c.starts_with("revision_details"));
+
+    let info =
+        RevisionInfo { date: String::new(), message: "commit 2".to_owned(), notes: String::new(), entries: Vec::new() };
+    // the commit passed over is not fetched
+    backend.script("revision_details", Ok(info));
+    headless.keys(&[Key::Down, Key::Down]);
+    headless.wait(Duration::from_millis(500));
+    assert_eq!(details_calls(&backend).collect::<Vec<_>>(), ["revision_details 0000002"]);
+
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::RevisionDetails);
+    assert!(headless.screen().contains("commit 2"));
+    assert_eq!(details_calls(&backend).count(), 1);
+}
+
+#[test]
+fn log_columns_follow_the_config() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("log.columns", "message, hash:4, unknown");
+    let mut headless = start_log(&backend, config);
+
+    let screen = headless.screen();
+    assert!(screen.contains("\ncommit 0 0000\ncommit 1 0000\n"));
+    assert!(!screen.contains("author"));
+}
+
+#[test]
+fn log_shows_abbreviated_hashes_until_toggled_but_checks_out_the_full_one() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut entries = log_entries(0..LOG_PAGE_LEN);
+    entries[0].hash = "0123456789abcdef0123456789abcdef01234567".to_owned();
+    entries[0].short_hash = "0123456789".to_owned();
+    backend.script("log", Ok((0usize, entries)));
+    let mut headless = start(&backend, Config::default(), ModeKind::Log, ModeChangeInfo::new(ModeKind::Log));
+    assert!(headless.screen().contains("* 0123456789 2024-01-01"));
+
+    headless.key(Key::Char('#'));
+    assert!(headless.screen().contains("* 0123456789abcdef0123456789abcdef01234567 2024-01-01"));
+
+    headless.keys(&[Key::Char('#'), Key::Char('c')]);
+    assert!(backend.calls().contains(&"checkout 0123456789abcdef0123456789abcdef01234567".to_owned()));
+}
+
+#[test]
+fn revision_details_show_the_date_before_the_subject() {
+    let backend = Arc::new(MockBackend::new(false));
+    let info = RevisionInfo {
+        date: "2024-01-01 12:30".to_owned(),
+        message: "subject\n\nbody".to_owned(),
+        notes: String::new(),
+        entries: Vec::new(),
+    };
+    backend.script("revision_details", Ok(info));
+    let info = ModeChangeInfo::revision(ModeKind::Log, "abc".to_owned());
+    let mut headless = start(&backend, Config::default(), ModeKind::RevisionDetails, info);
+    assert!(headless.screen().contains("\n2024-01-01 12:30 subject"));
+}
+
+#[test]
+fn revision_details_search_expands_the_message_and_hovers_files() {
+    let backend = Arc::new(MockBackend::new(false));
+    let entries = ["a.txt", "b.txt", "c.txt"].iter().map(|n| RevisionEntry::new(n.to_string(), FileStatus::Modified));
+    let info = RevisionInfo {
+        date: String::new(),
+        message: "subject\n\nthe body".to_owned(),
+        notes: String::new(),
+        entries: entries.collect(),
+    };
+    backend.script("revision_details", Ok(info));
+    let info = ModeChangeInfo::revision(ModeKind::Log, "abc".to_owned());
+    let mut headless = start(&backend, Config::default(), ModeKind::RevisionDetails, info);
+    assert!(!headless.screen().contains("the body"));
+
+    headless.key(Key::Char('/'));
+    headless.type_text("BODY");
+    assert!(headless.screen().contains("the body"));
+
+    headless.keys(&[Key::Enter, Key::Char('/')]);
+    headless.type_text("c.t");
+    headless.keys(&[Key::Enter, Key::Char('v')]);
+    assert!(backend.calls().contains(&"file_content abc c.txt".to_owned()));
+}
+
+#[test]
+fn log_shows_backend_errors() {
+    let backend = Arc::new(MockBackend::new(false));
+    backend.script::<(usize, Vec<LogEntry>)>("log", Err("fatal: your current branch has no commits yet".to_owned()));
+    let mut headless = start(&backend, Config::default(), ModeKind::Log, ModeChangeInfo::new(ModeKind::Log));
+    assert!(headless.screen().contains("fatal: your current branch has no commits yet"));
+}
+
+#[test]
+fn diff_is_split_into_files_and_hunks() {
+    let backend = Arc::new(MockBackend::new(false));
+    let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -10,2 +10,2 @@ fn a() {
+ fn a() {
+-    old();
++    new();
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-x
++y
+";
+    backend.script("diff", Ok(diff.to_owned()));
+    let info = ModeChangeInfo::diff(ModeKind::Log, Some("abc".to_owned()));
+    let mut headless = Headless::new(backend.clone(), Config::default(), (80, 24), (ModeKind::Diff, info));
+    assert_eq!(backend.calls(), ["diff abc"]);
+
+    let screen = headless.screen();
+    let body: Vec<_> = screen.lines().skip(2).filter(|l| !l.starts_with("---")).collect();
+    assert_eq!(
+        body,
+        [
+            "Modified: src/a.rs",
+            "@--- src/a.rs:Line 10 ---@",
+            "fn a() {",
+            " fn a() {",
+            "-    old();",
+            "+    new();",
+            "Modified: b.txt",
+            "@--- b.txt:Line 1 ---@",
+            "-x",
+            "+y",
+        ]
+    );
+}
+
+#[test]
+fn diff_sums_up_submodules_with_the_subjects_of_their_commits() {
+    let backend = Arc::new(MockBackend::new(false));
+    let diff = "\
+Submodule lib/sub module eee54bf..e663c80:
+  > sub third
+  > sub second
+Submodule other 0000000...1234567 (new submodule)
+Submodule dirty contains modified content
+diff --git a/x.txt b/x.txt
+--- a/x.txt
++++ b/x.txt
+@@ -1 +1 @@
+-x
++y
+";
+    backend.script("diff", Ok(diff.to_owned()));
+    let info = ModeChangeInfo::diff(ModeKind::Log, Some("abc".to_owned()));
+    let mut headless = Headless::new(backend.clone(), Config::default(), (80, 24), (ModeKind::Diff, info));
+
+    let screen = headless.screen();
+    let body: Vec<_> = screen.lines().skip(2).filter(|l| !l.starts_with("---")).collect();
+    assert_eq!(
+        body,
+        [
+            "Submodule lib/sub module: eee54bf -> e663c80",
+            "  > sub third",
+            "  > sub second",
+            "Submodule other: 0000000 -> 1234567 (new submodule)",
+            "Submodule dirty: contains modified content",
+            "Modified: x.txt",
+            "@--- x.txt:Line 1 ---@",
+            "-x",
+            "+y",
+        ]
+    );
+}
+
+#[test]
+fn diff_stages_only_changed_lines_and_says_so() {
+    let backend = Arc::new(MockBackend::new(false));
+    let diff = "\
+diff --git a/x.txt b/x.txt
+--- a/x.txt
++++ b/x.txt
+@@ -1 +1 @@
+-x
++y
+";
+    backend.script("diff", Ok(diff.to_owned()));
+    let info = ModeChangeInfo::diff(ModeKind::Status, None);
+    let mut headless = Headless::new(backend.clone(), Config::default(), (80, 24), (ModeKind::Diff, info));
+
+    // the cursor starts on the file header
+    headless.key(Key::Char('a'));
+    assert!(!backend.calls().iter().any(|c| c.starts_with("stage_patch")));
+    assert!(headless.screen().contains("only added or removed lines can be staged"));
+}
+
+#[test]
+fn diff_shows_renames_and_copies_with_their_similarity() {
+    let backend = Arc::new(MockBackend::new(false));
+    let diff = "\
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+diff --git a/a.rs b/b.rs
+similarity index 87%
+copy from a.rs
+copy to b.rs
+index 1111111..2222222 100644
+--- a/a.rs
++++ b/b.rs
+@@ -1 +1 @@
+-a
++b
+";
+    backend.script("diff", Ok(diff.to_owned()));
+    let info = ModeChangeInfo::diff(ModeKind::Log, Some("abc".to_owned()));
+    let mut headless = Headless::new(backend.clone(), Config::default(), (80, 24), (ModeKind::Diff, info));
+
+    let screen = headless.screen();
+    let body: Vec<_> = screen.lines().skip(2).filter(|l| !l.starts_with("---")).collect();
+    assert_eq!(body, ["Renamed: old.rs → new.rs (100%)", "Copied: a.rs → b.rs (87%)", "@--- b.rs:Line 1 ---@", "-a", "+b"]);
+}
+
+#[test]
+fn diff_search_counts_the_matching_lines_and_goes_through_them() {
+    let backend = Arc::new(MockBackend::new(false));
+    let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-old();
++new();
+";
+    backend.script("diff", Ok(diff.to_owned()));
+    let info = ModeChangeInfo::diff(ModeKind::Log, Some("abc".to_owned()));
+    let mut headless = Headless::new(backend.clone(), Config::default(), (80, 24), (ModeKind::Diff, info));
+
+    // the typed keys are text, 's' does not open status
+    headless.key(Key::Char('/'));
+    headless.type_text("SRC/a.rs");
+    assert_eq!(headless.mode_kind(), ModeKind::Diff);
+    assert!(headless.screen().contains("\nsearch:SRC/a.rs  1/2\n"));
+
+    headless.keys(&[Key::Enter, Key::Char('n')]);
+    assert!(headless.screen().contains("\nsearch:SRC/a.rs 2/2\n"));
+    headless.key(Key::Char('n'));
+    assert!(headless.screen().contains("\nsearch:SRC/a.rs 1/2\n"));
+
+    headless.keys(&[Key::Char('/'), Key::Char('x')]);
+    assert!(headless.screen().contains("\nsearch:x  no matches\n"));
+}
+
+#[test]
+fn global_keys_switch_modes_and_left_goes_back() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+
+    let branch = |name: &str, checked_out| BranchEntry { selected: false, name: name.to_owned(), checked_out };
+    backend.script("branches", Ok(vec![branch("main", true), branch("feature", false)]));
+    headless.key(Key::Char('b'));
+    assert_eq!(headless.mode_kind(), ModeKind::Branches);
+    assert!(headless.screen().contains("feature"));
+
+    headless.key(Key::Left);
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(headless.screen().contains("commit 0"));
+}
+
+#[test]
+fn branch_checkout_blocked_by_local_changes_is_offered_around_a_stash() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    let branch = |name: &str, checked_out| BranchEntry { selected: false, name: name.to_owned(), checked_out };
+    backend.script("branches", Ok(vec![branch("main", true), branch("feature", false)]));
+    headless.key(Key::Char('b'));
+
+    let error = "error: Your local changes to the following files would be overwritten by checkout:\n\tsrc/main.rs";
+    backend.script::<()>("checkout", Err(error.to_owned()));
+    headless.keys(&[Key::Up, Key::Enter]);
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(headless.screen().contains("stash the local changes, run the checkout of feature"));
+
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Char('y'));
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    let calls = backend.calls();
+    let stash_index = calls.iter().position(|c| c.starts_with("stash_all")).unwrap();
+    assert_eq!(calls[stash_index + 1..stash_index + 3], ["checkout feature", "stash_pop 0"]);
+}
+
+#[test]
+fn branches_merged_into_the_default_branch_are_deleted_at_once() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    let branch = |name: &str, checked_out| BranchEntry { selected: false, name: name.to_owned(), checked_out };
+    backend.script("branches", Ok(vec![branch("done", false), branch("feature", true), branch("main", false)]));
+    headless.key(Key::Char('b'));
+
+    backend.script("merged_branches", Ok(("main".to_owned(), vec!["done".to_owned(), "gone".to_owned()])));
+    headless.key(Key::Char('x'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(headless.screen().contains("delete the branches merged into main?"));
+
+    backend.script::<()>("delete_branches", Err("Deleted branch done\nerror: branch 'gone' not found".to_owned()));
+    headless.key(Key::Char('y'));
+    assert_eq!(headless.mode_kind(), ModeKind::Branches);
+    assert!(backend.calls().contains(&"delete_branches done,gone --force".to_owned()));
+    assert!(headless.screen().contains("error: branch 'gone' not found"));
+}
+
+#[test]
+fn branches_deleted_from_the_remote_are_confirmed_as_their_upstreams() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    let branch = |name: &str, checked_out| BranchEntry { selected: false, name: name.to_owned(), checked_out };
+    backend.script("branches", Ok(vec![branch("feat", false), branch("main", true)]));
+    headless.key(Key::Char('b'));
+
+    let refs = vec![("upstream".to_owned(), "refs/heads/feature".to_owned())];
+    backend.script("remote_branch_refs", Ok(refs));
+    headless.keys(&[Key::Home, Key::Char('X')]);
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(headless.screen().contains("upstream refs/heads/feature"));
+
+    backend.script("branches", Ok(vec![branch("feat", false), branch("main", true)]));
+    headless.key(Key::Char('y'));
+    assert!(backend.calls().contains(&"delete_remote_branches upstream refs/heads/feature".to_owned()));
+}
+
+#[test]
+fn branches_add_a_remote_named_after_its_owner_and_fetch_it() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    backend.script("branches", Ok(vec![BranchEntry { selected: false, name: "main".to_owned(), checked_out: true }]));
+    headless.key(Key::Char('b'));
+
+    headless.key(Key::Char('R'));
+    assert_eq!(headless.mode_kind(), ModeKind::MessageInput);
+    // whatever the clipboard of the machine running the tests holds
+    headless.key(Key::Ctrl('u'));
+    headless.type_text("git@github.com:alice/project.git");
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::MessageInput);
+    assert!(headless.screen().contains("alice"));
+
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    assert!(backend.calls().contains(&"add_remote alice git@github.com:alice/project.git".to_owned()));
+    assert!(headless.screen().contains("remote 'alice' added, fetch it now?"));
+
+    backend.script("branches", Ok(vec![BranchEntry { selected: false, name: "main".to_owned(), checked_out: true }]));
+    headless.key(Key::Char('y'));
+    assert_eq!(headless.mode_kind(), ModeKind::Branches);
+    assert!(backend.calls().contains(&"fetch_remote alice".to_owned()));
+}
+
+#[test]
+fn patch_series_writes_the_cover_letter_and_sends_the_patches() {
+    let directory = std::env::temp_dir().join(format!("gituse-test-patches-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let cover_letter = directory.join("0000-cover-letter.patch");
+    let patch = directory.join("0001-change.patch");
+    std::fs::write(&cover_letter, "Subject: [PATCH 0/1] *** SUBJECT HERE ***\n\n*** BLURB HERE ***\n").unwrap();
+    std::fs::write(&patch, "Subject: [PATCH 1/1] change\n").unwrap();
+    let paths = vec![cover_letter.to_string_lossy().into_owned(), patch.to_string_lossy().into_owned()];
+
+    let backend = Arc::new(MockBackend::new(false));
+    backend.script("format_patches", Ok(paths));
+    let mut config = Config::default();
+    config.set("patch.directory", &directory.to_string_lossy());
+    config.set("patch.send_command", "send");
+    let info = ModeChangeInfo::patch_series(ModeKind::Log, "a~..b".to_owned());
+    let mut headless = start(&backend, config, ModeKind::PatchSeries, info);
+    assert!(headless.screen().contains("a~..b: 1 patches and a cover letter"));
+    assert!(headless.screen().contains("0000-cover-letter.patch no subject yet"));
+
+    headless.key(Key::Char('e'));
+    headless.type_text("Count the changes");
+    headless.keys(&[Key::Ctrl('j'), Key::Ctrl('j')]);
+    headless.type_text("What they are about.");
+    headless.key(Key::Enter);
+    assert!(headless.screen().contains("0000-cover-letter.patch Count the changes"));
+    let text = std::fs::read_to_string(&cover_letter).unwrap();
+    assert_eq!(text, "Subject: [PATCH 0/1] Count the changes\n\nWhat they are about.\n");
+
+    headless.key(Key::Char('s'));
+    assert_eq!(headless.mode_kind(), ModeKind::Confirm);
+    headless.key(Key::Char('y'));
+    assert_eq!(headless.mode_kind(), ModeKind::Command);
+    let sent = format!("run_command send '{}' '{}'", cover_letter.display(), patch.display());
+    assert!(backend.calls().contains(&sent));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn patch_series_shows_a_patch_and_goes_back_to_the_list() {
+    let directory = std::env::temp_dir().join(format!("gituse-test-patch-view-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let patch = directory.join("0001-change.patch");
+    let text = "From 0123456789abcdef Mon Sep 17 00:00:00 2001\nSubject: [PATCH] change\n\n---\n\
+                diff --git a/file b/file\n--- a/file\n+++ b/file\n@@ -1 +1 @@\n-old\n+new\n";
+    std::fs::write(&patch, text).unwrap();
+
+    let backend = Arc::new(MockBackend::new(false));
+    backend.script("format_patches", Ok(vec![patch.to_string_lossy().into_owned()]));
+    let mut config = Config::default();
+    config.set("patch.directory", &directory.to_string_lossy());
+    let info = ModeChangeInfo::patch_series(ModeKind::Log, "a~..b".to_owned());
+    let mut headless = start(&backend, config, ModeKind::PatchSeries, info);
+
+    headless.key(Key::Enter);
+    assert_eq!(headless.mode_kind(), ModeKind::PatchSeries);
+    assert!(headless.screen().contains("From 0123456789abcdef"));
+
+    headless.key(Key::Left);
+    assert_eq!(headless.mode_kind(), ModeKind::PatchSeries);
+    assert!(headless.screen().contains("0001-change.patch"));
+
+    // [s] sends the series rather than going to the status
+    headless.key(Key::Char('s'));
+    assert_eq!(headless.mode_kind(), ModeKind::PatchSeries);
+    assert!(headless.screen().contains("set 'patch.send_command'"));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn header_shows_the_stopped_operation_until_the_backend_answers_without_it() {
+    let backend = Arc::new(MockBackend::new(false));
+    backend.set_operation(Some(Operation::Rebase));
+    let mut headless = start_log(&backend, Config::default());
+    assert!(headless.screen().contains("rebase in progress"));
+
+    backend.set_operation(None);
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Char('f'));
+    assert!(!headless.screen().contains("rebase in progress"));
+}
+
+#[test]
+fn read_only_disables_the_keys_changing_the_repository() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("read_only", "true");
+    let mut headless = start_log(&backend, config);
+
+    headless.key(Key::Char('f'));
+    assert!(!backend.calls().contains(&"fetch".to_owned()));
+
+    let backend = Arc::new(MockBackend::new(false));
+    let mut headless = start_log(&backend, Config::default());
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Char('f'));
+    assert!(backend.calls().contains(&"fetch".to_owned()));
+}
+
+#[test]
+fn bare_repositories_have_no_status() {
+    let backend = Arc::new(MockBackend::new(true));
+    let mut headless = start_log(&backend, Config::default());
+    headless.key(Key::Char('s'));
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+}
+
+#[test]
+fn status_grouped_by_directory_collapses_and_discards_a_directory() {
+    let backend = Arc::new(MockBackend::new(false));
+    let entries = ["top.txt", "src/b.rs", "src/a.rs"].iter().map(|n| RevisionEntry::new(n.to_string(), FileStatus::Modified));
+    let info = StatusInfo { header: "## main".to_owned(), entries: entries.collect(), operation: None };
+    backend.script("status", Ok(info));
+    let mut config = Config::default();
+    config.set("confirm.discard", "false");
+    let mut headless = start(&backend, config, ModeKind::Status, ModeChangeInfo::new(ModeKind::Status));
+    headless.resize((80, 12));
+
+    headless.key(Key::Char('g'));
+    let screen = headless.screen();
+    assert!(screen.contains("  - src/ (2)\n    [ modified] a.rs\n    [ modified] b.rs\n  [ modified] top.txt"));
+
+    headless.key(Key::Enter);
+    let screen = headless.screen();
+    assert!(screen.contains("  + src/ (2)\n  [ modified] top.txt"));
+
+    backend.script("status", Ok(StatusInfo { header: String::new(), entries: Vec::new(), operation: None }));
+    headless.key(Key::Char('D'));
+    assert!(backend.calls().contains(&"discard src/b.rs,src/a.rs".to_owned()));
+}
+
+#[test]
+fn status_commit_prompt_tells_when_only_the_staged_changes_go_in() {
+    let backend = Arc::new(MockBackend::new(false));
+    let entries = vec![RevisionEntry::new("a.txt".to_owned(), FileStatus::Modified)];
+    backend.script("status", Ok(StatusInfo { header: "## main".to_owned(), entries, operation: None }));
+    let mut headless = start(&backend, Config::default(), ModeKind::Status, ModeChangeInfo::new(ModeKind::Status));
+
+    backend.script("has_staged_changes", Ok(true));
+    headless.key(Key::Char('c'));
+    assert_eq!(headless.mode_kind(), ModeKind::MessageInput);
+    assert!(headless.screen().contains("committing the staged changes only"));
+}
+
+#[test]
+fn status_leaves_out_the_excluded_paths() {
+    let backend = Arc::new(MockBackend::new(false));
+    let entries = vec![RevisionEntry::new("a.txt".to_owned(), FileStatus::Modified)];
+    backend.script("status", Ok(StatusInfo { header: "## main".to_owned(), entries, operation: None }));
+    let mut config = Config::default();
+    config.set("status.exclude", "target/, node_modules");
+    config.set("confirm.clean", "false");
+    let mut headless = start(&backend, config, ModeKind::Status, ModeChangeInfo::new(ModeKind::Status));
+    assert_eq!(backend.calls(), ["status target/,node_modules"]);
+
+    // discarding all the changes leaves them alone too
+    headless.key(Key::Char('D'));
+    assert!(backend.calls().contains(&"discard  excluding target/,node_modules".to_owned()));
+}
+
+#[test]
+fn secret_prompts_are_masked_and_report_being_cancelled() {
+    static CANCELLED: AtomicBool = AtomicBool::new(false);
+    let backend = Arc::new(MockBackend::new(false));
+    let info = ModeChangeInfo::message_input(ModeKind::Log, false, "", |_, _| ())
+        .with_prompt("Password for 'https://alice@example.com':", true)
+        .with_on_cancel(|_| CANCELLED.store(true, Ordering::SeqCst));
+    let mut headless = start(&backend, Config::default(), ModeKind::MessageInput, info);
+
+    headless.type_text("hunter2");
+    let screen = headless.screen();
+    assert!(screen.contains("Password for 'https://alice@example.com':\n*******"));
+    assert!(!screen.contains("hunter2"));
+
+    headless.key(Key::Esc);
+    assert!(CANCELLED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn custom_actions_run_with_the_hovered_entry() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("action.log.K", "review {hash}");
+    let mut headless = start_log(&backend, config);
+
+    backend.script("run_command", Ok(vec!["reviewed".to_owned()]));
+    headless.keys(&[Key::Down, Key::Char('K')]);
+    assert_eq!(headless.mode_kind(), ModeKind::Command);
+    assert!(backend.calls().iter().any(|c| c.starts_with("run_command review ") && c.contains("0000001")));
+    assert!(headless.screen().contains("reviewed"));
+}
+
+#[test]
+fn custom_actions_leave_the_builtin_keys_and_read_only_alone() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("action.log.f", "review {hash}");
+    let mut headless = start_log(&backend, config);
+    backend.script("log", Ok((0usize, log_entries(0..LOG_PAGE_LEN))));
+    headless.key(Key::Char('f'));
+    assert!(backend.calls().contains(&"fetch".to_owned()));
+    assert!(!backend.calls().iter().any(|c| c.starts_with("run_command")));
+
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set("action.log.K", "review {hash}");
+    config.set("read_only", "true");
+    let mut headless = start_log(&backend, config);
+    headless.key(Key::Char('K'));
+    assert_eq!(headless.mode_kind(), ModeKind::Log);
+    assert!(!backend.calls().iter().any(|c| c.starts_with("run_command")));
+}
+
+#[test]
+fn setup_sets_the_missing_identity_globally() {
+    let backend = Arc::new(MockBackend::new(false));
+    let info = |user_name: Option<&str>| SetupInfo {
+        git_version: "2.43.0".to_owned(),
+        user_name: user_name.map(str::to_owned),
+        user_email: Some("jane@example.org".to_owned()),
+    };
+    backend.script("setup_info", Ok(info(None)));
+    let mut headless = start(&backend, Config::default(), ModeKind::Setup, ModeChangeInfo::new(ModeKind::Log));
+    let screen = headless.screen();
+    assert!(screen.contains("git 2.43.0"));
+    assert!(screen.contains("user.name is not set"));
+    assert!(!screen.contains("all set"));
+
+    backend.script("setup_info", Ok(info(Some("Jane Doe"))));
+    headless.keys(&[Key::Down, Key::Enter]);
+    headless.type_text("Jane Doe");
+    headless.key(Key::Enter);
+    assert!(backend.calls().contains(&"set_config global user.name Jane Doe".to_owned()));
+    let screen = headless.screen();
+    assert!(screen.contains("user.name Jane Doe"));
+    assert!(screen.contains("all set"));
+}
+
+#[test]
+fn header_strings_are_replaced_from_the_locale() {
+    let backend = Arc::new(MockBackend::new(false));
+    let mut config = Config::default();
+    config.set_message("log", "Verlauf");
+    config.set_message("status", "Status");
+    config.set_message("range", "Bereich");
+    config.set_message("first parent", "");
+    config.set_message(
+        "[c]checkout [enter]details [f]fetch [p]pull [P]push [F]force push [g]gerrit [r]reset [R]reset to remote [H]deepen [U]unshallow [x]fixup [X]autosquash [B]rebase onto [O]rebase range onto [J]jump to date",
+        "[c]auschecken [enter]Details",
+    );
+    let mut headless = start_log(&backend, config);
+    let screen = headless.screen();
+    assert!(screen.contains(" Verlauf "));
+    assert!(screen.contains("[s]Status [l]Verlauf [b]branches"));
+    assert!(screen.contains("[c]auschecken [enter]Details"));
+    assert!(screen.contains("[v]Bereich [e]merges"));
+}
@@ -0,0 +1,61 @@
+use gituse::ui::screen::Screen;
+
+const SIZE: (u16, u16) = (10, 3);
+
+fn update(screen: &mut Screen, frame: &str) -> String {
+    let mut out = Vec::new();
+    screen.update(frame.as_bytes(), SIZE, &mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn unchanged_frames_write_nothing() {
+    let mut screen = Screen::default();
+    let frame = "\x1b[0;0H\x1b[38;5;3mhello\x1b[0K\x1b[1Eworld\x1b[0J";
+    let out = update(&mut screen, frame);
+    assert!(out.starts_with("\x1b[0m\x1b[H\x1b[2J"));
+    assert!(out.contains("hello") && out.contains("world"));
+    assert_eq!(screen.text(), "hello\nworld");
+
+    assert_eq!(update(&mut screen, frame), "");
+}
+
+#[test]
+fn only_changed_cells_are_written() {
+    let mut screen = Screen::default();
+    update(&mut screen, "\x1b[0;0Hhello\x1b[1Eworld\x1b[0J");
+
+    assert_eq!(update(&mut screen, "\x1b[0;0Hhello\x1b[1Eworlds\x1b[0J"), "\x1b[2;6Hs");
+    // close changes on a line are written together instead of moving the cursor between them
+    assert_eq!(update(&mut screen, "\x1b[0;0Hjellu\x1b[1Eworlds\x1b[0J"), "\x1b[1;1Hjellu");
+}
+
+#[test]
+fn removed_text_is_cleared_to_the_end_of_the_line() {
+    let mut screen = Screen::default();
+    update(&mut screen, "\x1b[0;0Hhello world\x1b[0J");
+    assert_eq!(screen.text(), "hello worl\nd");
+
+    assert_eq!(update(&mut screen, "\x1b[0;0Hhe\x1b[0J"), "\x1b[1;3H\x1b[0K\x1b[2;1H\x1b[0K");
+    assert_eq!(screen.text(), "he");
+}
+
+#[test]
+fn colors_and_wide_characters_are_kept() {
+    let mut screen = Screen::default();
+    update(&mut screen, "\x1b[0;0H\x1b[48;5;5m日本\x1b[0K\x1b[0;49m\x1b[1Eok");
+    assert_eq!(screen.text(), "日本\nok");
+
+    // a color change alone is sent again
+    let out = update(&mut screen, "\x1b[0;0H\x1b[48;5;2m日本\x1b[0K\x1b[0;49m\x1b[1Eok");
+    assert!(out.contains("\x1b[0;48;5;2m日本"));
+}
+
+#[test]
+fn invalidated_screens_are_redrawn() {
+    let mut screen = Screen::default();
+    let frame = "\x1b[0;0Hhello";
+    update(&mut screen, frame);
+    screen.invalidate();
+    assert_eq!(update(&mut screen, frame), "\x1b[0m\x1b[H\x1b[2J\x1b[1;1Hhello");
+}
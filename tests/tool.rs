@@ -0,0 +1,24 @@
+use gituse::tool::{issue_references, Pattern};
+
+fn references<'a>(text: &'a str, patterns: &[&str]) -> Vec<&'a str> {
+    let patterns: Vec<_> = patterns.iter().map(|p| Pattern::new(p)).collect();
+    issue_references(text, &patterns).into_iter().map(|(begin, end)| &text[begin..end]).collect()
+}
+
+#[test]
+fn issue_patterns_find_the_references() {
+    let text = "fix #12 and JIRA-345, see abc#6 and #x";
+    assert_eq!(references(text, &[r"#\d+"]), ["#12"]);
+    assert_eq!(references(text, &[r"#\d+", r"[A-Z]+-\d+"]), ["#12", "JIRA-345"]);
+    assert_eq!(references("GH-abc GH-", &[r"GH-\w+"]), ["GH-abc"]);
+    assert_eq!(references("PR 7, PR12", &[r"PR\s?\d+"]), ["PR 7", "PR12"]);
+}
+
+#[test]
+fn issue_patterns_back_off_and_take_the_rest_literally() {
+    // the repetition gives back what the end of the pattern needs
+    assert_eq!(references("v1.2.3.", &[r"v.*\d"]), ["v1.2.3"]);
+    assert_eq!(references("a[1] [^", &[r"[^ ]+\[\d\]", "[^"]), ["a[1]", "[^"]);
+    assert_eq!(references("x+ y", &["+"]), Vec::<&str>::new());
+    assert_eq!(references("x* *", &["*"]), ["*"]);
+}